@@ -0,0 +1,273 @@
+#![no_std]
+
+//! Event schema shared by the `group`, `semaphore` and `multisig` contracts.
+//!
+//! Every event is a typed struct published with the topics
+//! `(name, SCHEMA_VERSION, group_id)` and the struct itself as data. Tree
+//! mutations always carry the leaf index and the new root, so an indexer can
+//! rebuild a group tree from events alone. Events about the contract as a
+//! whole have no group and are published with `(name, SCHEMA_VERSION)`.
+
+use soroban_sdk::{contracttype, Address, Bytes, BytesN, Env, IntoVal, Symbol, Val, Vec};
+
+/// Version of the event layout, published as the second topic of every event
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// An event scoped to a single group
+pub trait GroupEvent: Clone + IntoVal<Env, Val> {
+    /// Event name, published as the first topic
+    const NAME: &'static str;
+
+    /// Group the event belongs to, published as the third topic
+    fn group_id(&self) -> u32;
+
+    /// Publish the event from the current contract
+    fn publish(&self, env: &Env) {
+        env.events().publish(
+            (Symbol::new(env, Self::NAME), SCHEMA_VERSION, self.group_id()),
+            self.clone(),
+        );
+    }
+}
+
+/// An event about the contract as a whole
+pub trait ContractEvent: Clone + IntoVal<Env, Val> {
+    /// Event name, published as the first topic
+    const NAME: &'static str;
+
+    /// Publish the event from the current contract
+    fn publish(&self, env: &Env) {
+        env.events()
+            .publish((Symbol::new(env, Self::NAME), SCHEMA_VERSION), self.clone());
+    }
+}
+
+macro_rules! contract_event {
+    ($event:ident, $name:literal) => {
+        impl ContractEvent for $event {
+            const NAME: &'static str = $name;
+        }
+    };
+}
+
+macro_rules! group_event {
+    ($event:ident, $name:literal) => {
+        impl GroupEvent for $event {
+            const NAME: &'static str = $name;
+
+            fn group_id(&self) -> u32 {
+                self.group_id
+            }
+        }
+    };
+}
+
+/// A new group was created with an empty tree
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupCreated {
+    pub group_id: u32,
+    pub admin: Address,
+    pub depth: u32,
+    pub root: Bytes,
+}
+group_event!(GroupCreated, "group_created");
+
+/// The group about to be created uses a tree other than the default
+/// keccak256 incremental one, so `GroupCreated` right after carries the
+/// root of that tree
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupTreeSelected {
+    pub group_id: u32,
+    pub tree: Symbol,
+    /// Hash function combining the tree's nodes
+    pub hash: Symbol,
+}
+group_event!(GroupTreeSelected, "group_tree_selected");
+
+/// An admin transfer was started and waits for the new admin to accept it
+/// until `expiration_ledger`
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupAdminPending {
+    pub group_id: u32,
+    pub old_admin: Address,
+    pub new_admin: Address,
+    pub expiration_ledger: u32,
+}
+group_event!(GroupAdminPending, "group_admin_pending");
+
+/// The admin withdrew the transfer to `pending_admin`
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupAdminTransferCancelled {
+    pub group_id: u32,
+    pub admin: Address,
+    pub pending_admin: Address,
+}
+group_event!(GroupAdminTransferCancelled, "group_admin_transfer_cancelled");
+
+/// The group has no admin anymore and its members can no longer change
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupAdminRenounced {
+    pub group_id: u32,
+    pub old_admin: Address,
+}
+group_event!(GroupAdminRenounced, "group_admin_renounced");
+
+/// The group admin changed; `old_admin` is `None` when the group is created
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupAdminUpdated {
+    pub group_id: u32,
+    pub old_admin: Option<Address>,
+    pub new_admin: Address,
+}
+group_event!(GroupAdminUpdated, "group_admin_updated");
+
+/// A leaf was appended to the group tree
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemberAdded {
+    pub group_id: u32,
+    pub identity_commitment: Bytes,
+    pub index: u32,
+    pub root: Bytes,
+}
+group_event!(MemberAdded, "member_added");
+
+/// Consecutive leaves were appended to the group tree, the first one at
+/// `start_index`; `root` is the root after the whole batch
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MembersAdded {
+    pub group_id: u32,
+    pub identity_commitments: Vec<Bytes>,
+    pub start_index: u32,
+    pub root: Bytes,
+}
+group_event!(MembersAdded, "members_added");
+
+/// The leaf at `index` was replaced by a new identity commitment
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemberUpdated {
+    pub group_id: u32,
+    pub old_identity_commitment: Bytes,
+    pub new_identity_commitment: Bytes,
+    pub index: u32,
+    pub root: Bytes,
+}
+group_event!(MemberUpdated, "member_updated");
+
+/// The leaf at `index` was reset to the empty leaf
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemberRemoved {
+    pub group_id: u32,
+    pub identity_commitment: Bytes,
+    pub index: u32,
+    pub root: Bytes,
+}
+group_event!(MemberRemoved, "member_removed");
+
+/// Leaves were replaced in one batch: `new_identity_commitments[i]` took
+/// the place of `old_identity_commitments[i]` at `indexes[i]`
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MembersUpdated {
+    pub group_id: u32,
+    pub old_identity_commitments: Vec<Bytes>,
+    pub new_identity_commitments: Vec<Bytes>,
+    pub indexes: Vec<u32>,
+    pub root: Bytes,
+}
+group_event!(MembersUpdated, "members_updated");
+
+/// Leaves were reset to the empty leaf in one batch, `identity_commitments[i]`
+/// at `indexes[i]`
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MembersRemoved {
+    pub group_id: u32,
+    pub identity_commitments: Vec<Bytes>,
+    pub indexes: Vec<u32>,
+    pub root: Bytes,
+}
+group_event!(MembersRemoved, "members_removed");
+
+/// A Semaphore proof was accepted and its nullifier spent
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProofValidated {
+    pub group_id: u32,
+    pub merkle_tree_depth: u32,
+    pub merkle_tree_root: Bytes,
+    pub nullifier: Bytes,
+    pub message: Bytes,
+    pub scope: Bytes,
+}
+group_event!(ProofValidated, "proof_validated");
+
+/// The group was made read-only by its admin
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupArchived {
+    pub group_id: u32,
+}
+group_event!(GroupArchived, "group_archived");
+
+/// The last entries of an archived group were removed
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupDeleted {
+    pub group_id: u32,
+}
+group_event!(GroupDeleted, "group_deleted");
+
+/// The owner paused the contract at ledger time `timestamp`
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Paused {
+    pub by: Address,
+    pub timestamp: u64,
+}
+contract_event!(Paused, "paused");
+
+/// The owner unpaused the contract at ledger time `timestamp`
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unpaused {
+    pub by: Address,
+    pub timestamp: u64,
+}
+contract_event!(Unpaused, "unpaused");
+
+/// The multisig account now needs `new_threshold` signatures
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThresholdChanged {
+    pub old_threshold: u32,
+    pub new_threshold: u32,
+}
+contract_event!(ThresholdChanged, "threshold_changed");
+
+/// An ed25519 key was added to the multisig account's signers
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignerAdded {
+    pub public_key: BytesN<32>,
+}
+contract_event!(SignerAdded, "signer_added");
+
+/// An ed25519 key was removed from the multisig account's signers
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignerRemoved {
+    pub public_key: BytesN<32>,
+}
+contract_event!(SignerRemoved, "signer_removed");
+
+mod test;
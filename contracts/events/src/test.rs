@@ -0,0 +1,61 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::{
+    contract,
+    testutils::Events,
+    vec, TryFromVal,
+};
+
+#[contract]
+struct EventsTestContract;
+
+#[test]
+fn test_group_event_layout() {
+    let env = Env::default();
+    let contract_id = env.register(EventsTestContract, ());
+    let event = MemberAdded {
+        group_id: 7,
+        identity_commitment: Bytes::from_slice(&env, &[1u8; 32]),
+        index: 3,
+        root: Bytes::from_slice(&env, &[2u8; 32]),
+    };
+    env.as_contract(&contract_id, || event.publish(&env));
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                contract_id.clone(),
+                (Symbol::new(&env, "member_added"), SCHEMA_VERSION, 7_u32).into_val(&env),
+                event.clone().into_val(&env)
+            ),
+        ]
+    );
+
+    // indexers decode the data back into the same struct
+    let (_, _, data) = env.events().all().get(0).unwrap();
+    assert_eq!(MemberAdded::try_from_val(&env, &data).unwrap(), event);
+}
+
+#[test]
+fn test_contract_event_layout() {
+    let env = Env::default();
+    let contract_id = env.register(EventsTestContract, ());
+    let event = Paused {
+        by: contract_id.clone(),
+        timestamp: 12,
+    };
+    env.as_contract(&contract_id, || event.publish(&env));
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                contract_id.clone(),
+                (Symbol::new(&env, "paused"), SCHEMA_VERSION).into_val(&env),
+                event.into_val(&env)
+            ),
+        ]
+    );
+}
@@ -0,0 +1,180 @@
+//! Random membership changes against one group. After every call the member
+//! count must equal the number of members whose proof verifies, and members
+//! that are gone must not verify with any proof they held before. Once the
+//! group is archived its root must not change, and once it is deleted its
+//! tree must be gone.
+
+#![no_main]
+
+use std::collections::BTreeMap;
+
+use arbitrary::Arbitrary;
+use group::{datatypes::Error, proof::MemberProof, SemaphoreGroupContractClient};
+use group_fuzz::{defined, new_env, setup, Commitment, GroupConfig, MAX_OPS};
+use libfuzzer_sys::fuzz_target;
+use soroban_sdk::{Bytes, Env, Vec};
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    AddMember(Commitment),
+    AddMembers(std::vec::Vec<Commitment>),
+    UpdateMember(Commitment, Commitment),
+    UpdateMembers(std::vec::Vec<(Commitment, Commitment)>),
+    RemoveMember(Commitment),
+    RemoveMembers(std::vec::Vec<Commitment>),
+    GetProof(u8),
+    ArchiveGroup,
+    DeleteGroup(std::vec::Vec<Commitment>),
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    config: GroupConfig,
+    ops: std::vec::Vec<Op>,
+}
+
+struct Group<'a> {
+    env: &'a Env,
+    client: SemaphoreGroupContractClient<'a>,
+    group_id: u32,
+    /// Every commitment the input used
+    known: std::vec::Vec<Bytes>,
+    /// Last proof of each known commitment while it was a member
+    proofs: BTreeMap<usize, MemberProof>,
+    /// Root of the group when it was archived
+    archived_root: Option<Bytes>,
+    deleted: bool,
+}
+
+impl Group<'_> {
+    fn commitments(&mut self, commitments: &[Commitment]) -> Vec<Bytes> {
+        let mut resolved = Vec::new(self.env);
+        for commitment in commitments {
+            resolved.push_back(commitment.resolve(self.env, &mut self.known));
+        }
+        resolved
+    }
+
+    fn apply(&mut self, op: &Op) {
+        let (env, group_id) = (self.env, self.group_id);
+        let client = &self.client;
+        match op {
+            Op::AddMember(commitment) => {
+                let commitment = commitment.resolve(env, &mut self.known);
+                defined(client.try_add_member(&group_id, &commitment));
+            }
+            Op::AddMembers(commitments) => {
+                let commitments = self.commitments(commitments);
+                defined(self.client.try_add_members(&group_id, &commitments));
+            }
+            Op::UpdateMember(old, new) => {
+                let old = old.resolve(env, &mut self.known);
+                let new = new.resolve(env, &mut self.known);
+                defined(client.try_update_member(&group_id, &old, &new));
+            }
+            Op::UpdateMembers(pairs) => {
+                let mut updates = Vec::new(env);
+                for (old, new) in pairs {
+                    let old = old.resolve(env, &mut self.known);
+                    let new = new.resolve(env, &mut self.known);
+                    updates.push_back((old, new));
+                }
+                defined(client.try_update_members(&group_id, &updates));
+            }
+            Op::RemoveMember(commitment) => {
+                let commitment = commitment.resolve(env, &mut self.known);
+                defined(client.try_remove_member(&group_id, &commitment));
+            }
+            Op::RemoveMembers(commitments) => {
+                let commitments = self.commitments(commitments);
+                defined(self.client.try_remove_members(&group_id, &commitments));
+            }
+            Op::GetProof(index) => {
+                defined(client.try_get_proof(&group_id, &(*index as u32)));
+            }
+            Op::ArchiveGroup => {
+                let root = defined(client.try_get_merkle_root(&group_id));
+                if defined(client.try_archive_group(&group_id)).is_some() {
+                    self.archived_root = root;
+                }
+            }
+            Op::DeleteGroup(commitments) => {
+                let commitments = self.commitments(commitments);
+                if defined(self.client.try_delete_group(&group_id, &commitments)) == Some(0) {
+                    self.deleted = true;
+                }
+            }
+        }
+    }
+
+    fn check(&mut self) {
+        let (client, group_id) = (&self.client, &self.group_id);
+        if self.deleted {
+            let gone = Some(Ok(Error::GroupDoesNotExist));
+            assert_eq!(
+                client.try_get_merkle_root(group_id).err(),
+                gone,
+                "deleted group has a root"
+            );
+            assert_eq!(
+                client.try_get_proof(group_id, &0).err(),
+                gone,
+                "deleted group has a proof"
+            );
+            return;
+        }
+        let root = defined(client.try_get_merkle_root(group_id)).expect("group without a root");
+        if let Some(archived_root) = &self.archived_root {
+            // proofs are rejected from here on, only the root is left to check
+            assert_eq!(&root, archived_root, "archived group changed");
+            return;
+        }
+        let count = defined(client.try_get_member_count(group_id)).unwrap();
+        let mut live = 0;
+        for (i, commitment) in self.known.iter().enumerate() {
+            if defined(client.try_is_member(group_id, commitment)).unwrap() {
+                let proof = defined(client.try_get_member_proof(group_id, commitment))
+                    .expect("member without a proof");
+                assert!(
+                    defined(client.try_verify_member_proof(group_id, commitment, &proof)).unwrap(),
+                    "member proof does not verify"
+                );
+                self.proofs.insert(i, proof);
+                live += 1;
+            } else {
+                assert_eq!(
+                    client.try_get_member_proof(group_id, commitment),
+                    Err(Ok(Error::MemberDoesNotExist))
+                );
+                if let Some(proof) = self.proofs.get(&i) {
+                    assert!(
+                        !defined(client.try_verify_member_proof(group_id, commitment, proof))
+                            .unwrap(),
+                        "removed member verifies"
+                    );
+                }
+            }
+        }
+        assert_eq!(count, live, "member count differs from live leaves");
+    }
+}
+
+fuzz_target!(|input: Input| {
+    let env = new_env();
+    let Some((client, group_id)) = setup(&env, input.config) else {
+        return;
+    };
+    let mut group = Group {
+        env: &env,
+        client,
+        group_id,
+        known: std::vec::Vec::new(),
+        proofs: BTreeMap::new(),
+        archived_root: None,
+        deleted: false,
+    };
+    for op in input.ops.iter().take(MAX_OPS) {
+        group.apply(op);
+        group.check();
+    }
+});
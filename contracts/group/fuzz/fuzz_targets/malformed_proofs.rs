@@ -0,0 +1,188 @@
+//! Proofs taken from a group and then mangled: truncated, extended past any
+//! depth, with sides flipped or siblings and keys replaced. Whatever the
+//! shape, a membership proof may only verify for a current member and a
+//! non-membership proof only for a commitment outside the group.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use group::{
+    imt::hash_to_g1,
+    proof::{Branch, MemberProof, NonMembershipProof, Proof, SparseProof},
+};
+use group_fuzz::{defined, new_env, setup, Commitment, GroupConfig, MAX_OPS};
+use libfuzzer_sys::fuzz_target;
+use soroban_sdk::{Bytes, BytesN, Env, Vec};
+
+#[derive(Arbitrary, Debug)]
+enum Mutation {
+    /// Keep this many branches or siblings
+    Truncate(u8),
+    /// Repeat the last branch or sibling until there are this many
+    Extend(u8),
+    Push(bool, std::vec::Vec<u8>),
+    Insert(u8, bool, std::vec::Vec<u8>),
+    Remove(u8),
+    /// Move a branch to the other side
+    Flip(u8),
+    Replace(u8, std::vec::Vec<u8>),
+    Level(u32),
+    Empty([u8; 32]),
+    LowKey([u8; 32]),
+    NextKey([u8; 32]),
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    config: GroupConfig,
+    members: std::vec::Vec<Commitment>,
+    removed: std::vec::Vec<Commitment>,
+    /// Whose proof is mangled
+    source: Commitment,
+    /// Who the mangled proof is verified for
+    subject: Commitment,
+    mutations: std::vec::Vec<Mutation>,
+}
+
+fn branch(env: &Env, left: bool, sibling: &[u8]) -> Branch {
+    let sibling = Bytes::from_slice(env, sibling);
+    if left {
+        Branch::Left(sibling)
+    } else {
+        Branch::Right(sibling)
+    }
+}
+
+fn mutate_proof(env: &Env, proof: &mut Proof, mutation: &Mutation) {
+    let branches = &mut proof.0;
+    let at = |i: &u8| *i as u32 % (branches.len() + 1);
+    match mutation {
+        Mutation::Truncate(len) => *branches = branches.slice(..(*len as u32).min(branches.len())),
+        Mutation::Extend(len) => {
+            let last = branches.last().unwrap_or(Branch::Right(Bytes::new(env)));
+            while branches.len() < *len as u32 {
+                branches.push_back(last.clone());
+            }
+        }
+        Mutation::Push(left, sibling) => branches.push_back(branch(env, *left, sibling)),
+        Mutation::Insert(i, left, sibling) => branches.insert(at(i), branch(env, *left, sibling)),
+        Mutation::Remove(i) if at(i) < branches.len() => {
+            branches.remove(at(i));
+        }
+        Mutation::Flip(i) if at(i) < branches.len() => {
+            let flipped = match branches.get(at(i)).unwrap() {
+                Branch::Left(sibling) => Branch::Right(sibling),
+                Branch::Right(sibling) => Branch::Left(sibling),
+            };
+            branches.set(at(i), flipped);
+        }
+        Mutation::Replace(i, sibling) if at(i) < branches.len() => {
+            let left = matches!(branches.get(at(i)).unwrap(), Branch::Left(_));
+            branches.set(at(i), branch(env, left, sibling));
+        }
+        _ => {}
+    }
+}
+
+fn mutate_sparse_proof(env: &Env, proof: &mut SparseProof, mutation: &Mutation) {
+    let siblings = &mut proof.siblings;
+    let at = |i: &u8| *i as u32 % (siblings.len() + 1);
+    match mutation {
+        Mutation::Truncate(len) => *siblings = siblings.slice(..(*len as u32).min(siblings.len())),
+        Mutation::Extend(len) => {
+            let last = siblings.last().unwrap_or(Bytes::new(env));
+            while siblings.len() < *len as u32 {
+                siblings.push_back(last.clone());
+            }
+        }
+        Mutation::Push(_, sibling) => siblings.push_back(Bytes::from_slice(env, sibling)),
+        Mutation::Insert(i, _, sibling) => siblings.insert(at(i), Bytes::from_slice(env, sibling)),
+        Mutation::Remove(i) if at(i) < siblings.len() => {
+            siblings.remove(at(i));
+        }
+        Mutation::Replace(i, sibling) if at(i) < siblings.len() => {
+            siblings.set(at(i), Bytes::from_slice(env, sibling));
+        }
+        Mutation::Level(level) => proof.level = *level,
+        Mutation::Empty(empty) => proof.empty = BytesN::from_array(env, empty),
+        _ => {}
+    }
+}
+
+fn mutate(env: &Env, proof: &mut MemberProof, mutation: &Mutation) {
+    match proof {
+        MemberProof::Merkle(proof) => mutate_proof(env, proof, mutation),
+        MemberProof::Sparse(proof) => mutate_sparse_proof(env, proof, mutation),
+    }
+}
+
+fn mutate_non_membership(env: &Env, proof: &mut NonMembershipProof, mutation: &Mutation) {
+    match mutation {
+        Mutation::LowKey(key) => proof.low_key = BytesN::from_array(env, key),
+        Mutation::NextKey(key) => proof.next_key = BytesN::from_array(env, key),
+        _ => mutate_proof(env, &mut proof.proof, mutation),
+    }
+}
+
+fuzz_target!(|input: Input| {
+    let env = new_env();
+    let Some((client, group_id)) = setup(&env, input.config) else {
+        return;
+    };
+    let mut known = std::vec::Vec::new();
+    let mut members = Vec::new(&env);
+    for member in input.members.iter().take(MAX_OPS) {
+        members.push_back(member.resolve(&env, &mut known));
+    }
+    defined(client.try_add_members(&group_id, &members));
+    for member in input.removed.iter().take(MAX_OPS) {
+        let member = member.resolve(&env, &mut known);
+        defined(client.try_remove_member(&group_id, &member));
+    }
+    let source = input.source.resolve(&env, &mut known);
+    let subject = input.subject.resolve(&env, &mut known);
+    let is_member = defined(client.try_is_member(&group_id, &subject)).unwrap();
+
+    // membership, through whichever proof the tree gives
+    if let Some(mut proof) = defined(client.try_get_member_proof(&group_id, &source)) {
+        for mutation in &input.mutations {
+            mutate(&env, &mut proof, mutation);
+        }
+        if let MemberProof::Merkle(proof) = &proof {
+            // any length is fine, too long ones have no index
+            proof.leaf_index();
+        }
+        let verified = defined(client.try_verify_member_proof(&group_id, &subject, &proof));
+        assert!(verified != Some(true) || is_member, "non-member verifies");
+
+        match proof {
+            MemberProof::Merkle(proof) => {
+                let leaf = hash_to_g1(&env, subject.clone());
+                let verified = defined(client.try_verify_proof(&group_id, &leaf, &proof));
+                assert!(
+                    verified != Some(true) || is_member,
+                    "non-member leaf verifies"
+                );
+            }
+            MemberProof::Sparse(proof) => {
+                let verified = defined(client.try_verify_sparse_proof(&group_id, &subject, &proof));
+                assert!(
+                    verified != Some(true) || is_member,
+                    "non-member verifies sparse proof"
+                );
+            }
+        }
+    }
+
+    // non-membership, for indexed groups
+    if let Some(mut proof) = defined(client.try_prove_non_membership(&group_id, &source)) {
+        for mutation in &input.mutations {
+            mutate_non_membership(&env, &mut proof, mutation);
+        }
+        let verified = defined(client.try_verify_non_membership(&group_id, &subject, &proof));
+        assert!(
+            verified != Some(true) || !is_member,
+            "member verifies non-membership"
+        );
+    }
+});
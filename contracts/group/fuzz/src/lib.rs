@@ -0,0 +1,111 @@
+//! Shared setup of the `group` fuzz targets.
+//!
+//! The targets drive `SemaphoreGroupContractClient` in a test `Env` and
+//! panic when an invariant breaks. Run one with
+//!
+//! ```text
+//! cargo +nightly fuzz run group_ops
+//! ```
+//!
+//! from `contracts/group`.
+
+use arbitrary::Arbitrary;
+use group::{
+    datatypes::{Error, GroupMetadata, HashFunction, TreeKind},
+    SemaphoreGroupContract, SemaphoreGroupContractClient,
+};
+use soroban_sdk::{
+    testutils::{Address as _, EnvTestConfig},
+    Address, Bytes, Env, InvokeError,
+};
+
+/// Calls per input, so inputs cannot grow a group without bound
+pub const MAX_OPS: usize = 32;
+
+/// Trees of at most this depth, so groups fill up within an input
+pub const MAX_DEPTH: u32 = 6;
+
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub enum Tree {
+    Incremental,
+    Indexed,
+    Sparse,
+}
+
+/// Settings of the group a target runs against
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub struct GroupConfig {
+    pub tree: Tree,
+    pub sha256: bool,
+    /// Reduced modulo `MAX_DEPTH + 1`, so depth 0 is rejected
+    pub depth: u32,
+}
+
+/// An identity commitment, either one seen earlier in the input or new bytes
+#[derive(Arbitrary, Clone, Debug)]
+pub enum Commitment {
+    Known(u8),
+    New(Vec<u8>),
+}
+
+impl Commitment {
+    /// The commitment's bytes, recording new ones in `known`
+    pub fn resolve(&self, env: &Env, known: &mut Vec<Bytes>) -> Bytes {
+        match self {
+            Self::Known(_) if known.is_empty() => Bytes::new(env),
+            Self::Known(i) => known[*i as usize % known.len()].clone(),
+            Self::New(bytes) => {
+                let commitment = Bytes::from_slice(env, bytes);
+                if !known.contains(&commitment) {
+                    known.push(commitment.clone());
+                }
+                commitment
+            }
+        }
+    }
+}
+
+/// A test `Env` that writes no snapshot when dropped
+pub fn new_env() -> Env {
+    Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    })
+}
+
+/// Register the contract and create a group, `None` if the config is
+/// rejected with an `Error`
+pub fn setup(env: &Env, config: GroupConfig) -> Option<(SemaphoreGroupContractClient<'_>, u32)> {
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(env),));
+    let client = SemaphoreGroupContractClient::new(env, &contract_id);
+    let metadata = GroupMetadata {
+        name: None,
+        description_uri: None,
+        depth: Some(config.depth % (MAX_DEPTH + 1)),
+        tree: match config.tree {
+            Tree::Incremental => TreeKind::Incremental,
+            Tree::Indexed => TreeKind::Indexed,
+            Tree::Sparse => TreeKind::Sparse,
+        },
+        hash: if config.sha256 {
+            HashFunction::Sha256
+        } else {
+            HashFunction::Keccak256
+        },
+    };
+    let group_id = defined(client.try_create_group(&Address::generate(env), &Some(metadata)))?;
+    Some((client, group_id))
+}
+
+/// The value of a call, or `None` if it failed with one of the contract's
+/// `Error`s. Any other failure, a host trap or a panic, breaks the invariant
+pub fn defined<T: core::fmt::Debug, C: core::fmt::Debug>(
+    result: Result<Result<T, C>, Result<Error, InvokeError>>,
+) -> Option<T> {
+    match result {
+        Ok(Ok(value)) => Some(value),
+        Err(Ok(_)) => None,
+        other => panic!("call failed outside of the contract's errors: {other:?}"),
+    }
+}
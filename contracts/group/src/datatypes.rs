@@ -3,6 +3,9 @@ use soroban_sdk::contracterror;
 use soroban_sdk::contracttype;
 use soroban_sdk::Address;
 use soroban_sdk::Bytes;
+use soroban_sdk::Env;
+use soroban_sdk::String;
+use soroban_sdk::Symbol;
 
 #[contracterror]
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -15,16 +18,47 @@ pub enum Error {
     MemberAlreadyExists = 5,
     MemberDoesNotExist = 6,
     InvalidIdentityCommitment = 7,
+    UnsupportedSchemaVersion = 8,
+    ContractPaused = 9,
+    CallerIsNotAGroupCreator = 10,
+    InvalidCreationFee = 11,
+    InvalidTreeDepth = 12,
+    GroupIsArchived = 13,
+    GroupIsNotArchived = 14,
+    NoPendingAdmin = 15,
+    AdminTransferExpired = 16,
+    InvalidExpirationLedger = 17,
+    GroupIsImmutable = 18,
+    NonMembershipNotSupported = 19,
+    UnsupportedTreeKind = 20,
+    GroupIsFull = 21,
+    InvalidProofLength = 22,
+    LeafIndexOutOfRange = 23,
 }
 
+/// Keys with a group id are persistent entries, see [`crate::storage`]; the
+/// others live in the contract instance
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
 pub enum DataKey {
-    Admin(u32),         // maps group_id -> admin address
-    PendingAdmin(u32),  // maps group_id -> pending admin address
-    Member(u32, Bytes), // maps (group_id, identity_commitment) -> Member
-    MemberCount(u32),   // maps group_id -> number of members
-    Group(u32),         // maps group_id -> Group
+    Admin(u32),             // maps group_id -> admin address
+    PendingAdmin(u32),      // maps group_id -> pending admin address
+    Member(u32, Bytes),     // maps (group_id, identity_commitment) -> Member
+    MemberCount(u32),       // maps group_id -> number of members
+    Group(u32),             // maps group_id -> Group
+    Owner,                  // contract owner, may upgrade the contract
+    SchemaVersion,          // version of the storage layout
+    Paused,                 // set while the owner has paused the contract
+    CreatorAllowlist,       // set while only allowlisted addresses may create groups
+    GroupCreator(Address),  // addresses on the group creation allowlist
+    CreationFee,            // CreationFee, groups are free when unset
+    GroupCounter,           // next group id to assign
+    Metadata(u32),          // maps group_id -> GroupMetadata
+    Status(u32),            // maps group_id -> GroupStatus, active when unset
+    PendingAdminExpiration(u32), // maps group_id -> last ledger the pending admin may accept
+    NextIndex(u32),         // maps group_id -> next free leaf, MemberCount when unset
+    SortedLeaves(u32),      // maps group_id -> SortedLeaves of an indexed group
+    SparseTree(u32),        // maps group_id -> SparseMerkleTree, instead of Group
 }
 
 #[contracttype]
@@ -41,3 +75,133 @@ pub struct Group {
     pub admin: Address,
     pub merkle_tree: MerkleTree,
 }
+
+/// Fee a new group's admin pays to create it
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreationFee {
+    pub token: Address,
+    pub amount: i128,
+    pub recipient: Address,
+}
+
+/// Optional settings for a new group
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupMetadata {
+    pub name: Option<String>,
+    pub description_uri: Option<String>,
+    /// Depth of the group tree, `DEFAULT_DEPTH` when `None`
+    pub depth: Option<u32>,
+    /// Kind of group tree; groups created without metadata are
+    /// `TreeKind::Incremental`
+    pub tree: TreeKind,
+    /// Hash function of the group tree; groups created without metadata use
+    /// `HashFunction::Keccak256`
+    pub hash: HashFunction,
+}
+
+/// Settings of groups created without metadata
+impl Default for GroupMetadata {
+    fn default() -> Self {
+        Self {
+            name: None,
+            description_uri: None,
+            depth: None,
+            tree: TreeKind::Incremental,
+            hash: HashFunction::Keccak256,
+        }
+    }
+}
+
+/// Everything a front end shows about a group, returned by `get_group`
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupInfo {
+    pub id: u32,
+    pub admin: Address,
+    pub name: Option<String>,
+    pub description_uri: Option<String>,
+    pub depth: u32,
+    pub root: Bytes,
+    /// Number of members
+    pub size: u32,
+    pub status: GroupStatus,
+    pub tree: TreeKind,
+    pub hash: HashFunction,
+}
+
+/// How members are laid out in the group tree
+#[contracttype]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TreeKind {
+    /// Leaves are identity commitments hashed to G1, in insertion order.
+    /// With `HashFunction::Poseidon` they are the commitments themselves,
+    /// as in Semaphore's own groups
+    Incremental,
+    /// Leaves link each commitment's key to the next larger one, so the
+    /// tree can also prove that a commitment is not a member
+    Indexed,
+    /// Members sit at the path given by their commitment's key, so removals
+    /// leave no holes. The depth setting does not apply
+    Sparse,
+}
+
+impl TreeKind {
+    /// Name published in `GroupTreeSelected`
+    pub fn name(&self, env: &Env) -> Symbol {
+        match self {
+            Self::Incremental => Symbol::new(env, "incremental"),
+            Self::Indexed => Symbol::new(env, "indexed"),
+            Self::Sparse => Symbol::new(env, "sparse"),
+        }
+    }
+
+    /// Kind published under `name`
+    pub fn from_name(env: &Env, name: &Symbol) -> Option<Self> {
+        [Self::Incremental, Self::Indexed, Self::Sparse]
+            .into_iter()
+            .find(|kind| kind.name(env) == *name)
+    }
+}
+
+/// Hash function a group tree combines its nodes with
+#[contracttype]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HashFunction {
+    Keccak256,
+    Sha256,
+    /// Poseidon over the BN254 scalar field, as the Semaphore circuits hash
+    /// their tree. Only incremental Poseidon groups can be proven in them
+    Poseidon,
+}
+
+impl HashFunction {
+    /// Name published in `GroupTreeSelected`
+    pub fn name(&self, env: &Env) -> Symbol {
+        match self {
+            Self::Keccak256 => Symbol::new(env, "keccak256"),
+            Self::Sha256 => Symbol::new(env, "sha256"),
+            Self::Poseidon => Symbol::new(env, "poseidon"),
+        }
+    }
+
+    /// Hash function published under `name`
+    pub fn from_name(env: &Env, name: &Symbol) -> Option<Self> {
+        [Self::Keccak256, Self::Sha256, Self::Poseidon]
+            .into_iter()
+            .find(|hash| hash.name(env) == *name)
+    }
+}
+
+#[contracttype]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GroupStatus {
+    Active,
+    /// Read-only, and proofs against it are rejected
+    Archived,
+    /// Member entries and tree removed; the id is never reused
+    Deleted,
+    /// The admin renounced; members are fixed but proofs are still accepted
+    Immutable,
+}
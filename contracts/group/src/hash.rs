@@ -0,0 +1,132 @@
+//! Hash functions group trees combine their nodes with.
+
+use crate::datatypes::HashFunction;
+use crate::poseidon::{field_from_bytes, field_to_bytes, hash2};
+use ark_bn254::Fr;
+use soroban_sdk::{Bytes, Env};
+
+/// Hash function of a tree
+pub trait Hasher {
+    fn hash(&self, env: &Env, data: &Bytes) -> Bytes;
+
+    /// Hash of a parent node given its two children
+    fn hash_node(&self, env: &Env, left: &Bytes, right: &Bytes) -> Bytes {
+        let mut combined = Bytes::new(env);
+        combined.append(left);
+        combined.append(right);
+        self.hash(env, &combined)
+    }
+}
+
+pub struct Keccak256;
+
+impl Hasher for Keccak256 {
+    fn hash(&self, env: &Env, data: &Bytes) -> Bytes {
+        env.crypto().keccak256(data).into()
+    }
+}
+
+pub struct Sha256;
+
+impl Hasher for Sha256 {
+    fn hash(&self, env: &Env, data: &Bytes) -> Bytes {
+        env.crypto().sha256(data).into()
+    }
+}
+
+/// Poseidon over the BN254 scalar field, as the Semaphore circuits hash
+/// their tree. Nodes are read as big-endian numbers modulo the field
+pub struct Poseidon;
+
+/// Bytes of data absorbed at a time, fewer than a field element so every
+/// chunk is below the modulus
+const POSEIDON_CHUNK: u32 = 31;
+
+impl Hasher for Poseidon {
+    /// Data of any length, chained 31 bytes at a time onto its length
+    fn hash(&self, env: &Env, data: &Bytes) -> Bytes {
+        let mut state = Fr::from(data.len());
+        let mut start = 0;
+        while start < data.len() {
+            let end = data.len().min(start + POSEIDON_CHUNK);
+            state = hash2(state, field_from_bytes(&data.slice(start..end)));
+            start = end;
+        }
+        field_to_bytes(env, &state)
+    }
+
+    fn hash_node(&self, env: &Env, left: &Bytes, right: &Bytes) -> Bytes {
+        field_to_bytes(env, &hash2(field_from_bytes(left), field_from_bytes(right)))
+    }
+}
+
+impl Hasher for HashFunction {
+    fn hash(&self, env: &Env, data: &Bytes) -> Bytes {
+        match self {
+            Self::Keccak256 => Keccak256.hash(env, data),
+            Self::Sha256 => Sha256.hash(env, data),
+            Self::Poseidon => Poseidon.hash(env, data),
+        }
+    }
+
+    fn hash_node(&self, env: &Env, left: &Bytes, right: &Bytes) -> Bytes {
+        match self {
+            Self::Keccak256 => Keccak256.hash_node(env, left, right),
+            Self::Sha256 => Sha256.hash_node(env, left, right),
+            Self::Poseidon => Poseidon.hash_node(env, left, right),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_node() {
+        let env = Env::default();
+        let left = Bytes::from_array(&env, &[1; 32]);
+        let right = Bytes::from_array(&env, &[2; 32]);
+        let mut combined = left.clone();
+        combined.append(&right);
+
+        assert_eq!(
+            HashFunction::Keccak256.hash_node(&env, &left, &right),
+            Bytes::from(env.crypto().keccak256(&combined))
+        );
+        assert_eq!(
+            HashFunction::Sha256.hash_node(&env, &left, &right),
+            Bytes::from(env.crypto().sha256(&combined))
+        );
+        assert_ne!(
+            Sha256.hash_node(&env, &left, &right),
+            Sha256.hash_node(&env, &right, &left)
+        );
+        assert_eq!(
+            HashFunction::Poseidon.hash_node(&env, &left, &right),
+            field_to_bytes(
+                &env,
+                &hash2(field_from_bytes(&left), field_from_bytes(&right))
+            )
+        );
+    }
+
+    #[test]
+    fn test_poseidon_hash() {
+        let env = Env::default();
+        let data = Bytes::from_array(&env, &[7; 40]);
+        let chained = hash2(
+            hash2(Fr::from(40u32), field_from_bytes(&data.slice(0..31))),
+            field_from_bytes(&data.slice(31..40)),
+        );
+        assert_eq!(Poseidon.hash(&env, &data), field_to_bytes(&env, &chained));
+
+        // the length tells data apart from the same data with leading zeros
+        let mut padded = Bytes::from_array(&env, &[0]);
+        padded.append(&data.slice(0..31));
+        assert_ne!(
+            Poseidon.hash(&env, &padded),
+            Poseidon.hash(&env, &data.slice(0..31))
+        );
+    }
+}
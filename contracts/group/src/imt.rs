@@ -1,8 +1,11 @@
 use core::fmt::Debug;
 use core::iter::successors;
-use soroban_sdk::{contracttype, Bytes, Env, Vec};
+use soroban_sdk::{contracttype, Bytes, Env, Map, Vec};
 
-use crate::proof::{Branch, Proof};
+use crate::datatypes::{Error, HashFunction};
+use crate::hash::{Hasher, Keccak256};
+use crate::poseidon;
+use crate::proof::{Branch, Multiproof, Proof};
 
 const DST: &[u8] = b"BLS_SIG_BLS12381G1";
 
@@ -12,6 +15,9 @@ pub struct MerkleTree {
     /// Depth of the tree
     depth: u32,
 
+    /// Hash function combining nodes
+    hash: HashFunction,
+
     empty: Vec<Bytes>,
 
     /// Hash values of tree nodes and leaves
@@ -33,24 +39,24 @@ const fn left_child(index: usize) -> usize {
     index << 1
 }
 
-/// Compute the hash of a parent node given its two child nodes
-/// NOTE: currently using keccak256 for hashing
+/// Compute the hash of a parent node given its two child nodes, with the
+/// keccak256 of trees created by `MerkleTree::new`
 pub fn hash_node(env: &Env, left: &Bytes, right: &Bytes) -> Bytes {
-    let mut combined = Bytes::new(env);
-    combined.append(left);
-    combined.append(right);
-
-    env.crypto().keccak256(&combined).into()
+    Keccak256.hash_node(env, left, right)
 }
 
 impl MerkleTree {
     /// Create a new Merkle tree with a specified depth and default leaf value
     pub fn new(env: &Env, depth: u32, default_leaf: Bytes) -> Self {
-        // Precompute empty hashes using `successors`
+        Self::with_hash(env, depth, default_leaf, HashFunction::Keccak256)
+    }
+
+    /// Create a new Merkle tree combining its nodes with `hash`
+    pub fn with_hash(env: &Env, depth: u32, default_leaf: Bytes, hash: HashFunction) -> Self {
         // Precompute empty hashes using `successors`
         let mut empty = Vec::new(env);
         successors(Some(default_leaf.clone()), |prev| {
-            Some(hash_node(env, prev, prev))
+            Some(hash.hash_node(env, prev, prev))
         })
         .take((depth + 1) as usize)
         .for_each(|hash| empty.push_back(hash));
@@ -58,10 +64,10 @@ impl MerkleTree {
         let mut nodes = Vec::new(env);
         nodes.push_back(default_leaf); // First node
 
-        // Add empty nodes for each level
-        for d in 0..depth {
+        // Add empty nodes for each level, from the root down to the leaves
+        for d in 0..=depth {
             let num_nodes = 1 << d; // 2^d nodes at this depth
-            let empty_val = empty.get((depth - d) as u32).unwrap();
+            let empty_val = empty.get(depth - d).unwrap();
             for _ in 0..num_nodes {
                 nodes.push_back(empty_val.clone());
             }
@@ -69,6 +75,7 @@ impl MerkleTree {
 
         Self {
             depth,
+            hash,
             nodes,
             empty,
         }
@@ -85,7 +92,7 @@ impl MerkleTree {
         while let Some(parent_idx) = parent(current) {
             let left = self.nodes.get(left_child(parent_idx) as u32).unwrap();
             let right = self.nodes.get((left_child(parent_idx) + 1) as u32).unwrap();
-            let parent_hash = hash_node(env, &left, &right);
+            let parent_hash = self.hash.hash_node(env, &left, &right);
 
             // Update the parent hash
             self.nodes.set(parent_idx as u32, parent_hash);
@@ -93,6 +100,75 @@ impl MerkleTree {
         }
     }
 
+    /// Set consecutive leaves starting at `start_index`, hashing every
+    /// affected parent once per level
+    pub fn add_leaves(&mut self, env: &Env, start_index: usize, leaves: &Vec<Bytes>) {
+        if leaves.is_empty() {
+            return;
+        }
+
+        let first = self.get_leaf_position(start_index as u32);
+        for (offset, leaf) in leaves.iter().enumerate() {
+            self.nodes.set((first + offset) as u32, leaf);
+        }
+
+        // Range of changed nodes on the current level
+        let (mut start, mut end) = (first, first + leaves.len() as usize - 1);
+        while let (Some(parent_start), Some(parent_end)) = (parent(start), parent(end)) {
+            for parent_idx in parent_start..=parent_end {
+                let left = self.nodes.get(left_child(parent_idx) as u32).unwrap();
+                let right = self.nodes.get((left_child(parent_idx) + 1) as u32).unwrap();
+                let parent_hash = self.hash.hash_node(env, &left, &right);
+                self.nodes.set(parent_idx as u32, parent_hash);
+            }
+            (start, end) = (parent_start, parent_end);
+        }
+    }
+
+    /// Set the leaves at the given indexes, hashing every affected parent
+    /// once even when several of the leaves share it
+    pub fn set_leaves(&mut self, env: &Env, leaves: &Map<u32, Bytes>) {
+        // map keys are sorted, so positions on every level are too and
+        // shared parents come out next to each other
+        let mut level = Vec::new(env);
+        for (leaf_index, leaf) in leaves.iter() {
+            let leaf_pos = self.get_leaf_position(leaf_index) as u32;
+            self.nodes.set(leaf_pos, leaf);
+            level.push_back(leaf_pos);
+        }
+
+        while level
+            .first()
+            .and_then(|index| parent(index as usize))
+            .is_some()
+        {
+            let mut parents: Vec<u32> = Vec::new(env);
+            for index in level.iter() {
+                let parent_idx = parent(index as usize).unwrap();
+                if parents.last() == Some(parent_idx as u32) {
+                    continue;
+                }
+                let left = self.nodes.get(left_child(parent_idx) as u32).unwrap();
+                let right = self.nodes.get((left_child(parent_idx) + 1) as u32).unwrap();
+                let parent_hash = self.hash.hash_node(env, &left, &right);
+                self.nodes.set(parent_idx as u32, parent_hash);
+                parents.push_back(parent_idx as u32);
+            }
+            level = parents;
+        }
+    }
+
+    /// The empty leaf value
+    pub fn empty_leaf(&self) -> Bytes {
+        self.empty.get(0).unwrap()
+    }
+
+    /// Reset the leaf at the given index to the empty leaf value
+    pub fn remove_leaf(&mut self, env: &Env, leaf_index: usize) {
+        let empty_leaf = self.empty.get(0).unwrap();
+        self.add_leaf(env, leaf_index, empty_leaf);
+    }
+
     /// Generate a Merkle proof for a leaf at given index
     pub fn proof(&self, leaf_index: usize) -> Option<Proof> {
         if leaf_index >= self.num_leaves() {
@@ -100,7 +176,7 @@ impl MerkleTree {
         }
 
         let mut index = self.get_leaf_position(leaf_index as u32);
-        let mut path = Vec::new(&self.nodes.env());
+        let mut path = Vec::new(self.nodes.env());
 
         while let Some(parent_idx) = parent(index) {
             // Add proof for node at index to parent
@@ -118,10 +194,96 @@ impl MerkleTree {
         Some(Proof(path))
     }
 
-    pub fn verify_proof(&self, env: &Env, leaf_hash: &Bytes, proof: &Proof) -> bool {
-        let root = proof.root(env, leaf_hash);
-        let get_root = self.get_root();
-        root == get_root
+    /// Generate one proof for the leaves at the given indices, which must be
+    /// strictly ascending
+    pub fn multiproof(&self, indices: &Vec<u32>) -> Option<Multiproof> {
+        let env = self.nodes.env();
+        if indices.is_empty() {
+            return None;
+        }
+
+        let mut level = Vec::new(env);
+        let mut previous = None;
+        for index in indices.iter() {
+            if previous.is_some_and(|previous| previous >= index)
+                || index as usize >= self.num_leaves()
+            {
+                return None;
+            }
+            level.push_back(self.get_leaf_position(index) as u32);
+            previous = Some(index);
+        }
+
+        let mut siblings = Vec::new(env);
+        while level
+            .first()
+            .and_then(|index| parent(index as usize))
+            .is_some()
+        {
+            let mut parents = Vec::new(env);
+            let mut i = 0;
+            while i < level.len() {
+                let index = level.get_unchecked(i);
+                if index & 1 == 0 && level.get(i + 1) == Some(index + 1) {
+                    // the sibling is proven too
+                    i += 1;
+                } else {
+                    siblings.push_back(self.nodes.get(index ^ 1).unwrap());
+                }
+                parents.push_back(index >> 1);
+                i += 1;
+            }
+            level = parents;
+        }
+
+        Some(Multiproof {
+            indices: indices.clone(),
+            siblings,
+        })
+    }
+
+    /// Check a multiproof for the given leaf hashes, in the order of its
+    /// indices
+    pub fn verify_multiproof(
+        &self,
+        env: &Env,
+        leaf_hashes: &Vec<Bytes>,
+        proof: &Multiproof,
+    ) -> bool {
+        proof.root(env, &self.hash, self.depth, leaf_hashes) == Some(self.get_root())
+    }
+
+    /// Check that `proof` has a branch for every level below the root and
+    /// leads to one of the first `leaf_count` leaves
+    pub fn check_proof(&self, proof: &Proof, leaf_count: usize) -> Result<(), Error> {
+        if proof.0.len() != self.depth {
+            return Err(Error::InvalidProofLength);
+        }
+        match proof.leaf_index() {
+            Some(index) if index < leaf_count.min(self.num_leaves()) => Ok(()),
+            _ => Err(Error::LeafIndexOutOfRange),
+        }
+    }
+
+    /// Check a proof of `leaf_hash` at one of the first `leaf_count` leaves.
+    /// A proof that does not pass `check_proof` is an error rather than false
+    pub fn verify_proof(
+        &self,
+        env: &Env,
+        leaf_hash: &Bytes,
+        proof: &Proof,
+        leaf_count: usize,
+    ) -> Result<bool, Error> {
+        self.check_proof(proof, leaf_count)?;
+        Ok(proof.root(env, &self.hash, leaf_hash) == self.get_root())
+    }
+
+    pub fn hash_function(&self) -> HashFunction {
+        self.hash
+    }
+
+    pub fn get_depth(&self) -> u32 {
+        self.depth
     }
 
     pub fn get_root(&self) -> Bytes {
@@ -133,24 +295,53 @@ impl MerkleTree {
     }
 
     pub fn get_leaf_position(&self, leaf_index: u32) -> usize {
-        (1 << (self.depth - 1)) + leaf_index as usize
+        (1 << self.depth) + leaf_index as usize
     }
 
     /// hash the bls12_381 value to a g1 point
     pub fn hash_to_g1(&self, env: &Env, value: Bytes) -> Bytes {
-        env.crypto()
-            .bls12_381()
-            .hash_to_g1(&value, &Bytes::from_slice(env, DST))
-            .to_bytes()
-            .into()
+        hash_to_g1(env, value)
+    }
+
+    /// Leaf value of an identity commitment in this tree, see [`leaf`]
+    pub fn leaf(&self, env: &Env, identity_commitment: &Bytes) -> Option<Bytes> {
+        leaf(env, self.hash, identity_commitment)
     }
 }
 
+/// Leaf value of an identity commitment in a tree combining its nodes with
+/// `hash`. Poseidon trees hold the commitment itself, as Semaphore's do, and
+/// cannot hold commitments that are no field element or the empty leaf; the
+/// others hold it hashed to G1
+pub fn leaf(env: &Env, hash: HashFunction, identity_commitment: &Bytes) -> Option<Bytes> {
+    match hash {
+        HashFunction::Poseidon => (poseidon::is_field_element(env, identity_commitment)
+            && identity_commitment.iter().any(|byte| byte != 0))
+        .then(|| identity_commitment.clone()),
+        _ => Some(hash_to_g1(env, identity_commitment.clone())),
+    }
+}
+
+/// Leaf value stored for an identity commitment: the commitment hashed to a
+/// bls12_381 g1 point
+pub fn hash_to_g1(env: &Env, value: Bytes) -> Bytes {
+    env.crypto()
+        .bls12_381()
+        .hash_to_g1(&value, &Bytes::from_slice(env, DST))
+        .to_bytes()
+        .into()
+}
+
 #[cfg(test)]
 mod tests {
+    extern crate std;
+
     use soroban_sdk::{crypto::bls12_381::G1Affine, vec};
 
     use super::*;
+    use crate::hash::Sha256;
+    use crate::EMPTY_LEAF;
+    use proptest::prelude::*;
 
     #[test]
     fn bls12_381_can_convert_to_bytes_and_back() {
@@ -188,17 +379,21 @@ mod tests {
     fn test_new() {
         let env = Env::default();
         let default_leaf = Bytes::from_slice(&env, b"default_leaf");
-        let imt = MerkleTree::new(&env, 3, default_leaf.clone());
+        let imt = MerkleTree::new(&env, 2, default_leaf.clone());
 
         // calculate hash at level t - 1 (hash(leaf + leaf))
-        let hash_at_level2 = hash_node(&env, &default_leaf, &default_leaf);
+        let hash_at_level1 = hash_node(&env, &default_leaf, &default_leaf);
 
         // 0: default leaf
         // 1: root
-        // 2: hash(leaf + leaf)
-        // 3: leaf
-        let level2_idx = 0 + (1 << 0) + (1 << 1) + 1; // Using bit shifts: 0 + 1 + 2 + 1 = 4
-        assert_eq!(imt.nodes.get(level2_idx).unwrap(), hash_at_level2);
+        // 2, 3: hash(leaf + leaf)
+        // 4 to 7: leaves
+        assert_eq!(imt.nodes.get(2).unwrap(), hash_at_level1);
+        assert_eq!(imt.nodes.get(4).unwrap(), default_leaf);
+        assert_eq!(
+            imt.get_root(),
+            hash_node(&env, &hash_at_level1, &hash_at_level1)
+        );
     }
 
     #[test]
@@ -216,16 +411,6 @@ mod tests {
         let expected_nodes = vec![
             &env,
             Bytes::from_slice(&env, b"default_leaf"),
-            Bytes::from_slice(
-                &env,
-                &hex::decode("ec44baf66ce9f0165df6f7489e7b768fefaaf97a90e6377011f9725779d849e2")
-                    .unwrap(),
-            ),
-            Bytes::from_slice(
-                &env,
-                &hex::decode("b36425c35b074ac99a412458fc0bceaed7e9e401e65e32c9045c38645a0b99a4")
-                    .unwrap(),
-            ),
             Bytes::from_slice(
                 &env,
                 &hex::decode("b36425c35b074ac99a412458fc0bceaed7e9e401e65e32c9045c38645a0b99a4")
@@ -241,16 +426,10 @@ mod tests {
                 &hex::decode("e7d33409e0386a947ba46ff63ad2a5126450a326877d8b1094b70db57c03d50f")
                     .unwrap(),
             ),
-            Bytes::from_slice(
-                &env,
-                &hex::decode("e7d33409e0386a947ba46ff63ad2a5126450a326877d8b1094b70db57c03d50f")
-                    .unwrap(),
-            ),
-            Bytes::from_slice(
-                &env,
-                &hex::decode("e7d33409e0386a947ba46ff63ad2a5126450a326877d8b1094b70db57c03d50f")
-                    .unwrap(),
-            ),
+            Bytes::from_slice(&env, b"default_leaf"),
+            Bytes::from_slice(&env, b"default_leaf"),
+            Bytes::from_slice(&env, b"default_leaf"),
+            Bytes::from_slice(&env, b"default_leaf"),
         ];
 
         let test_cases = vec![
@@ -279,14 +458,14 @@ mod tests {
         ];
 
         for (index, leaf_value, expected_updated_nodes) in test_cases {
-            let mut imt = MerkleTree::new(&env, 3, default_leaf.clone());
+            let mut imt = MerkleTree::new(&env, 2, default_leaf.clone());
 
             // Add the leaf and log state
             imt.add_leaf(&env, index as usize, leaf_value);
 
             // Verify nodes are updated correctly
             for i in 0..expected_nodes.len() {
-                if expected_updated_nodes.contains(&i) {
+                if expected_updated_nodes.contains(i) {
                     // Updated nodes should be different from initial state
                     assert_ne!(
                         imt.nodes.get(i).unwrap(),
@@ -310,9 +489,115 @@ mod tests {
     }
 
     #[test]
-    fn test_proof_and_verify() {
+    fn test_add_leaves() {
+        let env = Env::default();
+        let default_leaf = Bytes::from_slice(&env, b"default_leaf");
+        let leaves = vec![
+            &env,
+            Bytes::from_slice(&env, b"leaf_1"),
+            Bytes::from_slice(&env, b"leaf_2"),
+            Bytes::from_slice(&env, b"leaf_3"),
+        ];
+
+        // same nodes as adding the leaves one by one, from any start index
+        for start in 0..2 {
+            let mut batch = MerkleTree::new(&env, 3, default_leaf.clone());
+            batch.add_leaf(&env, 0, Bytes::from_slice(&env, b"leaf_0"));
+            let mut single = batch.clone();
+
+            batch.add_leaves(&env, start, &leaves);
+            for (offset, leaf) in leaves.iter().enumerate() {
+                single.add_leaf(&env, start + offset, leaf);
+            }
+            assert_eq!(batch.nodes, single.nodes);
+        }
+    }
+
+    #[test]
+    fn test_set_leaves() {
+        let env = Env::default();
+        let mut batch = MerkleTree::new(&env, 3, Bytes::from_slice(&env, b"default_leaf"));
+        for index in 0..8 {
+            batch.add_leaf(&env, index, Bytes::from_array(&env, &[index as u8]));
+        }
+        let mut single = batch.clone();
+
+        let mut leaves = Map::new(&env);
+        for index in [6u32, 1, 0, 3] {
+            leaves.set(index, Bytes::from_array(&env, &[index as u8, 1]));
+        }
+        batch.set_leaves(&env, &leaves);
+        for (index, leaf) in leaves.iter() {
+            single.add_leaf(&env, index as usize, leaf);
+        }
+        assert_eq!(batch.nodes, single.nodes);
+    }
+
+    #[test]
+    fn test_multiproof() {
         let env = Env::default();
         let mut imt = MerkleTree::new(&env, 3, Bytes::from_slice(&env, b"default_leaf"));
+        for index in 0..6 {
+            imt.add_leaf(&env, index, Bytes::from_array(&env, &[index as u8]));
+        }
+        let leaf = |index: u32| imt.nodes.get(imt.get_leaf_position(index) as u32).unwrap();
+
+        for indices in [
+            vec![&env, 0u32],
+            vec![&env, 0, 1],
+            vec![&env, 1, 2],
+            vec![&env, 0, 3, 4],
+            vec![&env, 2, 5, 7],
+            vec![&env, 0, 1, 2, 3, 4, 5, 6, 7],
+        ] {
+            let proof = imt.multiproof(&indices).unwrap();
+            let mut leaves = Vec::new(&env);
+            let mut single_siblings = 0;
+            for index in indices.iter() {
+                leaves.push_back(leaf(index));
+                // same root as the proof for the leaf alone
+                let single = imt.proof(index as usize).unwrap();
+                assert_eq!(single.root(&env, &Keccak256, &leaf(index)), imt.get_root());
+                single_siblings += single.0.len();
+            }
+            assert_eq!(
+                proof.root(&env, &Keccak256, 3, &leaves),
+                Some(imt.get_root())
+            );
+            assert!(imt.verify_multiproof(&env, &leaves, &proof));
+            assert!(proof.siblings.len() <= single_siblings);
+
+            // a changed leaf changes the root
+            leaves.set(0, Bytes::from_slice(&env, b"other"));
+            assert!(!imt.verify_multiproof(&env, &leaves, &proof));
+        }
+
+        // siblings on a shared path are sent once
+        let proof = imt.multiproof(&vec![&env, 0, 1]).unwrap();
+        assert_eq!(proof.siblings.len(), 2);
+        let proof = imt.multiproof(&vec![&env, 0, 1, 2, 3, 4, 5, 6, 7]).unwrap();
+        assert_eq!(proof.siblings.len(), 0);
+
+        // indices must be ascending and in the tree
+        assert_eq!(imt.multiproof(&vec![&env, 1, 0]), None);
+        assert_eq!(imt.multiproof(&vec![&env, 1, 1]), None);
+        assert_eq!(imt.multiproof(&vec![&env, 8]), None);
+        assert_eq!(imt.multiproof(&vec![&env]), None);
+
+        // leftover or missing siblings do not verify
+        let mut proof = imt.multiproof(&vec![&env, 0, 3]).unwrap();
+        let leaves = vec![&env, leaf(0), leaf(3)];
+        proof.siblings.push_back(Bytes::new(&env));
+        assert!(!imt.verify_multiproof(&env, &leaves, &proof));
+        proof.siblings.pop_back();
+        proof.siblings.pop_back();
+        assert!(!imt.verify_multiproof(&env, &leaves, &proof));
+    }
+
+    #[test]
+    fn test_proof_and_verify() {
+        let env = Env::default();
+        let mut imt = MerkleTree::new(&env, 2, Bytes::from_slice(&env, b"default_leaf"));
 
         // Setup test leaves
         let test_leaves = vec![
@@ -398,8 +683,148 @@ mod tests {
 
             let leaf_hash = test_leaves.get(leaf_index).unwrap().1;
 
-            let root = proof.root(&env, &leaf_hash);
+            let root = proof.root(&env, &Keccak256, &leaf_hash);
             assert_eq!(root, imt.get_root());
         }
     }
+
+    #[test]
+    fn test_sha256() {
+        let env = Env::default();
+        let default_leaf = Bytes::from_slice(&env, b"default_leaf");
+        let mut keccak = MerkleTree::new(&env, 2, default_leaf.clone());
+        let mut sha256 = MerkleTree::with_hash(&env, 2, default_leaf, HashFunction::Sha256);
+        assert_eq!(sha256.hash_function(), HashFunction::Sha256);
+        assert_ne!(keccak.get_root(), sha256.get_root());
+
+        let leaves: std::vec::Vec<_> = (0..4u8).map(|i| Bytes::from_array(&env, &[i])).collect();
+        for (index, leaf) in leaves.iter().enumerate() {
+            keccak.add_leaf(&env, index, leaf.clone());
+            sha256.add_leaf(&env, index, leaf.clone());
+        }
+        let level1 = [
+            Sha256.hash_node(&env, &leaves[0], &leaves[1]),
+            Sha256.hash_node(&env, &leaves[2], &leaves[3]),
+        ];
+        assert_eq!(
+            sha256.get_root(),
+            Sha256.hash_node(&env, &level1[0], &level1[1])
+        );
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = sha256.proof(index).unwrap();
+            assert_eq!(sha256.verify_proof(&env, leaf, &proof, 4), Ok(true));
+            // the same path does not verify with the other hash
+            assert_eq!(keccak.verify_proof(&env, leaf, &proof, 4), Ok(false));
+        }
+    }
+
+    /// Membership change applied to both the tree and the reference
+    #[derive(Clone, Debug)]
+    enum Op {
+        /// Set the next unused leaf
+        Insert([u8; 32]),
+        /// Overwrite a used leaf, picked modulo the number of them
+        Update(usize, [u8; 32]),
+        /// Reset a used leaf to the empty leaf
+        Remove(usize),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            2 => any::<[u8; 32]>().prop_map(Op::Insert),
+            1 => (any::<usize>(), any::<[u8; 32]>()).prop_map(|(used, leaf)| Op::Update(used, leaf)),
+            1 => any::<usize>().prop_map(Op::Remove),
+        ]
+    }
+
+    /// Every level of a tree holding `leaves`, computed from scratch, from
+    /// the leaves up to the root
+    fn reference_levels(
+        env: &Env,
+        hash: HashFunction,
+        leaves: &[Bytes],
+    ) -> std::vec::Vec<std::vec::Vec<Bytes>> {
+        let mut levels = std::vec![leaves.to_vec()];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| hash.hash_node(env, &pair[0], &pair[1]))
+                .collect();
+            levels.push(level);
+        }
+        levels
+    }
+
+    /// Path of leaf `index` through `levels`
+    fn reference_proof(env: &Env, levels: &[std::vec::Vec<Bytes>], index: usize) -> Proof {
+        let mut path = Vec::new(env);
+        for (level, nodes) in levels[..levels.len() - 1].iter().enumerate() {
+            let position = index >> level;
+            path.push_back(match position & 1 {
+                0 => Branch::Right(nodes[position + 1].clone()),
+                _ => Branch::Left(nodes[position - 1].clone()),
+            });
+        }
+        Proof(path)
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_reference(
+            depth in 1u32..=5,
+            sha256 in any::<bool>(),
+            ops in prop::collection::vec(op(), 1..24),
+        ) {
+            let env = Env::default();
+            env.budget().reset_unlimited();
+            let hash = match sha256 {
+                false => HashFunction::Keccak256,
+                true => HashFunction::Sha256,
+            };
+            let empty_leaf = Bytes::from_array(&env, &EMPTY_LEAF);
+            let mut tree = MerkleTree::with_hash(&env, depth, empty_leaf.clone(), hash);
+            let mut leaves = std::vec![empty_leaf.clone(); 1 << depth];
+            let mut used = 0;
+            prop_assert_eq!(tree.num_leaves(), leaves.len());
+
+            for op in std::iter::once(None).chain(ops.into_iter().map(Some)) {
+                match op {
+                    Some(Op::Insert(leaf)) if used < leaves.len() => {
+                        leaves[used] = Bytes::from_array(&env, &leaf);
+                        tree.add_leaf(&env, used, leaves[used].clone());
+                        used += 1;
+                    }
+                    Some(Op::Update(index, leaf)) if used > 0 => {
+                        let index = index % used;
+                        leaves[index] = Bytes::from_array(&env, &leaf);
+                        tree.add_leaf(&env, index, leaves[index].clone());
+                    }
+                    Some(Op::Remove(index)) if used > 0 => {
+                        let index = index % used;
+                        leaves[index] = empty_leaf.clone();
+                        tree.remove_leaf(&env, index);
+                    }
+                    _ => {}
+                }
+
+                let levels = reference_levels(&env, hash, &leaves);
+                prop_assert_eq!(tree.get_root(), levels.last().unwrap()[0].clone());
+                for (index, leaf) in leaves.iter().enumerate() {
+                    let proof = tree.proof(index).unwrap();
+                    prop_assert_eq!(&proof, &reference_proof(&env, &levels, index));
+                    prop_assert_eq!(proof.leaf_index(), Some(index));
+                    // leaves past the used ones cannot be proven
+                    let verified = match index < used {
+                        true => Ok(true),
+                        false => Err(Error::LeafIndexOutOfRange),
+                    };
+                    prop_assert_eq!(tree.verify_proof(&env, leaf, &proof, used), verified);
+                }
+                prop_assert_eq!(tree.proof(leaves.len()), None);
+            }
+        }
+    }
 }
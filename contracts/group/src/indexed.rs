@@ -0,0 +1,297 @@
+//! Indexed Merkle tree: every leaf links a key to the next larger key in
+//! the tree, so it can prove that a key is absent as well as present.
+//!
+//! Keys are keccak256 hashes of identity commitments, whatever hash function
+//! combines the tree's nodes. Leaf 0 holds the zero
+//! key, smaller than every other key, so any absent key falls between the
+//! key of some leaf and the key that leaf links to. A leaf is
+//! `hash_node(key, next_key)`, and the largest key links to the zero key.
+
+use crate::hash::Hasher;
+use crate::imt::MerkleTree;
+use crate::proof::NonMembershipProof;
+use soroban_sdk::{contracttype, Bytes, BytesN, Env, Map, Vec};
+
+/// Key of an identity commitment
+pub fn key(env: &Env, identity_commitment: &Bytes) -> BytesN<32> {
+    env.crypto().keccak256(identity_commitment).into()
+}
+
+/// Leaf value linking `key` to `next_key`
+pub fn leaf(env: &Env, hasher: &impl Hasher, key: &BytesN<32>, next_key: &BytesN<32>) -> Bytes {
+    hasher.hash_node(env, key.as_ref(), next_key.as_ref())
+}
+
+/// Indexed tree: the leaves and the keys they link
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexedTree {
+    pub tree: MerkleTree,
+    pub sorted: SortedLeaves,
+}
+
+impl IndexedTree {
+    /// Empty tree, with the zero key at leaf 0
+    pub fn new(env: &Env, mut tree: MerkleTree) -> Self {
+        let sorted = SortedLeaves::new(env, &mut tree);
+        Self { tree, sorted }
+    }
+
+    /// Proof that `key` is not in the tree, `None` if it is
+    pub fn non_membership_proof(&self, env: &Env, key: &BytesN<32>) -> Option<NonMembershipProof> {
+        self.sorted.non_membership_proof(env, &self.tree, key)
+    }
+}
+
+/// Keys of an indexed tree in ascending order, each with the index of the
+/// leaf it is stored at
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SortedLeaves {
+    keys: Vec<BytesN<32>>,
+    indices: Vec<u32>,
+}
+
+impl SortedLeaves {
+    /// Store the zero key at leaf 0 of an empty tree
+    pub fn new(env: &Env, tree: &mut MerkleTree) -> Self {
+        let zero = BytesN::from_array(env, &[0; 32]);
+        tree.add_leaf(env, 0, leaf(env, &tree.hash_function(), &zero, &zero));
+        Self {
+            keys: soroban_sdk::vec![env, zero],
+            indices: soroban_sdk::vec![env, 0],
+        }
+    }
+
+    /// Store `key` at leaf `index` and link it in after the next smaller
+    /// key. `false` if the key is already in the tree
+    pub fn insert(
+        &mut self,
+        env: &Env,
+        tree: &mut MerkleTree,
+        key: BytesN<32>,
+        index: u32,
+    ) -> bool {
+        let position = match self.keys.binary_search(&key) {
+            Ok(_) => return false,
+            Err(position) => position,
+        };
+        let low = position - 1;
+        let hash = tree.hash_function();
+
+        let mut leaves = Map::new(env);
+        leaves.set(
+            self.indices.get_unchecked(low),
+            leaf(env, &hash, &self.keys.get_unchecked(low), &key),
+        );
+        leaves.set(index, leaf(env, &hash, &key, &self.next_key(env, low)));
+        tree.set_leaves(env, &leaves);
+
+        self.keys.insert(position, key);
+        self.indices.insert(position, index);
+        true
+    }
+
+    /// Reset the leaf of `key` to the empty leaf and link the next smaller
+    /// key past it. The index of the leaf, `None` if the key is not in the
+    /// tree
+    pub fn remove(&mut self, env: &Env, tree: &mut MerkleTree, key: &BytesN<32>) -> Option<u32> {
+        let position = match self.keys.binary_search(key) {
+            Ok(0) | Err(_) => return None,
+            Ok(position) => position,
+        };
+        let low = position - 1;
+        let index = self.indices.get_unchecked(position);
+
+        let mut leaves = Map::new(env);
+        leaves.set(
+            self.indices.get_unchecked(low),
+            leaf(
+                env,
+                &tree.hash_function(),
+                &self.keys.get_unchecked(low),
+                &self.next_key(env, position),
+            ),
+        );
+        leaves.set(index, tree.empty_leaf());
+        tree.set_leaves(env, &leaves);
+
+        self.keys.remove(position);
+        self.indices.remove(position);
+        Some(index)
+    }
+
+    /// Proof that `key` is not in the tree, `None` if it is
+    pub fn non_membership_proof(
+        &self,
+        env: &Env,
+        tree: &MerkleTree,
+        key: &BytesN<32>,
+    ) -> Option<NonMembershipProof> {
+        let low = self.keys.binary_search(key).err()? - 1;
+        Some(NonMembershipProof {
+            low_key: self.keys.get_unchecked(low),
+            next_key: self.next_key(env, low),
+            proof: tree.proof(self.indices.get_unchecked(low) as usize)?,
+        })
+    }
+
+    /// Key `key` links to, `None` if it is not in the tree
+    pub fn link(&self, env: &Env, key: &BytesN<32>) -> Option<BytesN<32>> {
+        match self.keys.binary_search(key) {
+            Ok(0) | Err(_) => None,
+            Ok(position) => Some(self.next_key(env, position)),
+        }
+    }
+
+    /// Key the key at `position` links to
+    fn next_key(&self, env: &Env, position: u32) -> BytesN<32> {
+        self.keys
+            .get(position + 1)
+            .unwrap_or_else(|| BytesN::from_array(env, &[0; 32]))
+    }
+}
+
+/// Check that `key` is not in `tree`. The proof must span every level of
+/// the tree, so an inner node cannot pass for a leaf, and lead to one of the
+/// first `leaf_count` leaves, so an unused one cannot either
+pub fn verify_non_membership(
+    env: &Env,
+    tree: &MerkleTree,
+    key: &BytesN<32>,
+    proof: &NonMembershipProof,
+    leaf_count: u32,
+) -> bool {
+    tree.check_proof(&proof.proof, leaf_count as usize).is_ok()
+        && proof.root(env, &tree.hash_function(), key) == Some(tree.get_root())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::hash::Keccak256;
+    use crate::EMPTY_LEAF;
+
+    fn keys(env: &Env, seeds: &[u8]) -> std::vec::Vec<BytesN<32>> {
+        seeds
+            .iter()
+            .map(|seed| key(env, &Bytes::from_array(env, &[*seed])))
+            .collect()
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let env = Env::default();
+        let mut tree = MerkleTree::new(&env, 4, Bytes::from_array(&env, &EMPTY_LEAF));
+        let mut sorted = SortedLeaves::new(&env, &mut tree);
+        let keys = keys(&env, &[1, 2, 3, 4]);
+        for (index, key) in keys.iter().enumerate() {
+            assert!(sorted.insert(&env, &mut tree, key.clone(), index as u32 + 1));
+        }
+        assert!(!sorted.insert(&env, &mut tree, keys[0].clone(), 5));
+
+        // every leaf links to the next larger key, the largest to zero
+        let zero = BytesN::from_array(&env, &[0; 32]);
+        let mut sorted_keys = keys.clone();
+        sorted_keys.sort();
+        let mut expected = MerkleTree::new(&env, 4, Bytes::from_array(&env, &EMPTY_LEAF));
+        let mut links = std::vec![zero.clone()];
+        links.extend(sorted_keys.iter().cloned());
+        for (position, link) in links.iter().enumerate() {
+            let next = links.get(position + 1).unwrap_or(&zero);
+            let index = match position {
+                0 => 0,
+                _ => keys.iter().position(|key| key == link).unwrap() + 1,
+            };
+            expected.add_leaf(&env, index, leaf(&env, &Keccak256, link, next));
+        }
+        assert_eq!(tree.get_root(), expected.get_root());
+        assert_eq!(
+            sorted.link(&env, &sorted_keys[0]),
+            Some(sorted_keys[1].clone())
+        );
+        assert_eq!(sorted.link(&env, &sorted_keys[3]), Some(zero.clone()));
+        assert_eq!(sorted.link(&env, &zero), None);
+
+        // removing a key leaves the tree as if it had never been inserted
+        let mut before = MerkleTree::new(&env, 4, Bytes::from_array(&env, &EMPTY_LEAF));
+        let mut before_sorted = SortedLeaves::new(&env, &mut before);
+        for (index, key) in keys.iter().enumerate().take(3) {
+            before_sorted.insert(&env, &mut before, key.clone(), index as u32 + 1);
+        }
+        assert_eq!(sorted.remove(&env, &mut tree, &keys[3]), Some(4));
+        assert_eq!(tree.get_root(), before.get_root());
+        assert_eq!(sorted, before_sorted);
+        assert_eq!(sorted.remove(&env, &mut tree, &keys[3]), None);
+        assert_eq!(sorted.remove(&env, &mut tree, &zero), None);
+    }
+
+    #[test]
+    fn test_non_membership() {
+        let env = Env::default();
+        let mut tree = MerkleTree::new(&env, 4, Bytes::from_array(&env, &EMPTY_LEAF));
+        let mut sorted = SortedLeaves::new(&env, &mut tree);
+        let absent = keys(&env, &[10, 11, 12, 13]);
+
+        // an empty tree proves through the zero key
+        let proof = sorted
+            .non_membership_proof(&env, &tree, &absent[0])
+            .unwrap();
+        assert!(verify_non_membership(&env, &tree, &absent[0], &proof, 1));
+
+        for (index, key) in keys(&env, &[1, 2, 3, 4]).into_iter().enumerate() {
+            sorted.insert(&env, &mut tree, key, index as u32 + 1);
+        }
+        for key in absent.iter() {
+            let proof = sorted.non_membership_proof(&env, &tree, key).unwrap();
+            assert!(verify_non_membership(&env, &tree, key, &proof, 5));
+            // the path is an ordinary membership proof of the low leaf
+            let low_leaf = leaf(&env, &Keccak256, &proof.low_key, &proof.next_key);
+            assert_eq!(
+                proof.proof.root(&env, &Keccak256, &low_leaf),
+                tree.get_root()
+            );
+        }
+
+        // members have no proof, and another key's proof does not fit them
+        let member = keys(&env, &[2]).remove(0);
+        assert_eq!(sorted.non_membership_proof(&env, &tree, &member), None);
+        for key in absent.iter() {
+            let proof = sorted.non_membership_proof(&env, &tree, key).unwrap();
+            assert!(!verify_non_membership(&env, &tree, &member, &proof, 5));
+        }
+
+        // nor does a proof shortened to an inner node
+        let mut proof = sorted
+            .non_membership_proof(&env, &tree, &absent[0])
+            .unwrap();
+        proof.proof.0.pop_front();
+        assert!(!verify_non_membership(&env, &tree, &absent[0], &proof, 5));
+
+        // nor does an unused leaf passed off as the zero key's
+        let zero = BytesN::from_array(&env, &[0; 32]);
+        let forged = NonMembershipProof {
+            low_key: zero.clone(),
+            next_key: zero,
+            proof: tree.proof(7).unwrap(),
+        };
+        assert!(!verify_non_membership(&env, &tree, &absent[0], &forged, 5));
+
+        // the low leaf must be one of the leaves handed out
+        let proof = sorted
+            .non_membership_proof(&env, &tree, &absent[0])
+            .unwrap();
+        let index = proof.proof.leaf_index().unwrap() as u32;
+        assert!(verify_non_membership(
+            &env,
+            &tree,
+            &absent[0],
+            &proof,
+            index + 1
+        ));
+        assert!(!verify_non_membership(
+            &env, &tree, &absent[0], &proof, index
+        ));
+    }
+}
@@ -1,15 +1,32 @@
 use crate::{
-    datatypes::{Error, Member},
-    proof::Proof,
+    datatypes::{Error, GroupInfo, GroupMetadata, GroupStatus, Member},
+    proof::{MemberProof, NonMembershipProof, Proof, SparseProof},
 };
 use soroban_sdk::{Address, Bytes, Env, Vec};
 
 pub trait SemaphoreGroupInterface {
     // Group Management
-    fn create_group(env: Env, group_id: u32, admin: Address) -> Result<(), Error>;
-    fn update_group_admin(env: Env, group_id: u32, new_admin: Address) -> Result<(), Error>;
+    fn create_group(
+        env: Env,
+        admin: Address,
+        metadata: Option<GroupMetadata>,
+    ) -> Result<u32, Error>;
+    fn update_group_admin(
+        env: Env,
+        group_id: u32,
+        new_admin: Address,
+        expiration_ledger: u32,
+    ) -> Result<(), Error>;
     fn accept_group_admin(env: Env, group_id: u32) -> Result<(), Error>;
+    fn cancel_admin_transfer(env: Env, group_id: u32) -> Result<(), Error>;
+    fn renounce_admin(env: Env, group_id: u32) -> Result<(), Error>;
     fn get_pending_admin(env: Env, group_id: u32) -> Result<Address, Error>;
+    fn archive_group(env: Env, group_id: u32) -> Result<(), Error>;
+    fn delete_group(
+        env: Env,
+        group_id: u32,
+        identity_commitments: Vec<Bytes>,
+    ) -> Result<u32, Error>;
 
     // Member Management
     fn add_member(env: Env, group_id: u32, identity_commitment: Bytes) -> Result<(), Error>;
@@ -20,7 +37,17 @@ pub trait SemaphoreGroupInterface {
         old_identity_commitment: Bytes,
         new_identity_commitment: Bytes,
     ) -> Result<(), Error>;
+    fn update_members(
+        env: Env,
+        group_id: u32,
+        identity_commitments: Vec<(Bytes, Bytes)>,
+    ) -> Result<(), Error>;
     fn remove_member(env: Env, group_id: u32, identity_commitment: Bytes) -> Result<(), Error>;
+    fn remove_members(
+        env: Env,
+        group_id: u32,
+        identity_commitments: Vec<Bytes>,
+    ) -> Result<(), Error>;
 
     // Verification methods
     fn verify_proof(
@@ -29,11 +56,46 @@ pub trait SemaphoreGroupInterface {
         identity_commitment: Bytes,
         proof: Proof,
     ) -> Result<bool, Error>;
+    fn prove_non_membership(
+        env: Env,
+        group_id: u32,
+        identity_commitment: Bytes,
+    ) -> Result<NonMembershipProof, Error>;
+    fn verify_non_membership(
+        env: Env,
+        group_id: u32,
+        identity_commitment: Bytes,
+        proof: NonMembershipProof,
+    ) -> Result<bool, Error>;
+    fn get_sparse_proof(
+        env: Env,
+        group_id: u32,
+        identity_commitment: Bytes,
+    ) -> Result<SparseProof, Error>;
+    fn verify_sparse_proof(
+        env: Env,
+        group_id: u32,
+        identity_commitment: Bytes,
+        proof: SparseProof,
+    ) -> Result<bool, Error>;
+    fn get_member_proof(
+        env: Env,
+        group_id: u32,
+        identity_commitment: Bytes,
+    ) -> Result<MemberProof, Error>;
+    fn verify_member_proof(
+        env: Env,
+        group_id: u32,
+        identity_commitment: Bytes,
+        proof: MemberProof,
+    ) -> Result<bool, Error>;
 
     fn get_merkle_root(env: Env, group_id: u32) -> Result<Bytes, Error>;
     fn get_proof(env: Env, group_id: u32, leaf_index: u32) -> Result<Proof, Error>;
 
     // View Functions
+    fn get_group(env: Env, group_id: u32) -> Result<GroupInfo, Error>;
+    fn get_group_status(env: Env, group_id: u32) -> Result<GroupStatus, Error>;
     fn get_group_admin(env: Env, group_id: u32) -> Result<Address, Error>;
     fn get_member(env: Env, group_id: u32, identity_commitment: Bytes) -> Result<Member, Error>;
     fn get_member_count(env: Env, group_id: u32) -> Result<u32, Error>;
@@ -2,80 +2,143 @@
 
 use crate::datatypes::{DataKey, Error, Member};
 use crate::interface::SemaphoreGroupInterface;
-use datatypes::Group;
-use imt::MerkleTree;
-use proof::Proof;
-use soroban_sdk::{contract, contractimpl, Address, Bytes, Env, Symbol, Vec};
-
-const DEFAULT_DEPTH: u32 = 10;
+use datatypes::{Group, GroupInfo, GroupMetadata, GroupStatus, HashFunction, TreeKind};
+use events::{
+    GroupAdminPending, GroupAdminRenounced, GroupAdminTransferCancelled, GroupAdminUpdated,
+    GroupArchived, GroupCreated, GroupDeleted, GroupEvent, GroupTreeSelected,
+    MemberAdded, MemberRemoved, MemberUpdated, MembersAdded, MembersRemoved, MembersUpdated,
+};
+use indexed::IndexedTree;
+use owner::{authorize_group_creation, require_not_paused};
+use proof::{MemberProof, NonMembershipProof, Proof, SparseProof};
+use tree::{GroupTree, Tree};
+use soroban_sdk::{contract, contractimpl, Address, Bytes, Env, Map, Vec};
+
+pub const DEFAULT_DEPTH: u32 = 10;
+pub const MIN_DEPTH: u32 = 1;
+
+/// Deepest tree a group may have, with 1024 leaves. A tree is a single entry
+/// holding every node, and creating one level deeper takes 68 MB, more than
+/// the 40 MB of memory a transaction may use
+pub const MAX_DEPTH: u32 = 10;
+
+/// Value of an empty leaf in every group tree
+pub const EMPTY_LEAF: [u8; 32] = [0u8; 32];
 
 #[contract]
 pub struct SemaphoreGroupContract;
 
 #[contractimpl]
 impl SemaphoreGroupInterface for SemaphoreGroupContract {
-    fn create_group(env: Env, group_id: u32, admin: Address) -> Result<(), Error> {
-        let admin_key = DataKey::Admin(group_id);
-
-        // Check if group already exists
-        if env.storage().instance().has(&admin_key) {
-            return Err(Error::GroupAlreadyExists);
+    fn create_group(
+        env: Env,
+        admin: Address,
+        metadata: Option<GroupMetadata>,
+    ) -> Result<u32, Error> {
+        require_not_paused(&env)?;
+
+        let metadata = metadata.unwrap_or_default();
+        let depth = metadata.depth.unwrap_or(DEFAULT_DEPTH);
+        if !(MIN_DEPTH..=MAX_DEPTH).contains(&depth) {
+            return Err(Error::InvalidTreeDepth);
         }
+        authorize_group_creation(&env, &admin)?;
 
-        // Initialize group
-        env.storage().instance().set(&admin_key, &admin);
+        // Ids are assigned in order, so nobody can claim an id ahead of time.
+        // Groups created before then chose their own, which `migrate` moves
+        // the counter past. Until it has run they are still in the instance,
+        // so any id already taken is skipped
+        let mut group_id: u32 = env
+            .storage()
+            .instance()
+            .get(&DataKey::GroupCounter)
+            .unwrap_or(0);
+        while storage::has(&env, &DataKey::Admin(group_id))
+            || storage::has(&env, &DataKey::Status(group_id))
+            || env.storage().instance().has(&DataKey::Admin(group_id))
+        {
+            group_id += 1;
+        }
         env.storage()
             .instance()
-            .set(&DataKey::MemberCount(group_id), &0u32);
+            .set(&DataKey::GroupCounter, &(group_id + 1));
+
+        // Initialize group
+        storage::set(&env, &DataKey::Admin(group_id), &admin);
+        storage::set(&env, &DataKey::MemberCount(group_id), &0u32);
+        storage::set(
+            &env,
+            &DataKey::Metadata(group_id),
+            &GroupMetadata {
+                depth: Some(depth),
+                ..metadata
+            },
+        );
 
         // Init merkle tree for group here
-        let merkle_tree = MerkleTree::new(&env, DEFAULT_DEPTH, Bytes::from_slice(&env, &[0u8; 32]));
-        let group = Group {
-            id: group_id,
+        let tree = Tree::new(&env, metadata.tree, metadata.hash, depth);
+        storage::set(&env, &DataKey::NextIndex(group_id), &tree.first_index());
+        let root = tree.root();
+        store_tree(&env, group_id, tree);
+
+        // Emit events
+        if metadata.tree != TreeKind::Incremental || metadata.hash != HashFunction::Keccak256 {
+            GroupTreeSelected {
+                group_id,
+                tree: metadata.tree.name(&env),
+                hash: metadata.hash.name(&env),
+            }
+            .publish(&env);
+        }
+        GroupCreated {
+            group_id,
             admin: admin.clone(),
-            merkle_tree,
-        };
-
-        // store group in storage
-        env.storage()
-            .instance()
-            .set(&DataKey::Group(group_id), &group);
-
-        // Emit events using Symbol for event names and proper tuple syntax
-        env.events()
-            .publish((Symbol::new(&env, "group_created"), group_id), group_id);
+            depth,
+            root,
+        }
+        .publish(&env);
 
-        env.events()
-            .publish((Symbol::new(&env, "group_admin_updated"), group_id), admin);
+        GroupAdminUpdated {
+            group_id,
+            old_admin: None,
+            new_admin: admin,
+        }
+        .publish(&env);
 
-        Ok(())
+        Ok(group_id)
     }
 
-    fn update_group_admin(env: Env, group_id: u32, new_admin: Address) -> Result<(), Error> {
+    fn update_group_admin(
+        env: Env,
+        group_id: u32,
+        new_admin: Address,
+        expiration_ledger: u32,
+    ) -> Result<(), Error> {
         let admin_key = DataKey::Admin(group_id);
-        let current_admin: Address = env
-            .storage()
-            .instance()
-            .get(&admin_key)
+        let current_admin: Address = storage::get(&env, &admin_key)
             .ok_or(Error::GroupDoesNotExist)?;
 
         // Verify caller is current admin
-        (&current_admin).require_auth();
+        current_admin.require_auth();
+        require_active(&env, group_id)?;
+
+        if expiration_ledger <= env.ledger().sequence() {
+            return Err(Error::InvalidExpirationLedger);
+        }
 
-        // Set pending admin
+        // Set pending admin, replacing any transfer already pending
         let pending_admin_key = DataKey::PendingAdmin(group_id);
-        env.storage().instance().set(&pending_admin_key, &new_admin);
+        storage::set(&env, &pending_admin_key, &new_admin);
+        storage::set(&env, &DataKey::PendingAdminExpiration(group_id), &expiration_ledger);
 
-        // Emit event with all information in topics
-        env.events().publish(
-            (
-                Symbol::new(&env, "group_admin_pending"),
-                group_id,
-                current_admin,
-                new_admin,
-            ),
-            (),
-        );
+        // Emit event
+        GroupAdminPending {
+            group_id,
+            old_admin: current_admin,
+            new_admin,
+            expiration_ledger,
+        }
+        .publish(&env);
 
         Ok(())
     }
@@ -85,129 +148,202 @@ impl SemaphoreGroupInterface for SemaphoreGroupContract {
         let admin_key = DataKey::Admin(group_id);
 
         // Verify group exists
-        let current_admin: Address = env
-            .storage()
-            .instance()
-            .get::<_, Address>(&admin_key)
+        let current_admin: Address = storage::get::<Address>(&env, &admin_key)
             .ok_or(Error::GroupDoesNotExist)?;
 
         // Get and verify pending admin
-        let new_admin: Address = env
-            .storage()
-            .instance()
-            .get::<_, Address>(&pending_admin_key)
-            .ok_or(Error::CallerIsNotThePendingGroupAdmin)?;
+        let new_admin: Address = storage::get::<Address>(&env, &pending_admin_key)
+            .ok_or(Error::NoPendingAdmin)?;
 
         // Verify caller is the pending admin
-        (&new_admin).require_auth();
+        new_admin.require_auth();
+
+        if pending_admin_expired(&env, group_id) {
+            return Err(Error::AdminTransferExpired);
+        }
 
         // Update admin
-        env.storage().instance().set(&admin_key, &new_admin);
-        env.storage().instance().remove(&pending_admin_key);
+        storage::set(&env, &admin_key, &new_admin);
+        storage::remove(&env, &pending_admin_key);
+        storage::remove(&env, &DataKey::PendingAdminExpiration(group_id));
 
         // Emit event
-        env.events().publish(
-            (
-                Symbol::new(&env, "group_admin_updated"),
-                group_id,
-                current_admin,
-                new_admin,
-            ),
-            (),
-        );
+        GroupAdminUpdated {
+            group_id,
+            old_admin: Some(current_admin),
+            new_admin,
+        }
+        .publish(&env);
 
         Ok(())
     }
+
+    fn cancel_admin_transfer(env: Env, group_id: u32) -> Result<(), Error> {
+        let admin = Self::get_group_admin(env.clone(), group_id)?;
+        admin.require_auth();
+
+        let pending_admin: Address = storage::get(&env, &DataKey::PendingAdmin(group_id))
+            .ok_or(Error::NoPendingAdmin)?;
+        storage::remove(&env, &DataKey::PendingAdmin(group_id));
+        storage::remove(&env, &DataKey::PendingAdminExpiration(group_id));
+
+        GroupAdminTransferCancelled {
+            group_id,
+            admin,
+            pending_admin,
+        }
+        .publish(&env);
+        Ok(())
+    }
+
+    fn renounce_admin(env: Env, group_id: u32) -> Result<(), Error> {
+        let admin = Self::get_group_admin(env.clone(), group_id)?;
+        admin.require_auth();
+        require_active(&env, group_id)?;
+
+        storage::remove(&env, &DataKey::PendingAdmin(group_id));
+        storage::remove(&env, &DataKey::PendingAdminExpiration(group_id));
+        storage::set(&env, &DataKey::Status(group_id), &GroupStatus::Immutable);
+
+        GroupAdminRenounced {
+            group_id,
+            old_admin: admin,
+        }
+        .publish(&env);
+        Ok(())
+    }
+
     fn get_pending_admin(env: Env, group_id: u32) -> Result<Address, Error> {
         let pending_admin_key = DataKey::PendingAdmin(group_id);
         // Try to get the pending admin; return an error if not set
-        env.storage()
-            .instance()
-            .get(&pending_admin_key)
-            .ok_or(Error::CallerIsNotThePendingGroupAdmin)
+        let pending_admin = storage::get(&env, &pending_admin_key).ok_or(Error::NoPendingAdmin)?;
+        if pending_admin_expired(&env, group_id) {
+            return Err(Error::AdminTransferExpired);
+        }
+        Ok(pending_admin)
     }
 
     fn add_member(env: Env, group_id: u32, identity_commitment: Bytes) -> Result<(), Error> {
+        require_not_paused(&env)?;
+
         if identity_commitment == Bytes::new(&env) {
             return Err(Error::InvalidIdentityCommitment);
         }
 
         let admin_key = DataKey::Admin(group_id);
-        let admin = env
-            .storage()
-            .instance()
-            .get::<_, Address>(&admin_key)
-            .ok_or(Error::GroupDoesNotExist)?;
+        let admin = storage::get::<Address>(&env, &admin_key).ok_or(Error::GroupDoesNotExist)?;
 
         // Verify caller is admin
-        (&admin).require_auth();
+        admin.require_auth();
+        require_active(&env, group_id)?;
 
         let member_key = DataKey::Member(group_id, identity_commitment.clone());
-        if env.storage().instance().has(&member_key) {
+        if storage::has(&env, &member_key) {
             return Err(Error::MemberAlreadyExists);
         }
 
         // Get and increment member count
         let count_key = DataKey::MemberCount(group_id);
-        let current_count: u32 = env.storage().instance().get(&count_key).unwrap_or(0);
+        let current_count: u32 = storage::get(&env, &count_key).unwrap_or(0);
+        let index = next_index(&env, group_id);
+        let mut tree = load_tree(&env, group_id).unwrap();
+        if !tree.accepts(&env, &identity_commitment) {
+            return Err(Error::InvalidIdentityCommitment);
+        }
+        require_room(&tree, index, 1)?;
 
         // Create and store new member
         let member = Member {
             identity_commitment: identity_commitment.clone(),
             group_id,
-            index: current_count,
+            index,
         };
 
-        env.storage().instance().set(&member_key, &member);
-        env.storage()
-            .instance()
-            .set(&count_key, &(current_count + 1));
+        storage::set(&env, &member_key, &member);
+        storage::set(&env, &count_key, &(current_count + 1));
+        storage::set(&env, &DataKey::NextIndex(group_id), &(index + 1));
 
         // update merkle tree
-        let group_key = DataKey::Group(group_id);
-        let mut group: Group = env.storage().instance().get(&group_key).unwrap();
-
-        group.merkle_tree.add_leaf(
-            &env,
-            current_count as usize,
-            group
-                .merkle_tree
-                .hash_to_g1(&env, identity_commitment.clone()),
-        );
+        tree.insert(&env, &identity_commitment, index);
 
-        env.storage().instance().set(&group_key, &group);
+        let root = tree.root();
+        store_tree(&env, group_id, tree);
 
         // Emit event
-        env.events().publish(
-            (
-                Symbol::new(&env, "MemberAdded"),
-                group_id,
-                identity_commitment,
-                current_count,
-            ),
-            (),
-        );
+        MemberAdded {
+            group_id,
+            identity_commitment,
+            index,
+            root,
+        }
+        .publish(&env);
 
         Ok(())
     }
 
     fn add_members(env: Env, group_id: u32, identity_commitments: Vec<Bytes>) -> Result<(), Error> {
-        // Get admin to verify authorization once for the whole operation
+        require_not_paused(&env)?;
+
         let admin_key = DataKey::Admin(group_id);
-        let admin = env
-            .storage()
-            .instance()
-            .get::<_, Address>(&admin_key)
-            .ok_or(Error::GroupDoesNotExist)?;
+        let admin = storage::get::<Address>(&env, &admin_key).ok_or(Error::GroupDoesNotExist)?;
+
+        // Verify caller is admin, once for the whole batch
+        admin.require_auth();
+        require_active(&env, group_id)?;
+
+        // Validate every commitment before changing anything, including
+        // duplicates within the batch
+        let mut tree = load_tree(&env, group_id).unwrap();
+        let mut batch = Map::<Bytes, ()>::new(&env);
+        for identity_commitment in identity_commitments.iter() {
+            if identity_commitment == Bytes::new(&env)
+                || !tree.accepts(&env, &identity_commitment)
+            {
+                return Err(Error::InvalidIdentityCommitment);
+            }
+            if batch.contains_key(identity_commitment.clone())
+                || storage::has(&env, &DataKey::Member(group_id, identity_commitment.clone()))
+            {
+                return Err(Error::MemberAlreadyExists);
+            }
+            batch.set(identity_commitment, ());
+        }
+        if identity_commitments.is_empty() {
+            return Ok(());
+        }
+
+        let count_key = DataKey::MemberCount(group_id);
+        let count: u32 = storage::get(&env, &count_key).unwrap_or(0);
+        let start_index = next_index(&env, group_id);
+        require_room(&tree, start_index, identity_commitments.len())?;
+
+        for (offset, identity_commitment) in identity_commitments.iter().enumerate() {
+            let member = Member {
+                identity_commitment: identity_commitment.clone(),
+                group_id,
+                index: start_index + offset as u32,
+            };
+            storage::set(&env, &DataKey::Member(group_id, identity_commitment), &member);
+        }
+        tree.insert_all(&env, &identity_commitments, start_index);
 
-        // Verify caller is admin for the main add_members call
-        (&admin).require_auth();
+        let root = tree.root();
+        store_tree(&env, group_id, tree);
+        storage::set(&env, &count_key, &(count + identity_commitments.len()));
+        storage::set(
+            &env,
+            &DataKey::NextIndex(group_id),
+            &(start_index + identity_commitments.len()),
+        );
 
-        // Add each member
-        for commitment in identity_commitments.iter() {
-            // Note: we don't need to require_auth again since we're in the same transaction
-            Self::add_member(env.clone(), group_id, commitment)?;
+        MembersAdded {
+            group_id,
+            identity_commitments,
+            start_index,
+            root,
         }
+        .publish(&env);
+
         Ok(())
     }
 
@@ -217,33 +353,33 @@ impl SemaphoreGroupInterface for SemaphoreGroupContract {
         old_identity_commitment: Bytes,
         new_identity_commitment: Bytes,
     ) -> Result<(), Error> {
+        require_not_paused(&env)?;
+
         if new_identity_commitment == Bytes::new(&env) {
             return Err(Error::InvalidIdentityCommitment);
         }
 
         let admin_key = DataKey::Admin(group_id);
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&admin_key)
-            .ok_or(Error::GroupDoesNotExist)?;
+        let admin: Address = storage::get(&env, &admin_key).ok_or(Error::GroupDoesNotExist)?;
 
         // Verify caller is admin
-        (&admin).require_auth();
+        admin.require_auth();
+        require_active(&env, group_id)?;
 
         // Check if old member exists and new member doesn't
         let old_member_key = DataKey::Member(group_id, old_identity_commitment.clone());
         let new_member_key = DataKey::Member(group_id, new_identity_commitment.clone());
 
-        let old_member: Member = env
-            .storage()
-            .instance()
-            .get(&old_member_key)
+        let old_member: Member = storage::get(&env, &old_member_key)
             .ok_or(Error::MemberDoesNotExist)?;
 
-        if env.storage().instance().has(&new_member_key) {
+        if storage::has(&env, &new_member_key) {
             return Err(Error::MemberAlreadyExists);
         }
+        let mut tree = load_tree(&env, group_id).unwrap();
+        if !tree.accepts(&env, &new_identity_commitment) {
+            return Err(Error::InvalidIdentityCommitment);
+        }
 
         // Create updated member
         let new_member = Member {
@@ -253,109 +389,330 @@ impl SemaphoreGroupInterface for SemaphoreGroupContract {
         };
 
         // Update storage
-        env.storage().instance().set(&new_member_key, &new_member);
-        env.storage().instance().remove(&old_member_key);
+        storage::set(&env, &new_member_key, &new_member);
+        storage::remove(&env, &old_member_key);
 
-        // Emit event
-        env.events().publish(
-            (
-                Symbol::new(&env, "MemberUpdated"),
-                group_id,
-                old_identity_commitment,
-                new_identity_commitment,
-            ),
-            (),
+        // overwrite the leaf in place
+        tree.update(
+            &env,
+            &old_identity_commitment,
+            &new_identity_commitment,
+            old_member.index,
         );
 
+        let root = tree.root();
+        store_tree(&env, group_id, tree);
+
+        // Emit event
+        MemberUpdated {
+            group_id,
+            old_identity_commitment,
+            new_identity_commitment,
+            index: old_member.index,
+            root,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    fn update_members(
+        env: Env,
+        group_id: u32,
+        identity_commitments: Vec<(Bytes, Bytes)>,
+    ) -> Result<(), Error> {
+        require_not_paused(&env)?;
+        let admin = Self::get_group_admin(env.clone(), group_id)?;
+        admin.require_auth();
+        require_active(&env, group_id)?;
+
+        // Validate every pair before changing anything, against the members
+        // as the earlier pairs of the batch leave them: `batch` maps the
+        // commitments the batch touched to their index, or `None` once
+        // replaced
+        let mut tree = load_tree(&env, group_id).unwrap();
+        let mut batch = Map::<Bytes, Option<u32>>::new(&env);
+        let index_of = |batch: &Map<Bytes, Option<u32>>, identity_commitment: &Bytes| {
+            match batch.get(identity_commitment.clone()) {
+                Some(index) => index,
+                None => storage::get::<Member>(
+                    &env,
+                    &DataKey::Member(group_id, identity_commitment.clone()),
+                )
+                .map(|member| member.index),
+            }
+        };
+        let mut updates = Vec::new(&env);
+        for (old_identity_commitment, new_identity_commitment) in identity_commitments.iter() {
+            let index = index_of(&batch, &old_identity_commitment);
+            let error = if new_identity_commitment == Bytes::new(&env)
+                || !tree.accepts(&env, &new_identity_commitment)
+            {
+                Some(Error::InvalidIdentityCommitment)
+            } else if index.is_none() {
+                Some(Error::MemberDoesNotExist)
+            } else if index_of(&batch, &new_identity_commitment).is_some() {
+                Some(Error::MemberAlreadyExists)
+            } else {
+                None
+            };
+            if let Some(error) = error {
+                return Err(error);
+            }
+            let index = index.unwrap();
+            batch.set(old_identity_commitment.clone(), None);
+            batch.set(new_identity_commitment.clone(), Some(index));
+            updates.push_back((old_identity_commitment, new_identity_commitment, index));
+        }
+
+        let mut old_identity_commitments = Vec::new(&env);
+        let mut new_identity_commitments = Vec::new(&env);
+        let mut indexes = Vec::new(&env);
+        for (old_identity_commitment, new_identity_commitment, index) in updates.iter() {
+            storage::remove(&env, &DataKey::Member(group_id, old_identity_commitment.clone()));
+            storage::set(
+                &env,
+                &DataKey::Member(group_id, new_identity_commitment.clone()),
+                &Member {
+                    identity_commitment: new_identity_commitment.clone(),
+                    group_id,
+                    index,
+                },
+            );
+            old_identity_commitments.push_back(old_identity_commitment);
+            new_identity_commitments.push_back(new_identity_commitment);
+            indexes.push_back(index);
+        }
+        tree.update_all(&env, &updates);
+
+        let root = tree.root();
+        store_tree(&env, group_id, tree);
+
+        MembersUpdated {
+            group_id,
+            old_identity_commitments,
+            new_identity_commitments,
+            indexes,
+            root,
+        }
+        .publish(&env);
+
         Ok(())
     }
 
     fn remove_member(env: Env, group_id: u32, identity_commitment: Bytes) -> Result<(), Error> {
+        require_not_paused(&env)?;
+
         let admin_key = DataKey::Admin(group_id);
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&admin_key)
-            .ok_or(Error::GroupDoesNotExist)?;
+        let admin: Address = storage::get(&env, &admin_key).ok_or(Error::GroupDoesNotExist)?;
 
         // Verify caller is admin
-        (&admin).require_auth();
+        admin.require_auth();
+        require_active(&env, group_id)?;
 
         // Check if member exists
         let member_key = DataKey::Member(group_id, identity_commitment.clone());
-        if !env.storage().instance().has(&member_key) {
-            return Err(Error::MemberDoesNotExist);
-        }
+        let member: Member = storage::get(&env, &member_key).ok_or(Error::MemberDoesNotExist)?;
 
         // Decrement member count
         let count_key = DataKey::MemberCount(group_id);
-        let current_count: u32 = env.storage().instance().get(&count_key).unwrap_or(1);
+        let current_count: u32 = storage::get(&env, &count_key).unwrap_or(1);
 
-        env.storage()
-            .instance()
-            .set(&count_key, &(current_count - 1));
-        env.storage().instance().remove(&member_key);
+        storage::set(&env, &count_key, &current_count.saturating_sub(1));
+        storage::remove(&env, &member_key);
+
+        // reset the leaf to the empty value
+        let mut tree = load_tree(&env, group_id).unwrap();
+        tree.remove(&env, &identity_commitment, member.index);
+
+        let root = tree.root();
+        store_tree(&env, group_id, tree);
 
         // Emit event
-        env.events().publish(
-            (
-                Symbol::new(&env, "MemberRemoved"),
-                group_id,
-                identity_commitment,
-            ),
-            (),
-        );
+        MemberRemoved {
+            group_id,
+            identity_commitment,
+            index: member.index,
+            root,
+        }
+        .publish(&env);
+
+        Ok(())
+    }
+
+    fn remove_members(
+        env: Env,
+        group_id: u32,
+        identity_commitments: Vec<Bytes>,
+    ) -> Result<(), Error> {
+        require_not_paused(&env)?;
+        let admin = Self::get_group_admin(env.clone(), group_id)?;
+        admin.require_auth();
+        require_active(&env, group_id)?;
+
+        // Validate every commitment before changing anything; one listed
+        // twice does not exist anymore the second time
+        let mut members = Vec::new(&env);
+        let mut seen = Map::<Bytes, ()>::new(&env);
+        for identity_commitment in identity_commitments.iter() {
+            let member = match seen.contains_key(identity_commitment.clone()) {
+                true => None,
+                false => storage::get::<Member>(
+                    &env,
+                    &DataKey::Member(group_id, identity_commitment.clone()),
+                ),
+            };
+            let member = member.ok_or(Error::MemberDoesNotExist)?;
+            members.push_back(member);
+            seen.set(identity_commitment, ());
+        }
+
+        let mut removals = Vec::new(&env);
+        let mut indexes = Vec::new(&env);
+        for member in members.iter() {
+            storage::remove(&env, &DataKey::Member(group_id, member.identity_commitment.clone()));
+            removals.push_back((member.identity_commitment, member.index));
+            indexes.push_back(member.index);
+        }
+        let mut tree = load_tree(&env, group_id).unwrap();
+        tree.remove_all(&env, &removals);
+
+        let count_key = DataKey::MemberCount(group_id);
+        let count: u32 = storage::get(&env, &count_key).unwrap_or(0);
+        storage::set(&env, &count_key, &count.saturating_sub(members.len()));
+
+        let root = tree.root();
+        store_tree(&env, group_id, tree);
+
+        MembersRemoved {
+            group_id,
+            identity_commitments,
+            indexes,
+            root,
+        }
+        .publish(&env);
 
         Ok(())
     }
 
+    fn archive_group(env: Env, group_id: u32) -> Result<(), Error> {
+        require_not_paused(&env)?;
+
+        let admin = Self::get_group_admin(env.clone(), group_id)?;
+        admin.require_auth();
+        require_active(&env, group_id)?;
+
+        storage::set(&env, &DataKey::Status(group_id), &GroupStatus::Archived);
+        GroupArchived { group_id }.publish(&env);
+        Ok(())
+    }
+
+    fn delete_group(
+        env: Env,
+        group_id: u32,
+        identity_commitments: Vec<Bytes>,
+    ) -> Result<u32, Error> {
+        require_not_paused(&env)?;
+
+        let admin = Self::get_group_admin(env.clone(), group_id)?;
+        admin.require_auth();
+        if Self::get_group_status(env.clone(), group_id)? != GroupStatus::Archived {
+            return Err(Error::GroupIsNotArchived);
+        }
+
+        // The tree goes away with the group, only the member entries are
+        // removed one by one
+        let count_key = DataKey::MemberCount(group_id);
+        let mut count: u32 = storage::get(&env, &count_key).unwrap_or(0);
+        for identity_commitment in identity_commitments.iter() {
+            let member_key = DataKey::Member(group_id, identity_commitment);
+            if !storage::has(&env, &member_key) {
+                return Err(Error::MemberDoesNotExist);
+            }
+            storage::remove(&env, &member_key);
+            count = count.saturating_sub(1);
+        }
+        if count > 0 {
+            storage::set(&env, &count_key, &count);
+            return Ok(count);
+        }
+
+        for key in storage::group_keys(group_id) {
+            storage::remove(&env, &key);
+        }
+        // ids are never handed out twice, the status records why this one
+        // has no group
+        storage::set(&env, &DataKey::Status(group_id), &GroupStatus::Deleted);
+        GroupDeleted { group_id }.publish(&env);
+        Ok(0)
+    }
+
+    fn get_group_status(env: Env, group_id: u32) -> Result<GroupStatus, Error> {
+        match storage::get(&env, &DataKey::Status(group_id)) {
+            Some(status) => Ok(status),
+            None if storage::has(&env, &DataKey::Admin(group_id)) => Ok(GroupStatus::Active),
+            None => Err(Error::GroupDoesNotExist),
+        }
+    }
+
     fn get_group_admin(env: Env, group_id: u32) -> Result<Address, Error> {
         let admin_key = DataKey::Admin(group_id);
-        env.storage()
-            .instance()
-            .get(&admin_key)
-            .ok_or(Error::GroupDoesNotExist)
+        storage::get(&env, &admin_key).ok_or(Error::GroupDoesNotExist)
+    }
+
+    fn get_group(env: Env, group_id: u32) -> Result<GroupInfo, Error> {
+        let tree = load_tree(&env, group_id).ok_or(Error::GroupDoesNotExist)?;
+        // groups created before metadata have the defaults
+        let metadata: GroupMetadata =
+            storage::get(&env, &DataKey::Metadata(group_id)).unwrap_or_default();
+        Ok(GroupInfo {
+            id: group_id,
+            admin: Self::get_group_admin(env.clone(), group_id)?,
+            name: metadata.name,
+            description_uri: metadata.description_uri,
+            depth: metadata.depth.unwrap_or(DEFAULT_DEPTH),
+            root: tree.root(),
+            size: Self::get_member_count(env.clone(), group_id)?,
+            status: Self::get_group_status(env, group_id)?,
+            tree: metadata.tree,
+            hash: metadata.hash,
+        })
     }
 
     fn get_member(env: Env, group_id: u32, identity_commitment: Bytes) -> Result<Member, Error> {
         let member_key = DataKey::Member(group_id, identity_commitment);
-        env.storage()
-            .instance()
-            .get(&member_key)
-            .ok_or(Error::MemberDoesNotExist)
+        storage::get(&env, &member_key).ok_or(Error::MemberDoesNotExist)
     }
 
     fn get_member_count(env: Env, group_id: u32) -> Result<u32, Error> {
         let count_key = DataKey::MemberCount(group_id);
-        env.storage()
-            .instance()
-            .get(&count_key)
-            .ok_or(Error::GroupDoesNotExist)
+        storage::get(&env, &count_key).ok_or(Error::GroupDoesNotExist)
     }
 
     fn is_member(env: Env, group_id: u32, identity_commitment: Bytes) -> Result<bool, Error> {
         // Check if group exists first
         let admin_key = DataKey::Admin(group_id);
-        if !env.storage().instance().has(&admin_key) {
+        if !storage::has(&env, &admin_key) {
             return Err(Error::GroupDoesNotExist);
         }
 
         let member_key = DataKey::Member(group_id, identity_commitment);
-        Ok(env.storage().instance().has(&member_key))
+        Ok(storage::has(&env, &member_key))
     }
 
     // return the root of the merkle tree
     fn get_merkle_root(env: Env, group_id: u32) -> Result<Bytes, Error> {
-        let group_key = DataKey::Group(group_id);
-        let group: Group = env.storage().instance().get(&group_key).unwrap();
-        Ok(group.merkle_tree.get_root())
+        Ok(load_tree(&env, group_id).ok_or(Error::GroupDoesNotExist)?.root())
     }
 
-    // get the proof for a given identity commitment
+    // get the proof for a leaf handed out so far
     fn get_proof(env: Env, group_id: u32, leaf_index: u32) -> Result<Proof, Error> {
-        let group_key = DataKey::Group(group_id);
-        let group: Group = env.storage().instance().get(&group_key).unwrap();
-        Ok(group.merkle_tree.proof(leaf_index as usize).unwrap())
+        let tree = load_tree(&env, group_id).ok_or(Error::GroupDoesNotExist)?;
+        let merkle_tree = tree.merkle_tree().ok_or(Error::UnsupportedTreeKind)?;
+        if leaf_index >= next_index(&env, group_id) {
+            return Err(Error::LeafIndexOutOfRange);
+        }
+        merkle_tree.proof(leaf_index as usize).ok_or(Error::LeafIndexOutOfRange)
     }
 
     // Verification methods
@@ -365,17 +722,184 @@ impl SemaphoreGroupInterface for SemaphoreGroupContract {
         identity_commitment: Bytes,
         proof: Proof,
     ) -> Result<bool, Error> {
-        let group_key = DataKey::Group(group_id);
-        let group: Group = env.storage().instance().get(&group_key).unwrap();
+        // Groups without an admin keep accepting proofs
+        if let Some(GroupStatus::Archived) = storage::get(&env, &DataKey::Status(group_id)) {
+            return Err(Error::GroupIsArchived);
+        }
+        let tree = load_tree(&env, group_id).ok_or(Error::GroupDoesNotExist)?;
+        let merkle_tree = tree.merkle_tree().ok_or(Error::UnsupportedTreeKind)?;
+        // the leaf of a removed member is empty again, and no member's
+        if identity_commitment == Bytes::from_array(&env, &EMPTY_LEAF) {
+            return Err(Error::InvalidIdentityCommitment);
+        }
 
-        // verify proof
-        Ok(group
-            .merkle_tree
-            .verify_proof(&env, &identity_commitment, &proof))
+        // only leaves handed out so far can be proven
+        let leaf_count = next_index(&env, group_id) as usize;
+        merkle_tree.verify_proof(&env, &identity_commitment, &proof, leaf_count)
+    }
+
+    fn prove_non_membership(
+        env: Env,
+        group_id: u32,
+        identity_commitment: Bytes,
+    ) -> Result<NonMembershipProof, Error> {
+        let Tree::Indexed(tree) = load_tree(&env, group_id).ok_or(Error::GroupDoesNotExist)? else {
+            return Err(Error::NonMembershipNotSupported);
+        };
+        tree.non_membership_proof(&env, &indexed::key(&env, &identity_commitment))
+            .ok_or(Error::MemberAlreadyExists)
+    }
+
+    fn verify_non_membership(
+        env: Env,
+        group_id: u32,
+        identity_commitment: Bytes,
+        proof: NonMembershipProof,
+    ) -> Result<bool, Error> {
+        if let Some(GroupStatus::Archived) = storage::get(&env, &DataKey::Status(group_id)) {
+            return Err(Error::GroupIsArchived);
+        }
+        let Tree::Indexed(tree) = load_tree(&env, group_id).ok_or(Error::GroupDoesNotExist)? else {
+            return Err(Error::NonMembershipNotSupported);
+        };
+
+        Ok(indexed::verify_non_membership(
+            &env,
+            &tree.tree,
+            &indexed::key(&env, &identity_commitment),
+            &proof,
+            next_index(&env, group_id),
+        ))
+    }
+
+    fn get_sparse_proof(
+        env: Env,
+        group_id: u32,
+        identity_commitment: Bytes,
+    ) -> Result<SparseProof, Error> {
+        let Tree::Sparse(tree) = load_tree(&env, group_id).ok_or(Error::GroupDoesNotExist)? else {
+            return Err(Error::UnsupportedTreeKind);
+        };
+        tree.proof(&env, &indexed::key(&env, &identity_commitment))
+            .ok_or(Error::MemberDoesNotExist)
+    }
+
+    fn verify_sparse_proof(
+        env: Env,
+        group_id: u32,
+        identity_commitment: Bytes,
+        proof: SparseProof,
+    ) -> Result<bool, Error> {
+        if let Some(GroupStatus::Archived) = storage::get(&env, &DataKey::Status(group_id)) {
+            return Err(Error::GroupIsArchived);
+        }
+        let Tree::Sparse(tree) = load_tree(&env, group_id).ok_or(Error::GroupDoesNotExist)? else {
+            return Err(Error::UnsupportedTreeKind);
+        };
+
+        Ok(tree.verify_proof(&env, &indexed::key(&env, &identity_commitment), &proof))
+    }
+
+    fn get_member_proof(
+        env: Env,
+        group_id: u32,
+        identity_commitment: Bytes,
+    ) -> Result<MemberProof, Error> {
+        let tree = load_tree(&env, group_id).ok_or(Error::GroupDoesNotExist)?;
+        let member = Self::get_member(env.clone(), group_id, identity_commitment.clone())?;
+        tree.proof(&env, &identity_commitment, member.index)
+            .ok_or(Error::MemberDoesNotExist)
+    }
+
+    fn verify_member_proof(
+        env: Env,
+        group_id: u32,
+        identity_commitment: Bytes,
+        proof: MemberProof,
+    ) -> Result<bool, Error> {
+        if let Some(GroupStatus::Archived) = storage::get(&env, &DataKey::Status(group_id)) {
+            return Err(Error::GroupIsArchived);
+        }
+        let tree = load_tree(&env, group_id).ok_or(Error::GroupDoesNotExist)?;
+
+        Ok(tree.verify(&env, &identity_commitment, &proof, next_index(&env, group_id)))
     }
 }
-mod datatypes;
-mod imt;
+/// Archived groups and groups whose admin renounced are read-only
+fn require_active(env: &Env, group_id: u32) -> Result<(), Error> {
+    match storage::get(env, &DataKey::Status(group_id)) {
+        Some(GroupStatus::Archived) => Err(Error::GroupIsArchived),
+        Some(GroupStatus::Immutable) => Err(Error::GroupIsImmutable),
+        _ => Ok(()),
+    }
+}
+
+/// Removed members keep their leaves, so a tree is full once `count` members
+/// from `index` on would pass its last leaf
+fn require_room(tree: &Tree, index: u32, count: u32) -> Result<(), Error> {
+    match tree.capacity() {
+        Some(capacity) if index as u64 + count as u64 > capacity as u64 => Err(Error::GroupIsFull),
+        _ => Ok(()),
+    }
+}
+
+/// Leaf the next member goes to. Leaves of removed members stay empty, so
+/// groups created before this was recorded continue after their member count
+pub(crate) fn next_index(env: &Env, group_id: u32) -> u32 {
+    storage::get(env, &DataKey::NextIndex(group_id))
+        .or_else(|| storage::get(env, &DataKey::MemberCount(group_id)))
+        .unwrap_or(0)
+}
+
+/// Tree of a group, `None` if the group does not exist. Sparse trees are
+/// stored on their own, the others in `Group` with the keys of indexed trees
+/// next to it
+fn load_tree(env: &Env, group_id: u32) -> Option<Tree> {
+    if let Some(tree) = storage::get(env, &DataKey::SparseTree(group_id)) {
+        return Some(Tree::Sparse(tree));
+    }
+    let group: Group = storage::get(env, &DataKey::Group(group_id))?;
+    Some(match storage::get(env, &DataKey::SortedLeaves(group_id)) {
+        Some(sorted) => Tree::Indexed(IndexedTree {
+            tree: group.merkle_tree,
+            sorted,
+        }),
+        None => Tree::Incremental(group.merkle_tree),
+    })
+}
+
+fn store_tree(env: &Env, group_id: u32, tree: Tree) {
+    let merkle_tree = match tree {
+        Tree::Incremental(tree) => tree,
+        Tree::Indexed(IndexedTree { tree, sorted }) => {
+            storage::set(env, &DataKey::SortedLeaves(group_id), &sorted);
+            tree
+        }
+        Tree::Sparse(tree) => return storage::set(env, &DataKey::SparseTree(group_id), &tree),
+    };
+    let group = Group {
+        id: group_id,
+        admin: storage::get(env, &DataKey::Admin(group_id)).unwrap(),
+        merkle_tree,
+    };
+    storage::set(env, &DataKey::Group(group_id), &group);
+}
+
+/// Transfers started before expiries were recorded never expire
+fn pending_admin_expired(env: &Env, group_id: u32) -> bool {
+    storage::get::<u32>(env, &DataKey::PendingAdminExpiration(group_id))
+        .is_some_and(|expiration_ledger| env.ledger().sequence() > expiration_ledger)
+}
+
+pub mod datatypes;
+pub mod hash;
+pub mod imt;
+pub mod indexed;
 mod interface;
-mod proof;
+pub mod owner;
+pub mod poseidon;
+pub mod proof;
+pub mod smt;
+pub mod storage;
 mod test;
+pub mod tree;
@@ -0,0 +1,243 @@
+//! Contract-level owner, who may upgrade the contract and migrate its
+//! storage, pause it, and decide who may create groups. Group admins only
+//! manage their own groups.
+
+use crate::datatypes::{CreationFee, DataKey, Error, Group, Member};
+use crate::imt::{self, MerkleTree};
+use crate::storage;
+use crate::{SemaphoreGroupContract, SemaphoreGroupContractArgs, SemaphoreGroupContractClient};
+use crate::{DEFAULT_DEPTH, EMPTY_LEAF};
+use events::{ContractEvent, Paused, Unpaused};
+use soroban_sdk::{contractimpl, token, Address, Bytes, BytesN, Env, Map, Vec};
+
+/// Version of the storage layout written by this code
+pub const STORAGE_SCHEMA_VERSION: u32 = 2;
+
+#[contractimpl]
+impl SemaphoreGroupContract {
+    pub fn __constructor(env: Env, owner: Address) {
+        env.storage().instance().set(&DataKey::Owner, &owner);
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &STORAGE_SCHEMA_VERSION);
+    }
+
+    pub fn owner(env: Env) -> Address {
+        env.storage().instance().get(&DataKey::Owner).unwrap()
+    }
+
+    /// Replace the contract's code, keeping its storage. Call `migrate`
+    /// afterwards if the new code uses a newer storage layout
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        Self::owner(env.clone()).require_auth();
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    /// Layout of the data in storage; 1 for data written before the version
+    /// was recorded
+    pub fn schema_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(1)
+    }
+
+    /// Convert the stored data to the layout of this code.
+    ///
+    /// Layout 1 kept every group in instance storage under an id its creator
+    /// chose. Neither those ids nor the members of a group can be found in
+    /// storage, so `members` maps the id of every group to the identity
+    /// commitments of its members, as the member events list them. The id
+    /// counter moves past them so `create_group` never hands one out again
+    pub fn migrate(env: Env, members: Map<u32, Vec<Bytes>>) -> Result<(), Error> {
+        Self::owner(env.clone()).require_auth();
+        let version = Self::schema_version(env.clone());
+        if version > STORAGE_SCHEMA_VERSION {
+            return Err(Error::UnsupportedSchemaVersion);
+        }
+
+        // A change to `DataKey` bumps `STORAGE_SCHEMA_VERSION` and adds the
+        // step converting the previous layout here
+        if version < 2 {
+            for (group_id, members) in members.iter() {
+                migrate_group(&env, group_id, &members)?;
+            }
+            let next = members.keys().iter().map(|group_id| group_id + 1).max();
+            if let Some(next) = next.filter(|next| *next > group_count(&env)) {
+                env.storage().instance().set(&DataKey::GroupCounter, &next);
+            }
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &STORAGE_SCHEMA_VERSION);
+        Ok(())
+    }
+
+    /// Stop group creation and membership changes until `unpause`. Views
+    /// keep working
+    pub fn pause(env: Env) {
+        let owner = Self::owner(env.clone());
+        owner.require_auth();
+        env.storage().instance().set(&DataKey::Paused, &true);
+        Paused {
+            by: owner,
+            timestamp: env.ledger().timestamp(),
+        }
+        .publish(&env);
+    }
+
+    pub fn unpause(env: Env) {
+        let owner = Self::owner(env.clone());
+        owner.require_auth();
+        env.storage().instance().remove(&DataKey::Paused);
+        Unpaused {
+            by: owner,
+            timestamp: env.ledger().timestamp(),
+        }
+        .publish(&env);
+    }
+
+    pub fn is_paused(env: Env) -> bool {
+        env.storage().instance().has(&DataKey::Paused)
+    }
+
+    /// Restrict group creation to addresses added with `set_group_creator`
+    pub fn set_creator_allowlist(env: Env, enabled: bool) {
+        Self::owner(env.clone()).require_auth();
+        if enabled {
+            env.storage()
+                .instance()
+                .set(&DataKey::CreatorAllowlist, &true);
+        } else {
+            env.storage().instance().remove(&DataKey::CreatorAllowlist);
+        }
+    }
+
+    pub fn has_creator_allowlist(env: Env) -> bool {
+        env.storage().instance().has(&DataKey::CreatorAllowlist)
+    }
+
+    /// Add `creator` to or remove it from the group creation allowlist
+    pub fn set_group_creator(env: Env, creator: Address, allowed: bool) {
+        Self::owner(env.clone()).require_auth();
+        let key = DataKey::GroupCreator(creator);
+        if allowed {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+    }
+
+    pub fn is_group_creator(env: Env, creator: Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&DataKey::GroupCreator(creator))
+    }
+
+    /// Charge `fee` for every new group, or make groups free again with `None`
+    pub fn set_creation_fee(env: Env, fee: Option<CreationFee>) -> Result<(), Error> {
+        Self::owner(env.clone()).require_auth();
+        match fee {
+            Some(fee) if fee.amount <= 0 => return Err(Error::InvalidCreationFee),
+            Some(fee) => env.storage().instance().set(&DataKey::CreationFee, &fee),
+            None => env.storage().instance().remove(&DataKey::CreationFee),
+        }
+        Ok(())
+    }
+
+    pub fn get_creation_fee(env: Env) -> Option<CreationFee> {
+        env.storage().instance().get(&DataKey::CreationFee)
+    }
+}
+
+pub(crate) fn require_not_paused(env: &Env) -> Result<(), Error> {
+    if SemaphoreGroupContract::is_paused(env.clone()) {
+        return Err(Error::ContractPaused);
+    }
+    Ok(())
+}
+
+/// Apply the allowlist and the fee to a new group's admin, who must sign for
+/// the group once either is set
+pub(crate) fn authorize_group_creation(env: &Env, admin: &Address) -> Result<(), Error> {
+    let allowlist = SemaphoreGroupContract::has_creator_allowlist(env.clone());
+    let fee = SemaphoreGroupContract::get_creation_fee(env.clone());
+    if !allowlist && fee.is_none() {
+        return Ok(());
+    }
+
+    admin.require_auth();
+    if allowlist && !SemaphoreGroupContract::is_group_creator(env.clone(), admin.clone()) {
+        return Err(Error::CallerIsNotAGroupCreator);
+    }
+    if let Some(fee) = fee {
+        token::Client::new(env, &fee.token).transfer(admin, &fee.recipient, &fee.amount);
+    }
+    Ok(())
+}
+
+fn group_count(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::GroupCounter)
+        .unwrap_or(0)
+}
+
+/// 1 to 2: a group's entries move out of instance storage into persistent
+/// entries of their own, and its tree is rebuilt from `members`. Layout 1
+/// counted the root as one of the tree's levels, so trees had half the
+/// leaves their depth promises and proofs one sibling short of a Semaphore
+/// proof, and it left the leaves of updated and removed members in place.
+/// The rebuilt tree has `DEFAULT_DEPTH` levels below its root and holds each
+/// member at its index, which changes its root
+fn migrate_group(env: &Env, group_id: u32, members: &Vec<Bytes>) -> Result<(), Error> {
+    let instance = env.storage().instance();
+    let key = DataKey::Group(group_id);
+    if !instance.has(&key) {
+        return Err(Error::GroupDoesNotExist);
+    }
+    instance.remove(&key);
+    for key in [DataKey::Admin(group_id), DataKey::PendingAdmin(group_id)] {
+        if let Some(admin) = instance.get::<_, Address>(&key) {
+            storage::set(env, &key, &admin);
+            instance.remove(&key);
+        }
+    }
+    let key = DataKey::MemberCount(group_id);
+    let member_count: u32 = instance.get(&key).unwrap_or(0);
+    storage::set(env, &key, &member_count);
+    instance.remove(&key);
+
+    // Layout 1 handed the last member's index out again after a removal, so
+    // a member may sit past the count
+    let mut leaf_count = member_count;
+    let mut placed = Vec::new(env);
+    for identity_commitment in members.iter() {
+        let key = DataKey::Member(group_id, identity_commitment.clone());
+        let member: Member = instance.get(&key).ok_or(Error::MemberDoesNotExist)?;
+        leaf_count = leaf_count.max(member.index + 1);
+        placed.push_back((member.index, imt::hash_to_g1(env, identity_commitment)));
+        storage::set(env, &key, &member);
+        instance.remove(&key);
+    }
+
+    let empty_leaf = Bytes::from_slice(env, &EMPTY_LEAF);
+    let mut leaves = Vec::new(env);
+    for _ in 0..leaf_count {
+        leaves.push_back(empty_leaf.clone());
+    }
+    for (index, leaf) in placed.iter() {
+        leaves.set(index, leaf);
+    }
+    let mut merkle_tree = MerkleTree::new(env, DEFAULT_DEPTH, empty_leaf);
+    merkle_tree.add_leaves(env, 0, &leaves);
+    let admin = storage::get(env, &DataKey::Admin(group_id)).unwrap();
+    let group = Group {
+        id: group_id,
+        admin,
+        merkle_tree,
+    };
+    storage::set(env, &DataKey::Group(group_id), &group);
+    storage::set(env, &DataKey::NextIndex(group_id), &leaf_count);
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use core::fmt::Debug;
-use soroban_sdk::{contracttype, Bytes, Env, Vec};
+use soroban_sdk::{contracttype, Bytes, BytesN, Env, Vec};
 
-use crate::imt::hash_node;
+use crate::hash::Hasher;
+use crate::indexed::leaf;
+use crate::smt::{bit, leaf_hash, KEY_BITS};
+use crate::EMPTY_LEAF;
 
 /// Merkle proof path, bottom to top
 #[contracttype]
@@ -9,32 +12,188 @@ use crate::imt::hash_node;
 pub struct Proof(pub Vec<Branch>);
 
 impl Proof {
-    /// Compute the leaf index for this proof
-    pub fn leaf_index(&self) -> usize {
-        self.0.iter().rev().fold(0, |index, branch| match branch {
-            Branch::Left(_) => index << 1,
-            Branch::Right(_) => (index << 1) + 1,
+    /// Compute the leaf index for this proof, `None` when it has more
+    /// branches than an index holds
+    pub fn leaf_index(&self) -> Option<usize> {
+        self.0.iter().rev().try_fold(0usize, |index, branch| {
+            let index = index.checked_mul(2)?;
+            Some(match branch {
+                Branch::Left(_) => index + 1,
+                Branch::Right(_) => index,
+            })
         })
     }
 
     /// Compute the Merkle root given a leaf hash
-    pub fn root(&self, env: &Env, hash: &Bytes) -> Bytes {
+    pub fn root(&self, env: &Env, hasher: &impl Hasher, hash: &Bytes) -> Bytes {
         self.0
             .iter()
             .fold(hash.clone(), |hash, branch| match branch {
-                Branch::Left(sibling) => hash_node(env, &sibling, &hash),
-                Branch::Right(sibling) => hash_node(env, &hash, &sibling),
+                Branch::Left(sibling) => hasher.hash_node(env, &sibling, &hash),
+                Branch::Right(sibling) => hasher.hash_node(env, &hash, &sibling),
             })
     }
 }
 
+/// Merkle proof for several leaves at once. A sibling is left out when it
+/// is on the path of another proven leaf, so shared parts of the paths are
+/// only sent once
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Multiproof {
+    /// Proven leaf indices, strictly ascending
+    pub indices: Vec<u32>,
+    /// Sibling hashes that cannot be computed from the leaves, bottom level
+    /// first and left to right within a level
+    pub siblings: Vec<Bytes>,
+}
+
+impl Multiproof {
+    /// Compute the Merkle root of a tree with `levels` levels above the
+    /// leaves, given the leaf hashes in the order of `indices`. `None` when
+    /// the proof does not fit the leaves
+    pub fn root(
+        &self,
+        env: &Env,
+        hasher: &impl Hasher,
+        levels: u32,
+        leaves: &Vec<Bytes>,
+    ) -> Option<Bytes> {
+        if self.indices.is_empty() || self.indices.len() != leaves.len() {
+            return None;
+        }
+
+        // (position within the level, hash), ascending by position
+        let mut nodes = Vec::new(env);
+        let mut previous = None;
+        for (index, leaf) in self.indices.iter().zip(leaves.iter()) {
+            if previous.is_some_and(|previous| previous >= index)
+                || levels < 32 && index >> levels != 0
+            {
+                return None;
+            }
+            nodes.push_back((index, leaf));
+            previous = Some(index);
+        }
+
+        let mut siblings = self.siblings.iter();
+        for _ in 0..levels {
+            let mut parents = Vec::new(env);
+            let mut i = 0;
+            while i < nodes.len() {
+                let (position, hash) = nodes.get_unchecked(i);
+                let next = nodes.get(i + 1);
+                let parent = match next {
+                    // both children are known
+                    Some((next_position, next_hash))
+                        if position & 1 == 0 && next_position == position + 1 =>
+                    {
+                        i += 1;
+                        hasher.hash_node(env, &hash, &next_hash)
+                    }
+                    _ => {
+                        let sibling = siblings.next()?;
+                        match position & 1 {
+                            0 => hasher.hash_node(env, &hash, &sibling),
+                            _ => hasher.hash_node(env, &sibling, &hash),
+                        }
+                    }
+                };
+                parents.push_back((position >> 1, parent));
+                i += 1;
+            }
+            nodes = parents;
+        }
+
+        if siblings.next().is_some() {
+            return None;
+        }
+        nodes.first().map(|(_, root)| root)
+    }
+}
+
+/// Proof that a key is not in an indexed tree: the path of the leaf linking
+/// the largest smaller key to the next larger one, which is zero when there
+/// is none
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct NonMembershipProof {
+    pub low_key: BytesN<32>,
+    pub next_key: BytesN<32>,
+    pub proof: Proof,
+}
+
+impl NonMembershipProof {
+    /// Compute the Merkle root, `None` if `key` does not fall between the
+    /// linked keys
+    pub fn root(&self, env: &Env, hasher: &impl Hasher, key: &BytesN<32>) -> Option<Bytes> {
+        let last = self.next_key.to_array() == [0; 32];
+        if self.low_key >= *key || (!last && *key >= self.next_key) {
+            return None;
+        }
+        let low_leaf = leaf(env, hasher, &self.low_key, &self.next_key);
+        Some(self.proof.root(env, hasher, &low_leaf))
+    }
+}
+
+/// Proof that a key is in a sparse Merkle tree. Empty siblings are left out
+/// and only marked in a bitmap
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseProof {
+    /// Level of the leaf holding the key, the number of levels on its path
+    pub level: u32,
+    /// Bit `l` is set when the sibling at level `l + 1` is empty, most
+    /// significant bit first
+    pub empty: BytesN<32>,
+    /// Hashes of the other siblings, from the leaf up
+    pub siblings: Vec<Bytes>,
+}
+
+impl SparseProof {
+    /// Compute the Merkle root given the key, `None` when the proof does not
+    /// fit the key
+    pub fn root(&self, env: &Env, hasher: &impl Hasher, key: &BytesN<32>) -> Option<Bytes> {
+        if self.level > KEY_BITS {
+            return None;
+        }
+        let mut siblings = self.siblings.iter();
+        let mut hash = leaf_hash(env, hasher, key);
+        for level in (1..=self.level).rev() {
+            let sibling = match bit(&self.empty, level - 1) {
+                true => Bytes::from_array(env, &EMPTY_LEAF),
+                false => siblings.next()?,
+            };
+            hash = match bit(key, level - 1) {
+                false => hasher.hash_node(env, &hash, &sibling),
+                true => hasher.hash_node(env, &sibling, &hash),
+            };
+        }
+        if siblings.next().is_some() {
+            return None;
+        }
+        Some(hash)
+    }
+}
+
+/// Proof that a member is in a group tree, of the shape the group's tree
+/// kind proves membership with
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum MemberProof {
+    /// Path of the member's leaf, for trees placing members by leaf index
+    Merkle(Proof),
+    /// Path to the member's key in a sparse tree
+    Sparse(SparseProof),
+}
+
 /// Element of a Merkle proof
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
 pub enum Branch {
-    /// Left branch taken, value is the right sibling hash
+    /// Sibling on the left, the path goes through the right child
     Left(Bytes),
-    /// Right branch taken, value is the left sibling hash
+    /// Sibling on the right, the path goes through the left child
     Right(Bytes),
 }
 
@@ -0,0 +1,348 @@
+//! Sparse Merkle tree addressed by key: the path to a member is the bits of
+//! its key, so members do not depend on the order they were added in and
+//! removals leave no holes.
+//!
+//! Keys are the 256-bit keccak256 hashes used by indexed trees. A subtree
+//! holding a single key is stored as a leaf at the top of that subtree
+//! instead of at level 256, so paths are about as long as the log of the
+//! number of members. Empty subtrees hash to `EMPTY_LEAF` at every level.
+
+use crate::datatypes::HashFunction;
+use crate::hash::Hasher;
+use crate::proof::SparseProof;
+use crate::EMPTY_LEAF;
+use soroban_sdk::{contracttype, Bytes, BytesN, Env, Map, Vec};
+
+/// Number of key bits, the deepest level a leaf can be at
+pub const KEY_BITS: u32 = 256;
+
+/// Non-empty subtree
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum SparseNode {
+    /// Subtree holding a single key
+    Leaf(BytesN<32>),
+    /// Hash of a subtree holding several keys
+    Internal(Bytes),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseMerkleTree {
+    /// Hash function combining nodes
+    hash: HashFunction,
+    /// Non-empty subtrees by level and the key bits leading to them, with
+    /// the bits below the level cleared
+    nodes: Map<(u32, BytesN<32>), SparseNode>,
+}
+
+/// Bit `level` of `bits`, most significant first
+pub fn bit(bits: &BytesN<32>, level: u32) -> bool {
+    bits.get(level / 8).unwrap() >> (7 - level % 8) & 1 == 1
+}
+
+/// Hash of a subtree holding only `key`
+pub fn leaf_hash(env: &Env, hasher: &impl Hasher, key: &BytesN<32>) -> Bytes {
+    // one byte longer than an inner node, so neither can pass for the other
+    let mut leaf = Bytes::from_array(env, &[0]);
+    leaf.append(key.as_ref());
+    hasher.hash(env, &leaf)
+}
+
+/// Position of the node at `level` on the path to `key`
+fn position(key: &BytesN<32>, level: u32) -> BytesN<32> {
+    let mut bits = key.to_array();
+    for (i, byte) in bits.iter_mut().enumerate() {
+        let start = i as u32 * 8;
+        if start + 8 <= level {
+            continue;
+        }
+        *byte &= match level.checked_sub(start) {
+            Some(kept) => !(0xffu8 >> kept),
+            None => 0,
+        };
+    }
+    BytesN::from_array(key.env(), &bits)
+}
+
+/// Position of the sibling of the node at `level` on the path to `key`
+fn sibling(key: &BytesN<32>, level: u32) -> BytesN<32> {
+    let mut bits = position(key, level).to_array();
+    let flipped = level - 1;
+    bits[flipped as usize / 8] ^= 0x80 >> (flipped % 8);
+    BytesN::from_array(key.env(), &bits)
+}
+
+impl SparseMerkleTree {
+    pub fn new(env: &Env) -> Self {
+        Self::with_hash(env, HashFunction::Keccak256)
+    }
+
+    /// Empty tree combining its nodes with `hash`
+    pub fn with_hash(env: &Env, hash: HashFunction) -> Self {
+        Self {
+            hash,
+            nodes: Map::new(env),
+        }
+    }
+
+    pub fn hash_function(&self) -> HashFunction {
+        self.hash
+    }
+
+    pub fn get_root(&self) -> Bytes {
+        self.node_hash(0, &BytesN::from_array(self.nodes.env(), &[0; 32]))
+    }
+
+    /// Whether `key` is in the tree
+    pub fn contains(&self, key: &BytesN<32>) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Add `key`. `false` if it is already in the tree
+    pub fn insert(&mut self, env: &Env, key: &BytesN<32>) -> bool {
+        let mut level = 0;
+        loop {
+            match self.node(level, key) {
+                None => break,
+                Some(SparseNode::Internal(_)) => level += 1,
+                Some(SparseNode::Leaf(other)) if other == *key => return false,
+                Some(SparseNode::Leaf(other)) => {
+                    // move the other key down to where the paths part
+                    while bit(&other, level) == bit(key, level) {
+                        level += 1;
+                    }
+                    level += 1;
+                    self.nodes
+                        .set((level, position(&other, level)), SparseNode::Leaf(other));
+                    break;
+                }
+            }
+        }
+        self.nodes
+            .set((level, position(key, level)), SparseNode::Leaf(key.clone()));
+        self.rehash(env, key, level);
+        true
+    }
+
+    /// Remove `key`. `false` if it is not in the tree
+    pub fn remove(&mut self, env: &Env, key: &BytesN<32>) -> bool {
+        let Some(mut level) = self.find(key) else {
+            return false;
+        };
+        self.nodes.remove((level, position(key, level)));
+
+        // a key left alone in its subtree moves up to the top of it
+        while level > 0 {
+            let lifted = match (self.node(level, key), self.sibling(level, key)) {
+                (None, Some(SparseNode::Leaf(other))) | (Some(SparseNode::Leaf(other)), None) => {
+                    other
+                }
+                _ => break,
+            };
+            self.nodes.remove((level, position(&lifted, level)));
+            level -= 1;
+            self.nodes
+                .set((level, position(&lifted, level)), SparseNode::Leaf(lifted));
+        }
+        self.rehash(env, key, level);
+        true
+    }
+
+    /// Proof that `key` is in the tree, `None` if it is not
+    pub fn proof(&self, env: &Env, key: &BytesN<32>) -> Option<SparseProof> {
+        let level = self.find(key)?;
+        let mut empty = [0u8; 32];
+        let mut siblings = Vec::new(env);
+        for level in (1..=level).rev() {
+            match self.sibling(level, key) {
+                None => empty[(level - 1) as usize / 8] |= 0x80 >> ((level - 1) % 8),
+                Some(_) => siblings.push_back(self.node_hash(level, &sibling(key, level))),
+            }
+        }
+        Some(SparseProof {
+            level,
+            empty: BytesN::from_array(env, &empty),
+            siblings,
+        })
+    }
+
+    /// Check a proof that `key` is in the tree
+    pub fn verify_proof(&self, env: &Env, key: &BytesN<32>, proof: &SparseProof) -> bool {
+        proof.root(env, &self.hash, key) == Some(self.get_root())
+    }
+
+    /// Level of the leaf holding `key`
+    fn find(&self, key: &BytesN<32>) -> Option<u32> {
+        let mut level = 0;
+        loop {
+            match self.node(level, key)? {
+                SparseNode::Internal(_) => level += 1,
+                SparseNode::Leaf(other) => return (other == *key).then_some(level),
+            }
+        }
+    }
+
+    fn node(&self, level: u32, key: &BytesN<32>) -> Option<SparseNode> {
+        self.nodes.get((level, position(key, level)))
+    }
+
+    fn sibling(&self, level: u32, key: &BytesN<32>) -> Option<SparseNode> {
+        self.nodes.get((level, sibling(key, level)))
+    }
+
+    fn node_hash(&self, level: u32, position: &BytesN<32>) -> Bytes {
+        let env = self.nodes.env();
+        match self.nodes.get((level, position.clone())) {
+            None => Bytes::from_array(env, &EMPTY_LEAF),
+            Some(SparseNode::Leaf(key)) => leaf_hash(env, &self.hash, &key),
+            Some(SparseNode::Internal(hash)) => hash,
+        }
+    }
+
+    /// Recompute the inner nodes above `level` on the path to `key`
+    fn rehash(&mut self, env: &Env, key: &BytesN<32>, level: u32) {
+        for level in (0..level).rev() {
+            let child = position(key, level + 1);
+            let (left, right) = match bit(key, level) {
+                false => (child.clone(), sibling(key, level + 1)),
+                true => (sibling(key, level + 1), child),
+            };
+            let hash = self.hash.hash_node(
+                env,
+                &self.node_hash(level + 1, &left),
+                &self.node_hash(level + 1, &right),
+            );
+            self.nodes
+                .set((level, position(key, level)), SparseNode::Internal(hash));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::hash::Keccak256;
+    use crate::indexed::key;
+
+    fn keys(env: &Env, count: u8) -> std::vec::Vec<BytesN<32>> {
+        (0..count)
+            .map(|seed| key(env, &Bytes::from_array(env, &[seed])))
+            .collect()
+    }
+
+    /// Root of a tree holding `keys`, computed from scratch
+    fn reference_root(env: &Env, keys: &[BytesN<32>], level: u32) -> Bytes {
+        match keys {
+            [] => Bytes::from_array(env, &EMPTY_LEAF),
+            [key] => leaf_hash(env, &Keccak256, key),
+            _ => {
+                let (left, right): (std::vec::Vec<_>, std::vec::Vec<_>) =
+                    keys.iter().cloned().partition(|key| !bit(key, level));
+                Keccak256.hash_node(
+                    env,
+                    &reference_root(env, &left, level + 1),
+                    &reference_root(env, &right, level + 1),
+                )
+            }
+        }
+    }
+
+    #[test]
+    fn test_bits() {
+        let env = Env::default();
+        let mut bits = [0u8; 32];
+        bits[0] = 0b1010_0000;
+        bits[31] = 1;
+        let bits = BytesN::from_array(&env, &bits);
+        assert!(bit(&bits, 0) && !bit(&bits, 1) && bit(&bits, 2) && bit(&bits, 255));
+        assert_eq!(position(&bits, 0), BytesN::from_array(&env, &[0; 32]));
+        assert_eq!(position(&bits, 1).get(0), Some(0b1000_0000));
+        assert_eq!(position(&bits, 256), bits);
+        assert_eq!(sibling(&bits, 2).get(0), Some(0b1100_0000));
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let env = Env::default();
+        let mut tree = SparseMerkleTree::new(&env);
+        let keys = keys(&env, 16);
+        assert_eq!(tree.get_root(), Bytes::from_array(&env, &EMPTY_LEAF));
+
+        for (count, key) in keys.iter().enumerate() {
+            assert!(tree.insert(&env, key));
+            assert_eq!(tree.get_root(), reference_root(&env, &keys[..=count], 0));
+        }
+        assert!(!tree.insert(&env, &keys[0]));
+
+        // removals in another order than the insertions
+        let mut left = keys.clone();
+        for i in (0..keys.len()).step_by(2).chain((1..keys.len()).step_by(2)) {
+            left.retain(|key| *key != keys[i]);
+            assert!(tree.remove(&env, &keys[i]));
+            assert!(!tree.contains(&keys[i]));
+            assert_eq!(tree.get_root(), reference_root(&env, &left, 0));
+        }
+        assert!(!tree.remove(&env, &keys[0]));
+
+        // nothing is left behind
+        assert_eq!(tree, SparseMerkleTree::new(&env));
+    }
+
+    #[test]
+    fn test_proof_and_verify() {
+        let env = Env::default();
+        let mut tree = SparseMerkleTree::new(&env);
+        let keys = keys(&env, 16);
+        for key in keys.iter().take(12) {
+            tree.insert(&env, key);
+        }
+
+        for key in keys.iter().take(12) {
+            let proof = tree.proof(&env, key).unwrap();
+            // empty siblings are left out
+            let empty = (0..proof.level)
+                .filter(|level| bit(&proof.empty, *level))
+                .count();
+            assert_eq!(proof.siblings.len() as usize + empty, proof.level as usize);
+            assert!(tree.verify_proof(&env, key, &proof));
+            for other in keys.iter().filter(|other| *other != key) {
+                assert!(!tree.verify_proof(&env, other, &proof));
+            }
+        }
+        for key in keys.iter().skip(12) {
+            assert_eq!(tree.proof(&env, key), None);
+        }
+
+        // a proof with a sibling too many or too few does not verify
+        let mut proof = tree.proof(&env, &keys[0]).unwrap();
+        proof
+            .siblings
+            .push_back(Bytes::from_array(&env, &EMPTY_LEAF));
+        assert!(!tree.verify_proof(&env, &keys[0], &proof));
+        proof.siblings.pop_back();
+        proof.siblings.pop_back();
+        assert!(!tree.verify_proof(&env, &keys[0], &proof));
+    }
+
+    #[test]
+    fn test_sha256() {
+        let env = Env::default();
+        let mut keccak = SparseMerkleTree::new(&env);
+        let mut sha256 = SparseMerkleTree::with_hash(&env, HashFunction::Sha256);
+        let keys = keys(&env, 4);
+        for key in keys.iter() {
+            keccak.insert(&env, key);
+            sha256.insert(&env, key);
+        }
+        assert_ne!(keccak.get_root(), sha256.get_root());
+
+        for key in keys.iter() {
+            let proof = sha256.proof(&env, key).unwrap();
+            assert!(sha256.verify_proof(&env, key, &proof));
+            assert!(!keccak.verify_proof(&env, key, &proof));
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Entries belonging to a single group: its admin, members, tree and
+//! settings. They live in persistent storage, one entry each, rather than in
+//! the contract instance that every call loads in full, and every read or
+//! write extends their time to live. Contract-wide settings stay in the
+//! instance.
+
+use crate::datatypes::DataKey;
+use soroban_sdk::{Env, IntoVal, TryFromVal, Val};
+
+/// Ledgers in a day, at five seconds a ledger
+pub const DAY_IN_LEDGERS: u32 = 17_280;
+
+/// An entry used with fewer ledgers than this left to live is extended
+pub const GROUP_TTL_THRESHOLD: u32 = 30 * DAY_IN_LEDGERS;
+
+/// Ledgers an extended entry lives for
+pub const GROUP_TTL: u32 = 60 * DAY_IN_LEDGERS;
+
+/// Every entry of a group apart from its members and status
+pub(crate) fn group_keys(group_id: u32) -> [DataKey; 9] {
+    [
+        DataKey::Admin(group_id),
+        DataKey::PendingAdmin(group_id),
+        DataKey::PendingAdminExpiration(group_id),
+        DataKey::MemberCount(group_id),
+        DataKey::Group(group_id),
+        DataKey::Metadata(group_id),
+        DataKey::NextIndex(group_id),
+        DataKey::SortedLeaves(group_id),
+        DataKey::SparseTree(group_id),
+    ]
+}
+
+fn extend(env: &Env, key: &DataKey) {
+    env.storage()
+        .persistent()
+        .extend_ttl(key, GROUP_TTL_THRESHOLD, GROUP_TTL);
+}
+
+pub(crate) fn get<V: TryFromVal<Env, Val>>(env: &Env, key: &DataKey) -> Option<V> {
+    let value = env.storage().persistent().get(key);
+    if value.is_some() {
+        extend(env, key);
+    }
+    value
+}
+
+pub(crate) fn has(env: &Env, key: &DataKey) -> bool {
+    env.storage().persistent().has(key)
+}
+
+pub(crate) fn set<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    env.storage().persistent().set(key, value);
+    extend(env, key);
+}
+
+pub(crate) fn remove(env: &Env, key: &DataKey) {
+    env.storage().persistent().remove(key);
+}
@@ -2,55 +2,149 @@
 extern crate std;
 
 use super::*;
+use datatypes::{CreationFee, GroupInfo, GroupMetadata, GroupStatus, HashFunction, TreeKind};
+use imt::MerkleTree;
+use indexed::SortedLeaves;
+use smt::SparseMerkleTree;
+use events::{GroupAdminRenounced, GroupAdminTransferCancelled, GroupArchived, GroupDeleted, GroupEvent, Paused, Unpaused, SCHEMA_VERSION};
 use soroban_sdk::{
-    testutils::{Address as _, Events, AuthorizedFunction, AuthorizedInvocation},
-    vec, Env, IntoVal, Bytes
+    xdr::ScErrorType,
+    testutils::{storage::Persistent as _, Address as _, Events, AuthorizedFunction, AuthorizedInvocation, Ledger},
+    token, vec, BytesN, Env, String, IntoVal, Bytes, Symbol, Val
 };
 
-const GROUP_ID: u32 = 1;
+/// Id of the first group created
+const GROUP_ID: u32 = 0;
+
+/// Last ledger on which admin transfers started by the tests can be accepted
+const EXPIRATION_LEDGER: u32 = 100;
+
+fn event<E: GroupEvent>(env: &Env, contract_id: &Address, event: E) -> (Address, Vec<Val>, Val) {
+    (
+        contract_id.clone(),
+        (Symbol::new(env, E::NAME), SCHEMA_VERSION, event.group_id()).into_val(env),
+        event.into_val(env),
+    )
+}
+
+/// Store a group as layout 1, that of the original contract, did: in
+/// instance storage under an id its creator chose, with its members at the
+/// indices they were added at. The tree is not read, the migration rebuilds
+/// it from the members
+fn layout_1_group(env: &Env, group_id: u32, admin: &Address, members: &[&Bytes]) {
+    let storage = env.storage().instance();
+    storage.set(&DataKey::Admin(group_id), admin);
+    storage.set(&DataKey::MemberCount(group_id), &(members.len() as u32));
+    for (index, identity_commitment) in members.iter().enumerate() {
+        let member = Member { identity_commitment: (*identity_commitment).clone(), group_id, index: index as u32 };
+        storage.set(&DataKey::Member(group_id, (*identity_commitment).clone()), &member);
+    }
+    storage.set(&DataKey::Group(group_id), &Group { id: group_id, admin: admin.clone(), merkle_tree: empty_tree(env) });
+    storage.remove(&DataKey::SchemaVersion);
+}
+
+fn empty_tree(env: &Env) -> MerkleTree {
+    MerkleTree::new(env, DEFAULT_DEPTH, Bytes::from_slice(env, &EMPTY_LEAF))
+}
 
 fn member_identity_commitment(env: &Env, items: &[u8]) -> Bytes {
     // Create identity commitments using BLS12-381
     let bls12_381 = env.crypto().bls12_381();
-    let dst = Bytes::from_slice(&env, b"SEMAPHORE_IDENTITY");
+    let dst = Bytes::from_slice(env, b"SEMAPHORE_IDENTITY");
     // Member creates identity commitment
-    let secret = Bytes::from_slice(&env, items);
+    let secret = Bytes::from_slice(env, items);
     let commitment = bls12_381.hash_to_g1(&secret, &dst);
     // Convert commitments to Bytes
-    Bytes::from_slice(&env, &commitment.to_array())
+    Bytes::from_slice(env, &commitment.to_array())
+}
+
+#[test]
+fn test_create_group_assigns_ids() {
+    let env = Env::default();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    assert_eq!(client.create_group(&admin, &None), GROUP_ID);
+    assert_eq!(client.create_group(&admin, &None), GROUP_ID + 1);
+    assert_eq!(client.get_group_admin(&(GROUP_ID + 1)), admin);
+}
+
+#[test]
+fn test_create_group_skips_taken_ids() {
+    let env = Env::default();
+    env.budget().reset_unlimited();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+
+    // Layout 1 groups not yet migrated still hold their ids
+    let member = member_identity_commitment(&env, b"member1_secret");
+    env.as_contract(&contract_id, || {
+        layout_1_group(&env, GROUP_ID, &admin, &[&member]);
+        layout_1_group(&env, GROUP_ID + 1, &admin, &[]);
+    });
+    assert_eq!(client.create_group(&admin, &None), GROUP_ID + 2);
+    assert_eq!(client.create_group(&admin, &None), GROUP_ID + 3);
 }
 
 #[test]
-fn test_create_group_group_already_exists() {
+fn test_get_group() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
-    assert_eq!(client.try_create_group(&GROUP_ID, &admin), Err(Ok(Error::GroupAlreadyExists)));
+
+    let metadata = GroupMetadata {
+        name: Some(String::from_str(&env, "Voters")),
+        description_uri: Some(String::from_str(&env, "ipfs://voters")),
+        depth: Some(4),
+        tree: TreeKind::Incremental,
+        hash: HashFunction::Keccak256,
+    };
+    let group_id = client.create_group(&admin, &Some(metadata));
+    client.add_member(&group_id, &member_identity_commitment(&env, b"member1_secret"));
+    assert_eq!(
+        client.get_group(&group_id),
+        GroupInfo {
+            id: group_id,
+            admin: admin.clone(),
+            name: Some(String::from_str(&env, "Voters")),
+            description_uri: Some(String::from_str(&env, "ipfs://voters")),
+            depth: 4,
+            root: client.get_merkle_root(&group_id),
+            size: 1,
+            status: GroupStatus::Active,
+            tree: TreeKind::Incremental,
+            hash: HashFunction::Keccak256,
+        }
+    );
+
+    let group_id = client.create_group(&admin, &None);
+    let info = client.get_group(&group_id);
+    assert_eq!((info.name, info.depth), (None, DEFAULT_DEPTH));
+    assert_eq!(info.root, empty_tree(&env).get_root());
+    assert_eq!(info.size, 0);
+
+    assert_eq!(client.try_get_group(&(group_id + 1)), Err(Ok(Error::GroupDoesNotExist)));
+    let too_deep = GroupMetadata { name: None, description_uri: None, depth: Some(MAX_DEPTH + 1), tree: TreeKind::Incremental, hash: HashFunction::Keccak256 };
+    assert_eq!(client.try_create_group(&admin, &Some(too_deep)), Err(Ok(Error::InvalidTreeDepth)));
 }
 
 #[test]
 fn test_create_group() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
+    let empty_root = empty_tree(&env).get_root();
     assert_eq!(
         env.events().all(),
         vec![
             &env,
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_created"), GROUP_ID).into_val(&env),
-                GROUP_ID.into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_admin_updated"), GROUP_ID).into_val(&env),
-                admin.into_val(&env)
-            ),
+            event(&env, &contract_id, GroupCreated { group_id: GROUP_ID, admin: admin.clone(), depth: DEFAULT_DEPTH, root: empty_root }),
+            event(&env, &contract_id, GroupAdminUpdated { group_id: GROUP_ID, old_admin: None, new_admin: admin.clone() }),
         ]
     );
 }
@@ -58,34 +152,34 @@ fn test_create_group() {
 #[test]
 fn test_update_group_admin_group_does_not_exist() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let new_admin = Address::generate(&env);
-    assert_eq!(client.try_update_group_admin(&GROUP_ID, &new_admin), Err(Ok(Error::GroupDoesNotExist)));
+    assert_eq!(client.try_update_group_admin(&GROUP_ID, &new_admin, &EXPIRATION_LEDGER), Err(Ok(Error::GroupDoesNotExist)));
 }
 
 #[test]
 #[should_panic(expected = "HostError: Error(Auth, InvalidAction)")]
 fn test_update_group_admin_caller_not_group_admin() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let new_admin = Address::generate(&env);
-    client.update_group_admin(&GROUP_ID, &new_admin);
+    client.update_group_admin(&GROUP_ID, &new_admin, &EXPIRATION_LEDGER);
 }
 
 #[test]
 fn test_update_group_admin() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let new_admin = Address::generate(&env);
-    client.update_group_admin(&GROUP_ID, &new_admin);
+    client.update_group_admin(&GROUP_ID, &new_admin, &EXPIRATION_LEDGER);
     assert_eq!(
         env.auths(),
         std::vec![(
@@ -94,31 +188,20 @@ fn test_update_group_admin() {
                 function: AuthorizedFunction::Contract((
                     contract_id.clone(),
                     Symbol::new(&env, "update_group_admin"),
-                    (GROUP_ID, &new_admin).into_val(&env)
+                    (GROUP_ID, &new_admin, EXPIRATION_LEDGER).into_val(&env)
                 )),
                 sub_invocations: std::vec![]
             }
         )]
     );
+    let empty_root = empty_tree(&env).get_root();
     assert_eq!(
         env.events().all(),
         vec![
             &env,
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_created"), GROUP_ID).into_val(&env),
-                GROUP_ID.into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_admin_updated"), GROUP_ID).into_val(&env),
-                admin.into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_admin_pending"), GROUP_ID, &admin, &new_admin).into_val(&env),
-                ().into_val(&env)
-            )
+            event(&env, &contract_id, GroupCreated { group_id: GROUP_ID, admin: admin.clone(), depth: DEFAULT_DEPTH, root: empty_root }),
+            event(&env, &contract_id, GroupAdminUpdated { group_id: GROUP_ID, old_admin: None, new_admin: admin.clone() }),
+            event(&env, &contract_id, GroupAdminPending { group_id: GROUP_ID, old_admin: admin.clone(), new_admin: new_admin.clone(), expiration_ledger: EXPIRATION_LEDGER })
         ]
     );
 }
@@ -126,31 +209,31 @@ fn test_update_group_admin() {
 #[test]
 fn test_accept_group_admin_group_does_not_exist() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     assert_eq!(client.try_accept_group_admin(&GROUP_ID), Err(Ok(Error::GroupDoesNotExist)));
 }
 
 #[test]
-fn test_accept_group_admin_caller_is_not_the_pending_group_admin() {
+fn test_accept_group_admin_no_pending_admin() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
-    assert_eq!(client.try_accept_group_admin(&GROUP_ID), Err(Ok(Error::CallerIsNotThePendingGroupAdmin)));
+    client.create_group(&admin, &None);
+    assert_eq!(client.try_accept_group_admin(&GROUP_ID), Err(Ok(Error::NoPendingAdmin)));
 }
 
 #[test]
 #[should_panic(expected = "HostError: Error(Auth, InvalidAction)")]
 fn test_accept_group_admin_caller_not_pending_admin() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let new_admin = Address::generate(&env);
-    client.update_group_admin(&GROUP_ID, &new_admin);
+    client.update_group_admin(&GROUP_ID, &new_admin, &EXPIRATION_LEDGER);
     client.accept_group_admin(&GROUP_ID);
 }
 
@@ -158,12 +241,12 @@ fn test_accept_group_admin_caller_not_pending_admin() {
 fn test_accept_group_admin() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let new_admin = Address::generate(&env);
-    client.update_group_admin(&GROUP_ID, &new_admin);
+    client.update_group_admin(&GROUP_ID, &new_admin, &EXPIRATION_LEDGER);
     client.accept_group_admin(&GROUP_ID);
     assert_eq!(
         env.auths(),
@@ -179,59 +262,144 @@ fn test_accept_group_admin() {
             }
         )]
     );
+    let empty_root = empty_tree(&env).get_root();
     assert_eq!(
         env.events().all(),
         vec![
             &env,
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_created"), GROUP_ID).into_val(&env),
-                GROUP_ID.into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_admin_updated"), GROUP_ID).into_val(&env),
-                admin.into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_admin_pending"), GROUP_ID, &admin, &new_admin).into_val(&env),
-                ().into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_admin_updated"), GROUP_ID, &admin, &new_admin).into_val(&env),
-                ().into_val(&env)
-            ),
+            event(&env, &contract_id, GroupCreated { group_id: GROUP_ID, admin: admin.clone(), depth: DEFAULT_DEPTH, root: empty_root }),
+            event(&env, &contract_id, GroupAdminUpdated { group_id: GROUP_ID, old_admin: None, new_admin: admin.clone() }),
+            event(&env, &contract_id, GroupAdminPending { group_id: GROUP_ID, old_admin: admin.clone(), new_admin: new_admin.clone(), expiration_ledger: EXPIRATION_LEDGER }),
+            event(&env, &contract_id, GroupAdminUpdated { group_id: GROUP_ID, old_admin: Some(admin.clone()), new_admin: new_admin.clone() }),
         ]
     );
 }
 
 #[test]
-fn test_get_pending_admin_caller_is_not_the_pending_group_admin() {
+fn test_get_pending_admin_no_pending_admin() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
-    assert_eq!(client.try_get_pending_admin(&GROUP_ID), Err(Ok(Error::CallerIsNotThePendingGroupAdmin)));
+    assert_eq!(client.try_get_pending_admin(&GROUP_ID), Err(Ok(Error::NoPendingAdmin)));
 }
 
 #[test]
 fn test_get_pending_admin() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let new_admin = Address::generate(&env);
-    client.update_group_admin(&GROUP_ID, &new_admin);
+    client.update_group_admin(&GROUP_ID, &new_admin, &EXPIRATION_LEDGER);
     assert_eq!(client.get_pending_admin(&GROUP_ID), new_admin);
 }
 
+#[test]
+fn test_update_group_admin_invalid_expiration_ledger() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    client.create_group(&Address::generate(&env), &None);
+    env.ledger().set_sequence_number(EXPIRATION_LEDGER);
+    let new_admin = Address::generate(&env);
+    assert_eq!(client.try_update_group_admin(&GROUP_ID, &new_admin, &EXPIRATION_LEDGER), Err(Ok(Error::InvalidExpirationLedger)));
+}
+
+#[test]
+fn test_accept_group_admin_expired() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.create_group(&admin, &None);
+    let new_admin = Address::generate(&env);
+    client.update_group_admin(&GROUP_ID, &new_admin, &EXPIRATION_LEDGER);
+
+    env.ledger().set_sequence_number(EXPIRATION_LEDGER + 1);
+    assert_eq!(client.try_get_pending_admin(&GROUP_ID), Err(Ok(Error::AdminTransferExpired)));
+    assert_eq!(client.try_accept_group_admin(&GROUP_ID), Err(Ok(Error::AdminTransferExpired)));
+    assert_eq!(client.get_group_admin(&GROUP_ID), admin);
+
+    // a new transfer replaces the expired one
+    client.update_group_admin(&GROUP_ID, &new_admin, &(EXPIRATION_LEDGER + 10));
+    client.accept_group_admin(&GROUP_ID);
+    assert_eq!(client.get_group_admin(&GROUP_ID), new_admin);
+}
+
+#[test]
+fn test_cancel_admin_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.create_group(&admin, &None);
+    assert_eq!(client.try_cancel_admin_transfer(&GROUP_ID), Err(Ok(Error::NoPendingAdmin)));
+
+    let new_admin = Address::generate(&env);
+    client.update_group_admin(&GROUP_ID, &new_admin, &EXPIRATION_LEDGER);
+    client.cancel_admin_transfer(&GROUP_ID);
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    contract_id.clone(),
+                    Symbol::new(&env, "cancel_admin_transfer"),
+                    (GROUP_ID,).into_val(&env)
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
+        vec![&env, event(&env, &contract_id, GroupAdminTransferCancelled { group_id: GROUP_ID, admin: admin.clone(), pending_admin: new_admin })]
+    );
+    assert_eq!(client.try_get_pending_admin(&GROUP_ID), Err(Ok(Error::NoPendingAdmin)));
+    assert_eq!(client.try_accept_group_admin(&GROUP_ID), Err(Ok(Error::NoPendingAdmin)));
+}
+
+#[test]
+fn test_renounce_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.create_group(&admin, &None);
+    let member = member_identity_commitment(&env, &[1, 2, 3]);
+    client.add_member(&GROUP_ID, &member);
+    client.update_group_admin(&GROUP_ID, &Address::generate(&env), &EXPIRATION_LEDGER);
+
+    client.renounce_admin(&GROUP_ID);
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
+        vec![&env, event(&env, &contract_id, GroupAdminRenounced { group_id: GROUP_ID, old_admin: admin })]
+    );
+    assert_eq!(client.get_group_status(&GROUP_ID), GroupStatus::Immutable);
+    assert_eq!(client.try_accept_group_admin(&GROUP_ID), Err(Ok(Error::NoPendingAdmin)));
+    assert_eq!(client.try_add_member(&GROUP_ID, &member_identity_commitment(&env, &[4])), Err(Ok(Error::GroupIsImmutable)));
+    assert_eq!(client.try_remove_member(&GROUP_ID, &member), Err(Ok(Error::GroupIsImmutable)));
+    assert_eq!(client.try_update_group_admin(&GROUP_ID, &Address::generate(&env), &EXPIRATION_LEDGER), Err(Ok(Error::GroupIsImmutable)));
+    assert_eq!(client.try_archive_group(&GROUP_ID), Err(Ok(Error::GroupIsImmutable)));
+    assert_eq!(client.try_renounce_admin(&GROUP_ID), Err(Ok(Error::GroupIsImmutable)));
+
+    // members can still prove membership
+    let leaf = empty_tree(&env).hash_to_g1(&env, member);
+    let proof = client.get_proof(&GROUP_ID, &0);
+    assert!(client.verify_proof(&GROUP_ID, &leaf, &proof));
+}
+
 #[test]
 fn test_add_member_invalid_identity_commitment() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     assert_eq!(client.try_add_member(&GROUP_ID, &Bytes::new(&env)), Err(Ok(Error::InvalidIdentityCommitment)));
 }
@@ -239,7 +407,7 @@ fn test_add_member_invalid_identity_commitment() {
 #[test]
 fn test_add_member_group_does_not_exist() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
     assert_eq!(client.try_add_member(&GROUP_ID, &member1_identity_commitment), Err(Ok(Error::GroupDoesNotExist)));
@@ -249,10 +417,10 @@ fn test_add_member_group_does_not_exist() {
 #[should_panic(expected = "HostError: Error(Auth, InvalidAction)")]
 fn test_add_member_caller_not_group_admin() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
     client.add_member(&GROUP_ID, &member1_identity_commitment);
 }
@@ -261,10 +429,10 @@ fn test_add_member_caller_not_group_admin() {
 fn test_add_member_member_already_exists() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
     client.add_member(&GROUP_ID, &member1_identity_commitment);
     assert_eq!(client.try_add_member(&GROUP_ID, &member1_identity_commitment), Err(Ok(Error::MemberAlreadyExists)));
@@ -274,10 +442,10 @@ fn test_add_member_member_already_exists() {
 fn test_add_member() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
     client.add_member(&GROUP_ID, &member1_identity_commitment);
     assert_eq!(
@@ -294,25 +462,17 @@ fn test_add_member() {
             }
         )]
     );
+    let mut tree = empty_tree(&env);
+    let empty_root = tree.get_root();
+    tree.add_leaf(&env, 0, tree.hash_to_g1(&env, member1_identity_commitment.clone()));
+    let member_added_root = tree.get_root();
     assert_eq!(
         env.events().all(),
         vec![
             &env,
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_created"), GROUP_ID).into_val(&env),
-                GROUP_ID.into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_admin_updated"), GROUP_ID).into_val(&env),
-                admin.into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "MemberAdded"), GROUP_ID, &member1_identity_commitment, 0_u32).into_val(&env),
-                ().into_val(&env)
-            )
+            event(&env, &contract_id, GroupCreated { group_id: GROUP_ID, admin: admin.clone(), depth: DEFAULT_DEPTH, root: empty_root }),
+            event(&env, &contract_id, GroupAdminUpdated { group_id: GROUP_ID, old_admin: None, new_admin: admin.clone() }),
+            event(&env, &contract_id, MemberAdded { group_id: GROUP_ID, identity_commitment: member1_identity_commitment.clone(), index: 0, root: member_added_root })
         ]
     );
 }
@@ -320,7 +480,7 @@ fn test_add_member() {
 #[test]
 fn test_add_members_group_does_not_exist() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
     let member2_identity_commitment = member_identity_commitment(&env, b"member2_secret");
@@ -332,77 +492,112 @@ fn test_add_members_group_does_not_exist() {
 #[should_panic(expected = "HostError: Error(Auth, InvalidAction)")]
 fn test_add_members_caller_not_group_admin() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
     let member2_identity_commitment = member_identity_commitment(&env, b"member2_secret");
     let members = vec![&env, member1_identity_commitment, member2_identity_commitment];
     client.add_members(&GROUP_ID, &members);
 }
 
-// This test is commented out because it will fail until add_members is refactored to call
-// require_auth only once
+#[test]
+fn test_add_members_member_already_exists() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    client.create_group(&Address::generate(&env), &None);
+    let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
+    let member2_identity_commitment = member_identity_commitment(&env, b"member2_secret");
+    client.add_member(&GROUP_ID, &member1_identity_commitment);
 
-// #[test]
-// fn test_add_members() {
-//     let env = Env::default();
-//     env.mock_all_auths();
-//     let contract_id = env.register(SemaphoreGroupContract, ());
-//     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
-//     let admin = Address::generate(&env);
-//     client.create_group(&GROUP_ID, &admin);
-//     let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
-//     let member2_identity_commitment = member_identity_commitment(&env, b"member2_secret");
-//     let members = vec![&env, member1_identity_commitment.clone(), member2_identity_commitment.clone()];
-//     client.add_members(&GROUP_ID, &members);
-//     assert_eq!(
-//         env.auths(),
-//         std::vec![(
-//             admin.clone(),
-//             AuthorizedInvocation {
-//                 function: AuthorizedFunction::Contract((
-//                     contract_id.clone(),
-//                     Symbol::new(&env, "add_members"),
-//                     (GROUP_ID, members).into_val(&env)
-//                 )),
-//                 sub_invocations: std::vec![]
-//             }
-//         )]
-//     );
-//     assert_eq!(
-//         env.events().all(),
-//         vec![
-//             &env,
-//             (
-//                 contract_id.clone(),
-//                 (Symbol::new(&env, "group_created"), GROUP_ID).into_val(&env),
-//                 GROUP_ID.into_val(&env)
-//             ),
-//             (
-//                 contract_id.clone(),
-//                 (Symbol::new(&env, "group_admin_updated"), GROUP_ID).into_val(&env),
-//                 admin.into_val(&env)
-//             ),
-//             (
-//                 contract_id.clone(),
-//                 (Symbol::new(&env, "MemberAdded"), GROUP_ID, &member1_identity_commitment, 0_u32).into_val(&env),
-//                 ().into_val(&env)
-//             ),
-//             (
-//                 contract_id.clone(),
-//                 (Symbol::new(&env, "MemberAdded"), GROUP_ID, &member2_identity_commitment, 1_u32).into_val(&env),
-//                 ().into_val(&env)
-//             )
-//         ]
-//     );
-// }
+    let members = vec![&env, member2_identity_commitment.clone(), member1_identity_commitment];
+    assert_eq!(client.try_add_members(&GROUP_ID, &members), Err(Ok(Error::MemberAlreadyExists)));
+    let members = vec![&env, member2_identity_commitment.clone(), member2_identity_commitment.clone()];
+    assert_eq!(client.try_add_members(&GROUP_ID, &members), Err(Ok(Error::MemberAlreadyExists)));
+    let members = vec![&env, member2_identity_commitment.clone(), Bytes::new(&env)];
+    assert_eq!(client.try_add_members(&GROUP_ID, &members), Err(Ok(Error::InvalidIdentityCommitment)));
+    assert!(!client.is_member(&GROUP_ID, &member2_identity_commitment));
+    assert_eq!(client.get_member_count(&GROUP_ID), 1);
+}
+
+#[test]
+fn test_add_members_group_is_full() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let small = |tree| Some(GroupMetadata { depth: Some(1), ..tree_metadata(tree).unwrap() });
+    let members: [Bytes; 3] = [b"a", b"b", b"c"].map(|secret| member_identity_commitment(&env, secret));
+
+    // two leaves, and removed members keep theirs
+    let group_id = client.create_group(&Address::generate(&env), &small(TreeKind::Incremental));
+    assert_eq!(client.try_add_members(&group_id, &Vec::from_array(&env, members.clone())), Err(Ok(Error::GroupIsFull)));
+    client.add_members(&group_id, &vec![&env, members[0].clone(), members[1].clone()]);
+    client.remove_member(&group_id, &members[0]);
+    assert_eq!(client.try_add_member(&group_id, &members[2]), Err(Ok(Error::GroupIsFull)));
+    assert_eq!(client.get_member_count(&group_id), 1);
+
+    // the first leaf of an indexed tree holds the zero key
+    let group_id = client.create_group(&Address::generate(&env), &small(TreeKind::Indexed));
+    client.add_member(&group_id, &members[0]);
+    assert_eq!(client.try_add_member(&group_id, &members[1]), Err(Ok(Error::GroupIsFull)));
+
+    // sparse trees have no leaf indices to run out of
+    let group_id = client.create_group(&Address::generate(&env), &small(TreeKind::Sparse));
+    client.add_members(&group_id, &Vec::from_array(&env, members));
+    assert_eq!(client.get_member_count(&group_id), 3);
+}
+
+#[test]
+fn test_add_members() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.create_group(&admin, &None);
+    client.add_member(&GROUP_ID, &member_identity_commitment(&env, b"member0_secret"));
+    let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
+    let member2_identity_commitment = member_identity_commitment(&env, b"member2_secret");
+    let members = vec![&env, member1_identity_commitment.clone(), member2_identity_commitment.clone()];
+    client.add_members(&GROUP_ID, &members);
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    contract_id.clone(),
+                    Symbol::new(&env, "add_members"),
+                    (GROUP_ID, members.clone()).into_val(&env)
+                )),
+                sub_invocations: std::vec![]
+            }
+        )]
+    );
+
+    // same tree as adding the members one by one
+    let mut tree = empty_tree(&env);
+    tree.add_leaf(&env, 0, tree.hash_to_g1(&env, member_identity_commitment(&env, b"member0_secret")));
+    tree.add_leaf(&env, 1, tree.hash_to_g1(&env, member1_identity_commitment.clone()));
+    tree.add_leaf(&env, 2, tree.hash_to_g1(&env, member2_identity_commitment.clone()));
+    assert_eq!(client.get_merkle_root(&GROUP_ID), tree.get_root());
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
+        vec![&env, event(&env, &contract_id, MembersAdded { group_id: GROUP_ID, identity_commitments: members, start_index: 1, root: tree.get_root() })]
+    );
+    assert_eq!(client.get_member_count(&GROUP_ID), 3);
+    assert_eq!(client.get_member(&GROUP_ID, &member2_identity_commitment).index, 2);
+}
 
 #[test]
 fn test_update_member_invalid_identity_commitment() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     assert_eq!(client.try_update_member(&GROUP_ID, &Bytes::new(&env), &Bytes::new(&env)), Err(Ok(Error::InvalidIdentityCommitment)));
 }
@@ -410,7 +605,7 @@ fn test_update_member_invalid_identity_commitment() {
 #[test]
 fn test_update_member_group_does_not_exist() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let member1_new_identity_commitment = member_identity_commitment(&env, b"member1_new_secret");
     assert_eq!(client.try_update_member(&GROUP_ID, &Bytes::new(&env), &member1_new_identity_commitment), Err(Ok(Error::GroupDoesNotExist)));
@@ -420,10 +615,10 @@ fn test_update_member_group_does_not_exist() {
 #[should_panic(expected = "HostError: Error(Auth, InvalidAction)")]
 fn test_update_member_caller_not_group_admin() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let member1_new_identity_commitment = member_identity_commitment(&env, b"member1_new_secret");
     client.update_member(&GROUP_ID, &Bytes::new(&env), &member1_new_identity_commitment);
 }
@@ -433,10 +628,10 @@ fn test_update_member_caller_not_group_admin() {
 fn test_update_member_member_does_not_exist() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let member1_new_identity_commitment = member_identity_commitment(&env, b"member1_new_secret");
     assert_eq!(client.try_update_member(&GROUP_ID, &Bytes::new(&env), &member1_new_identity_commitment), Err(Ok(Error::MemberDoesNotExist)));
 }
@@ -445,10 +640,10 @@ fn test_update_member_member_does_not_exist() {
 fn test_update_member_member_already_exists() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let member1_new_identity_commitment = member_identity_commitment(&env, b"member1_new_secret");
     client.add_member(&GROUP_ID, &member1_new_identity_commitment);
     assert_eq!(client.try_update_member(&GROUP_ID, &member1_new_identity_commitment, &member1_new_identity_commitment), Err(Ok(Error::MemberAlreadyExists)));
@@ -458,10 +653,10 @@ fn test_update_member_member_already_exists() {
 fn test_update_member() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
     client.add_member(&GROUP_ID, &member1_identity_commitment);
     let member1_new_identity_commitment = member_identity_commitment(&env, b"member1_new_secret");
@@ -480,30 +675,20 @@ fn test_update_member() {
             }
         )]
     );
+    let mut tree = empty_tree(&env);
+    let empty_root = tree.get_root();
+    tree.add_leaf(&env, 0, tree.hash_to_g1(&env, member1_identity_commitment.clone()));
+    let member_added_root = tree.get_root();
+    tree.add_leaf(&env, 0, tree.hash_to_g1(&env, member1_new_identity_commitment.clone()));
+    let member_updated_root = tree.get_root();
     assert_eq!(
         env.events().all(),
         vec![
             &env,
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_created"), GROUP_ID).into_val(&env),
-                GROUP_ID.into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_admin_updated"), GROUP_ID).into_val(&env),
-                admin.into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "MemberAdded"), GROUP_ID, &member1_identity_commitment, 0_u32).into_val(&env),
-                ().into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "MemberUpdated"), GROUP_ID, &member1_identity_commitment, &member1_new_identity_commitment).into_val(&env),
-                ().into_val(&env)
-            )
+            event(&env, &contract_id, GroupCreated { group_id: GROUP_ID, admin: admin.clone(), depth: DEFAULT_DEPTH, root: empty_root }),
+            event(&env, &contract_id, GroupAdminUpdated { group_id: GROUP_ID, old_admin: None, new_admin: admin.clone() }),
+            event(&env, &contract_id, MemberAdded { group_id: GROUP_ID, identity_commitment: member1_identity_commitment.clone(), index: 0, root: member_added_root }),
+            event(&env, &contract_id, MemberUpdated { group_id: GROUP_ID, old_identity_commitment: member1_identity_commitment.clone(), new_identity_commitment: member1_new_identity_commitment.clone(), index: 0, root: member_updated_root })
         ]
     );
 }
@@ -511,7 +696,7 @@ fn test_update_member() {
 #[test]
 fn test_remove_member_group_does_not_exist() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     assert_eq!(client.try_remove_member(&GROUP_ID, &Bytes::new(&env)), Err(Ok(Error::GroupDoesNotExist)));
 }
@@ -520,10 +705,10 @@ fn test_remove_member_group_does_not_exist() {
 #[should_panic(expected = "HostError: Error(Auth, InvalidAction)")]
 fn test_remove_member_caller_not_group_admin() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     client.remove_member(&GROUP_ID, &Bytes::new(&env));
 }
 
@@ -531,10 +716,10 @@ fn test_remove_member_caller_not_group_admin() {
 fn test_remove_member_member_does_not_exist() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     assert_eq!(client.try_remove_member(&GROUP_ID, &Bytes::new(&env)), Err(Ok(Error::MemberDoesNotExist)));
 }
 
@@ -542,10 +727,10 @@ fn test_remove_member_member_does_not_exist() {
 fn test_remove_member() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
     client.add_member(&GROUP_ID, &member1_identity_commitment);
     client.remove_member(&GROUP_ID, &member1_identity_commitment);
@@ -563,38 +748,182 @@ fn test_remove_member() {
             }
         )]
     );
+    let mut tree = empty_tree(&env);
+    let empty_root = tree.get_root();
+    tree.add_leaf(&env, 0, tree.hash_to_g1(&env, member1_identity_commitment.clone()));
+    let member_added_root = tree.get_root();
+    tree.remove_leaf(&env, 0);
+    let member_removed_root = tree.get_root();
     assert_eq!(
         env.events().all(),
         vec![
             &env,
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_created"), GROUP_ID).into_val(&env),
-                GROUP_ID.into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "group_admin_updated"), GROUP_ID).into_val(&env),
-                admin.into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "MemberAdded"), GROUP_ID, &member1_identity_commitment, 0_u32).into_val(&env),
-                ().into_val(&env)
-            ),
-            (
-                contract_id.clone(),
-                (Symbol::new(&env, "MemberRemoved"), GROUP_ID, &member1_identity_commitment).into_val(&env),
-                ().into_val(&env)
-            )
+            event(&env, &contract_id, GroupCreated { group_id: GROUP_ID, admin: admin.clone(), depth: DEFAULT_DEPTH, root: empty_root }),
+            event(&env, &contract_id, GroupAdminUpdated { group_id: GROUP_ID, old_admin: None, new_admin: admin.clone() }),
+            event(&env, &contract_id, MemberAdded { group_id: GROUP_ID, identity_commitment: member1_identity_commitment.clone(), index: 0, root: member_added_root }),
+            event(&env, &contract_id, MemberRemoved { group_id: GROUP_ID, identity_commitment: member1_identity_commitment.clone(), index: 0, root: member_removed_root })
         ]
     );
 }
 
+#[test]
+fn test_add_member_after_removal() {
+    let env = Env::default();
+    let (_, client, mut tree) = group_with_members(&env, &[b"a", b"b"]);
+    let (a, c) = (member_identity_commitment(&env, b"a"), member_identity_commitment(&env, b"c"));
+    client.remove_member(&GROUP_ID, &a);
+    tree.remove_leaf(&env, 0);
+
+    // the leaf of "b" is not handed out again
+    client.add_member(&GROUP_ID, &c);
+    tree.add_leaf(&env, 2, tree.hash_to_g1(&env, c.clone()));
+    assert_eq!(client.get_member(&GROUP_ID, &c).index, 2);
+    assert_eq!(client.get_member_count(&GROUP_ID), 2);
+    assert_eq!(client.get_merkle_root(&GROUP_ID), tree.get_root());
+}
+
+/// Group with members added from the given secrets, in order
+fn group_with_members<'a>(env: &'a Env, secrets: &[&[u8]]) -> (Address, SemaphoreGroupContractClient<'a>, MerkleTree) {
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(env),));
+    let client = SemaphoreGroupContractClient::new(env, &contract_id);
+    client.create_group(&Address::generate(env), &None);
+    let mut tree = empty_tree(env);
+    for (index, secret) in secrets.iter().enumerate() {
+        let commitment = member_identity_commitment(env, secret);
+        client.add_member(&GROUP_ID, &commitment);
+        tree.add_leaf(env, index, tree.hash_to_g1(env, commitment));
+    }
+    (contract_id, client, tree)
+}
+
+#[test]
+fn test_update_members() {
+    let env = Env::default();
+    let (contract_id, client, mut tree) = group_with_members(&env, &[b"a", b"b", b"c", b"d"]);
+    let (b, d) = (member_identity_commitment(&env, b"b"), member_identity_commitment(&env, b"d"));
+    let (x, y) = (member_identity_commitment(&env, b"x"), member_identity_commitment(&env, b"y"));
+
+    let updates = vec![&env, (d.clone(), x.clone()), (b.clone(), y.clone())];
+    client.update_members(&GROUP_ID, &updates);
+
+    tree.add_leaf(&env, 3, tree.hash_to_g1(&env, x.clone()));
+    tree.add_leaf(&env, 1, tree.hash_to_g1(&env, y.clone()));
+    assert_eq!(client.get_merkle_root(&GROUP_ID), tree.get_root());
+    assert_eq!(client.get_member(&GROUP_ID, &y).index, 1);
+    assert!(!client.is_member(&GROUP_ID, &b));
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
+        vec![&env, event(&env, &contract_id, MembersUpdated {
+            group_id: GROUP_ID,
+            old_identity_commitments: vec![&env, d, b],
+            new_identity_commitments: vec![&env, x, y],
+            indexes: vec![&env, 3, 1],
+            root: tree.get_root(),
+        })]
+    );
+}
+
+#[test]
+fn test_update_members_chained() {
+    let env = Env::default();
+    let (_, client, mut tree) = group_with_members(&env, &[b"a", b"b", b"c"]);
+    let (a, c) = (member_identity_commitment(&env, b"a"), member_identity_commitment(&env, b"c"));
+    let (x, y) = (member_identity_commitment(&env, b"x"), member_identity_commitment(&env, b"y"));
+
+    // later pairs see the members the earlier ones left
+    let updates = vec![&env, (a.clone(), x.clone()), (c.clone(), a.clone()), (x.clone(), y.clone())];
+    client.update_members(&GROUP_ID, &updates);
+
+    tree.add_leaf(&env, 0, tree.hash_to_g1(&env, y.clone()));
+    tree.add_leaf(&env, 2, tree.hash_to_g1(&env, a.clone()));
+    assert_eq!(client.get_merkle_root(&GROUP_ID), tree.get_root());
+    assert_eq!(client.get_member(&GROUP_ID, &y).index, 0);
+    assert_eq!(client.get_member(&GROUP_ID, &a).index, 2);
+    assert!(!client.is_member(&GROUP_ID, &c));
+    assert!(!client.is_member(&GROUP_ID, &x));
+}
+
+#[test]
+fn test_update_members_failure() {
+    let env = Env::default();
+    let (_, client, tree) = group_with_members(&env, &[b"a", b"b"]);
+    let (a, b) = (member_identity_commitment(&env, b"a"), member_identity_commitment(&env, b"b"));
+    let (x, y) = (member_identity_commitment(&env, b"x"), member_identity_commitment(&env, b"y"));
+
+    let cases = [
+        (vec![&env, (a.clone(), x.clone()), (y.clone(), x.clone())], Error::MemberDoesNotExist),
+        (vec![&env, (a.clone(), x.clone()), (a.clone(), y.clone())], Error::MemberDoesNotExist),
+        (vec![&env, (a.clone(), x.clone()), (b.clone(), x.clone())], Error::MemberAlreadyExists),
+        (vec![&env, (a.clone(), b.clone())], Error::MemberAlreadyExists),
+        (vec![&env, (a.clone(), x.clone()), (b.clone(), Bytes::new(&env))], Error::InvalidIdentityCommitment),
+    ];
+    for (updates, error) in cases {
+        assert_eq!(client.try_update_members(&GROUP_ID, &updates), Err(Ok(error)));
+    }
+    // nothing was applied
+    assert_eq!(client.get_merkle_root(&GROUP_ID), tree.get_root());
+    assert!(client.is_member(&GROUP_ID, &a));
+    assert!(!client.is_member(&GROUP_ID, &x));
+}
+
+#[test]
+fn test_remove_members() {
+    let env = Env::default();
+    let (contract_id, client, mut tree) = group_with_members(&env, &[b"a", b"b", b"c"]);
+    let (a, c) = (member_identity_commitment(&env, b"a"), member_identity_commitment(&env, b"c"));
+
+    let members = vec![&env, c.clone(), a.clone()];
+    client.remove_members(&GROUP_ID, &members);
+
+    tree.remove_leaf(&env, 2);
+    tree.remove_leaf(&env, 0);
+    assert_eq!(client.get_merkle_root(&GROUP_ID), tree.get_root());
+    assert_eq!(client.get_member_count(&GROUP_ID), 1);
+    assert!(!client.is_member(&GROUP_ID, &a));
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
+        vec![&env, event(&env, &contract_id, MembersRemoved {
+            group_id: GROUP_ID,
+            identity_commitments: members,
+            indexes: vec![&env, 2, 0],
+            root: tree.get_root(),
+        })]
+    );
+}
+
+#[test]
+fn test_remove_members_failure() {
+    let env = Env::default();
+    let (_, client, tree) = group_with_members(&env, &[b"a", b"b"]);
+    let (a, b) = (member_identity_commitment(&env, b"a"), member_identity_commitment(&env, b"b"));
+
+    let unknown = member_identity_commitment(&env, b"x");
+    let failure = Err(Ok(Error::MemberDoesNotExist));
+    assert_eq!(client.try_remove_members(&GROUP_ID, &vec![&env, a.clone(), b.clone(), unknown]), failure);
+    assert_eq!(client.try_remove_members(&GROUP_ID, &vec![&env, a.clone(), b.clone(), a.clone()]), failure);
+    // nothing was applied
+    assert_eq!(client.get_merkle_root(&GROUP_ID), tree.get_root());
+    assert_eq!(client.get_member_count(&GROUP_ID), 2);
+    assert!(client.is_member(&GROUP_ID, &a));
+}
+
+#[test]
+fn test_batch_requires_group_admin() {
+    let env = Env::default();
+    let (_, client, _) = group_with_members(&env, &[b"a"]);
+    env.set_auths(&[]);
+    let a = member_identity_commitment(&env, b"a");
+    assert!(client.try_remove_members(&GROUP_ID, &vec![&env, a.clone()]).is_err());
+    let x = member_identity_commitment(&env, b"x");
+    assert!(client.try_update_members(&GROUP_ID, &vec![&env, (a, x)]).is_err());
+}
+
 #[test]
 fn test_get_group_admin_group_does_not_exist() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     assert_eq!(client.try_get_group_admin(&GROUP_ID), Err(Ok(Error::GroupDoesNotExist)));
 }
@@ -602,17 +931,17 @@ fn test_get_group_admin_group_does_not_exist() {
 #[test]
 fn test_get_group_admin() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     assert_eq!(client.get_group_admin(&GROUP_ID), admin);
 }
 
 #[test]
 fn test_get_member_member_does_not_exist() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     assert_eq!(client.try_get_member(&GROUP_ID, &Bytes::new(&env)), Err(Ok(Error::MemberDoesNotExist)));
 }
@@ -621,10 +950,10 @@ fn test_get_member_member_does_not_exist() {
 fn test_get_member() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
     client.add_member(&GROUP_ID, &member1_identity_commitment);
     assert_eq!(
@@ -636,7 +965,7 @@ fn test_get_member() {
 #[test]
 fn test_get_member_count_group_does_not_exist() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     assert_eq!(client.try_get_member_count(&GROUP_ID), Err(Ok(Error::GroupDoesNotExist)));
 }
@@ -645,10 +974,10 @@ fn test_get_member_count_group_does_not_exist() {
 fn test_get_member_count() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
+    client.create_group(&admin, &None);
     assert_eq!(client.get_member_count(&GROUP_ID), 0_u32);
     let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
     client.add_member(&GROUP_ID, &member1_identity_commitment);
@@ -658,7 +987,7 @@ fn test_get_member_count() {
 #[test]
 fn test_is_member_group_does_not_exist() {
     let env = Env::default();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     assert_eq!(client.try_is_member(&GROUP_ID, &Bytes::new(&env)), Err(Ok(Error::GroupDoesNotExist)));
 }
@@ -667,14 +996,361 @@ fn test_is_member_group_does_not_exist() {
 fn test_is_member() {
     let env = Env::default();
     env.mock_all_auths();
-    let contract_id = env.register(SemaphoreGroupContract, ());
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
     let client = SemaphoreGroupContractClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
-    client.create_group(&GROUP_ID, &admin);
-    assert_eq!(client.is_member(&GROUP_ID, &Bytes::new(&env)), false);
+    client.create_group(&admin, &None);
+    assert!(!client.is_member(&GROUP_ID, &Bytes::new(&env)));
     let member1_identity_commitment = member_identity_commitment(&env, b"member1_secret");
     client.add_member(&GROUP_ID, &member1_identity_commitment);
-    assert_eq!(client.is_member(&GROUP_ID, &member1_identity_commitment), true);
+    assert!(client.is_member(&GROUP_ID, &member1_identity_commitment));
+}
+
+#[test]
+fn test_upgrade() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let owner = Address::generate(&env);
+    let contract_id = env.register(SemaphoreGroupContract, (owner.clone(),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    assert_eq!(client.owner(), owner);
+
+    // no code was uploaded with this hash, so the call gets past the owner's
+    // authorization and fails in the host
+    let err = client.try_upgrade(&BytesN::from_array(&env, &[1u8; 32])).unwrap_err().unwrap();
+    assert!(!err.is_type(ScErrorType::Auth));
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_upgrade_requires_owner() {
+    let env = Env::default();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    client.upgrade(&BytesN::from_array(&env, &[1u8; 32]));
+}
+
+#[test]
+fn test_migrate() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    assert_eq!(client.schema_version(), owner::STORAGE_SCHEMA_VERSION);
+    client.migrate(&Map::new(&env));
+    assert_eq!(client.schema_version(), owner::STORAGE_SCHEMA_VERSION);
+
+    // data from before the version was recorded
+    env.as_contract(&contract_id, || env.storage().instance().remove(&DataKey::SchemaVersion));
+    assert_eq!(client.schema_version(), 1);
+    client.migrate(&Map::new(&env));
+    assert_eq!(client.schema_version(), owner::STORAGE_SCHEMA_VERSION);
+
+    // data from newer code
+    env.as_contract(&contract_id, || {
+        env.storage().instance().set(&DataKey::SchemaVersion, &(owner::STORAGE_SCHEMA_VERSION + 1))
+    });
+    assert_eq!(client.try_migrate(&Map::new(&env)), Err(Ok(Error::UnsupportedSchemaVersion)));
+}
+
+#[test]
+fn test_migrate_layout_1() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let members: std::vec::Vec<_> = (0..4u8).map(|i| member_identity_commitment(&env, &[i])).collect();
+
+    // a group with the id its admin chose, where a removal handed the last
+    // index out again, and an empty one
+    env.as_contract(&contract_id, || {
+        layout_1_group(&env, 7, &admin, &[&members[0], &members[1], &members[2]]);
+        env.storage().instance().set(&DataKey::MemberCount(7), &2u32);
+        layout_1_group(&env, 3, &admin, &[]);
+    });
+    assert_eq!(client.schema_version(), 1);
+    assert_eq!(client.try_get_group(&7), Err(Ok(Error::GroupDoesNotExist)));
+
+    // every group and member listed must exist
+    let listed = vec![&env, members[0].clone(), members[1].clone(), members[2].clone()];
+    let layout_1 = Map::from_array(&env, [(7, listed.clone()), (3, Vec::new(&env))]);
+    let mut unknown_group = layout_1.clone();
+    unknown_group.set(5, Vec::new(&env));
+    assert_eq!(client.try_migrate(&unknown_group), Err(Ok(Error::GroupDoesNotExist)));
+    let unknown_member = Map::from_array(&env, [(7, vec![&env, members[0].clone(), members[3].clone()]), (3, Vec::new(&env))]);
+    assert_eq!(client.try_migrate(&unknown_member), Err(Ok(Error::MemberDoesNotExist)));
+    client.migrate(&layout_1);
+    assert_eq!(client.schema_version(), owner::STORAGE_SCHEMA_VERSION);
+
+    // the tree of a group created now with the same members
+    let expected = client.create_group(&admin, &None);
+    client.add_members(&expected, &listed);
+    let info = client.get_group(&7);
+    assert_eq!((info.admin, info.name, info.depth, info.tree, info.hash), (admin.clone(), None, DEFAULT_DEPTH, TreeKind::Incremental, HashFunction::Keccak256));
+    assert_eq!(info.root, client.get_merkle_root(&expected));
+    assert_eq!(client.get_group(&3).root, empty_tree(&env).get_root());
+    let proof = client.get_member_proof(&7, &members[2]);
+    assert!(client.verify_member_proof(&7, &members[2], &proof));
+
+    // the entries left the instance, and the group works as any other
+    let in_instance = |key: DataKey| env.as_contract(&contract_id, || env.storage().instance().has(&key));
+    assert!(!in_instance(DataKey::Group(7)) && !in_instance(DataKey::Admin(7)));
+    assert!(!in_instance(DataKey::Member(7, members[0].clone())));
+    client.add_member(&7, &members[3]);
+    assert_eq!(client.get_member(&7, &members[3]).index, 3);
+    client.remove_member(&7, &members[0]);
+    assert_eq!(client.try_get_member(&7, &members[0]), Err(Ok(Error::MemberDoesNotExist)));
+
+    // new groups are given ids past the ones listed
+    assert_eq!(expected, 8);
+}
+
+#[test]
+fn test_group_entries_are_persistent() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let group_id = client.create_group(&Address::generate(&env), &None);
+    let member = member_identity_commitment(&env, b"member1_secret");
+    client.add_member(&group_id, &member);
+
+    let member_key = DataKey::Member(group_id, member.clone());
+    env.as_contract(&contract_id, || {
+        assert!(!env.storage().instance().has(&member_key));
+        assert!(!env.storage().instance().has(&DataKey::Group(group_id)));
+        assert_eq!(env.storage().persistent().get_ttl(&member_key), storage::GROUP_TTL);
+    });
+
+    // reading an entry that is close to expiring extends it again
+    env.as_contract(&contract_id, || {
+        env.deployer().extend_ttl(contract_id.clone(), storage::GROUP_TTL, storage::GROUP_TTL)
+    });
+    let ledger = env.ledger().sequence() + storage::GROUP_TTL - storage::GROUP_TTL_THRESHOLD + 1;
+    env.ledger().set_sequence_number(ledger);
+    assert!(client.is_member(&group_id, &member));
+    client.get_member(&group_id, &member);
+    env.as_contract(&contract_id, || {
+        assert_eq!(env.storage().persistent().get_ttl(&member_key), storage::GROUP_TTL);
+    });
+}
+
+#[test]
+fn test_archive_group() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let group_id = client.create_group(&admin, &None);
+    let member = member_identity_commitment(&env, b"member1_secret");
+    client.add_member(&group_id, &member);
+    let proof = client.get_proof(&group_id, &0);
+    let leaf = empty_tree(&env).hash_to_g1(&env, member.clone());
+    assert!(client.verify_proof(&group_id, &leaf, &proof));
+
+    client.archive_group(&group_id);
+    assert_eq!(client.get_group_status(&group_id), GroupStatus::Archived);
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
+        vec![&env, event(&env, &contract_id, GroupArchived { group_id })]
+    );
+
+    let other = member_identity_commitment(&env, b"member2_secret");
+    assert_eq!(client.try_add_member(&group_id, &other), Err(Ok(Error::GroupIsArchived)));
+    assert_eq!(client.try_update_member(&group_id, &member, &other), Err(Ok(Error::GroupIsArchived)));
+    assert_eq!(client.try_remove_member(&group_id, &member), Err(Ok(Error::GroupIsArchived)));
+    assert_eq!(client.try_verify_proof(&group_id, &leaf, &proof), Err(Ok(Error::GroupIsArchived)));
+    assert_eq!(client.try_archive_group(&group_id), Err(Ok(Error::GroupIsArchived)));
+
+    // still readable
+    assert!(client.is_member(&group_id, &member));
+    assert_eq!(client.get_group(&group_id).status, GroupStatus::Archived);
+}
+
+#[test]
+fn test_delete_group() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let group_id = client.create_group(&admin, &None);
+    let members: std::vec::Vec<_> = (0..3u8).map(|i| member_identity_commitment(&env, &[i])).collect();
+    for member in &members {
+        client.add_member(&group_id, member);
+    }
+
+    assert_eq!(client.try_delete_group(&group_id, &Vec::new(&env)), Err(Ok(Error::GroupIsNotArchived)));
+    client.archive_group(&group_id);
+
+    // one batch at a time
+    assert_eq!(client.delete_group(&group_id, &vec![&env, members[0].clone(), members[1].clone()]), 1);
+    assert!(!client.is_member(&group_id, &members[0]));
+    assert_eq!(client.get_member_count(&group_id), 1);
+    assert_eq!(
+        client.try_delete_group(&group_id, &vec![&env, members[0].clone()]),
+        Err(Ok(Error::MemberDoesNotExist))
+    );
+    assert_eq!(client.delete_group(&group_id, &vec![&env, members[2].clone()]), 0);
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
+        vec![&env, event(&env, &contract_id, GroupDeleted { group_id })]
+    );
+
+    assert_eq!(client.get_group_status(&group_id), GroupStatus::Deleted);
+    assert_eq!(client.try_get_group(&group_id), Err(Ok(Error::GroupDoesNotExist)));
+    assert_eq!(client.try_add_member(&group_id, &members[0]), Err(Ok(Error::GroupDoesNotExist)));
+
+    // the id stays taken
+    assert_eq!(client.create_group(&admin, &None), group_id + 1);
+}
+
+#[test]
+fn test_delete_group_count_below_members() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let group_id = client.create_group(&admin, &None);
+    let members: std::vec::Vec<_> = (0..2u8).map(|i| member_identity_commitment(&env, &[i])).collect();
+    for member in &members {
+        client.add_member(&group_id, member);
+    }
+    client.archive_group(&group_id);
+
+    // a count that fell behind the members stops at zero
+    env.as_contract(&contract_id, || {
+        storage::set(&env, &DataKey::MemberCount(group_id), &1u32);
+    });
+    assert_eq!(client.delete_group(&group_id, &vec![&env, members[0].clone(), members[1].clone()]), 0);
+    assert_eq!(client.get_group_status(&group_id), GroupStatus::Deleted);
+}
+
+#[test]
+fn test_pause() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let owner = Address::generate(&env);
+    let contract_id = env.register(SemaphoreGroupContract, (owner.clone(),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.create_group(&admin, &None);
+    let member = member_identity_commitment(&env, b"member1_secret");
+    client.add_member(&GROUP_ID, &member);
+
+    env.ledger().set_timestamp(1000);
+    client.pause();
+    assert!(client.is_paused());
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
+        vec![
+            &env,
+            (
+                contract_id.clone(),
+                (Symbol::new(&env, "paused"), SCHEMA_VERSION).into_val(&env),
+                Paused { by: owner.clone(), timestamp: 1000 }.into_val(&env)
+            )
+        ]
+    );
+
+    let other = member_identity_commitment(&env, b"member2_secret");
+    assert_eq!(client.try_create_group(&admin, &None), Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_add_member(&GROUP_ID, &other), Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_add_members(&GROUP_ID, &vec![&env, other.clone()]), Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_update_member(&GROUP_ID, &member, &other), Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_remove_member(&GROUP_ID, &member), Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_archive_group(&GROUP_ID), Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_delete_group(&GROUP_ID, &Vec::new(&env)), Err(Ok(Error::ContractPaused)));
+
+    // views keep working
+    assert!(client.is_member(&GROUP_ID, &member));
+    assert_eq!(client.get_member_count(&GROUP_ID), 1);
+    client.get_merkle_root(&GROUP_ID);
+
+    env.ledger().set_timestamp(2000);
+    client.unpause();
+    assert!(!client.is_paused());
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
+        vec![
+            &env,
+            (
+                contract_id.clone(),
+                (Symbol::new(&env, "unpaused"), SCHEMA_VERSION).into_val(&env),
+                Unpaused { by: owner, timestamp: 2000 }.into_val(&env)
+            )
+        ]
+    );
+    client.add_member(&GROUP_ID, &other);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_pause_requires_owner() {
+    let env = Env::default();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    client.pause();
+}
+
+#[test]
+fn test_creator_allowlist() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+
+    client.set_creator_allowlist(&true);
+    assert_eq!(client.try_create_group(&admin, &None), Err(Ok(Error::CallerIsNotAGroupCreator)));
+
+    client.set_group_creator(&admin, &true);
+    assert!(client.is_group_creator(&admin));
+    client.create_group(&admin, &None);
+    // the admin signs for the group it creates
+    assert_eq!(env.auths()[0].0, admin);
+
+    client.set_group_creator(&admin, &false);
+    assert_eq!(client.try_create_group(&admin, &None), Err(Ok(Error::CallerIsNotAGroupCreator)));
+    client.set_creator_allowlist(&false);
+    client.create_group(&admin, &None);
+}
+
+#[test]
+fn test_creation_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let token = env.register_stellar_asset_contract_v2(Address::generate(&env)).address();
+    let token_client = token::TokenClient::new(&env, &token);
+    let admin = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    token::StellarAssetClient::new(&env, &token).mint(&admin, &150);
+
+    let fee = CreationFee { token: token.clone(), amount: 100, recipient: recipient.clone() };
+    assert_eq!(
+        client.try_set_creation_fee(&Some(CreationFee { amount: 0, ..fee.clone() })),
+        Err(Ok(Error::InvalidCreationFee))
+    );
+    client.set_creation_fee(&Some(fee.clone()));
+    assert_eq!(client.get_creation_fee(), Some(fee));
+
+    client.create_group(&admin, &None);
+    assert_eq!(token_client.balance(&admin), 50);
+    assert_eq!(token_client.balance(&recipient), 100);
+
+    // not enough left for a second group
+    assert!(client.try_create_group(&admin, &None).is_err());
+    assert!(client.try_get_group_admin(&(GROUP_ID + 1)).is_err());
+
+    client.set_creation_fee(&None);
+    client.create_group(&admin, &None);
+    assert_eq!(token_client.balance(&admin), 50);
 }
 
 // #[test]
@@ -741,3 +1417,394 @@ fn test_is_member() {
 //     let is_member = client.is_member(&group_id, &commitment1_bytes);
 //     assert!(is_member);
 // }
+
+fn tree_metadata(tree: TreeKind) -> Option<GroupMetadata> {
+    Some(GroupMetadata {
+        name: None,
+        description_uri: None,
+        depth: None,
+        tree,
+        hash: HashFunction::Keccak256,
+    })
+}
+
+#[test]
+fn test_create_indexed_group() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let group_id = client.create_group(&admin, &tree_metadata(TreeKind::Indexed));
+
+    let mut tree = empty_tree(&env);
+    SortedLeaves::new(&env, &mut tree);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            event(&env, &contract_id, GroupTreeSelected { group_id, tree: Symbol::new(&env, "indexed"), hash: Symbol::new(&env, "keccak256") }),
+            event(&env, &contract_id, GroupCreated { group_id, admin: admin.clone(), depth: DEFAULT_DEPTH, root: tree.get_root() }),
+            event(&env, &contract_id, GroupAdminUpdated { group_id, old_admin: None, new_admin: admin.clone() }),
+        ]
+    );
+    let info = client.get_group(&group_id);
+    assert_eq!((info.tree, info.size, info.root), (TreeKind::Indexed, 0, tree.get_root()));
+}
+
+#[test]
+fn test_non_membership() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let group_id = client.create_group(&Address::generate(&env), &tree_metadata(TreeKind::Indexed));
+    let members: std::vec::Vec<_> = (0..4u8).map(|i| member_identity_commitment(&env, &[i])).collect();
+    let outsider = member_identity_commitment(&env, b"outsider");
+
+    // every way of changing members keeps the tree in step with a local copy
+    let mut tree = empty_tree(&env);
+    let mut sorted = SortedLeaves::new(&env, &mut tree);
+    client.add_member(&group_id, &members[0]);
+    client.add_members(&group_id, &vec![&env, members[1].clone(), members[2].clone()]);
+    for (index, member) in members.iter().take(3).enumerate() {
+        sorted.insert(&env, &mut tree, indexed::key(&env, member), index as u32 + 1);
+    }
+    assert_eq!(client.get_member(&group_id, &members[0]).index, 1);
+    assert_eq!(client.get_merkle_root(&group_id), tree.get_root());
+
+    client.update_member(&group_id, &members[2], &members[3]);
+    sorted.remove(&env, &mut tree, &indexed::key(&env, &members[2]));
+    sorted.insert(&env, &mut tree, indexed::key(&env, &members[3]), 3);
+    client.remove_member(&group_id, &members[0]);
+    sorted.remove(&env, &mut tree, &indexed::key(&env, &members[0]));
+    assert_eq!(client.get_merkle_root(&group_id), tree.get_root());
+
+    assert_eq!(sorted.non_membership_proof(&env, &tree, &indexed::key(&env, &members[1])), None);
+
+    // removed and never added commitments are not members
+    for absent in [&members[0], &members[2], &outsider] {
+        let proof = client.prove_non_membership(&group_id, absent);
+        assert!(client.verify_non_membership(&group_id, absent, &proof));
+        assert!(!client.verify_non_membership(&group_id, &members[1], &proof));
+    }
+    for member in [&members[1], &members[3]] {
+        assert_eq!(client.try_prove_non_membership(&group_id, member), Err(Ok(Error::MemberAlreadyExists)));
+    }
+
+    // proofs go stale when the tree changes
+    let proof = client.prove_non_membership(&group_id, &outsider);
+    client.add_member(&group_id, &members[0]);
+    assert!(!client.verify_non_membership(&group_id, &outsider, &proof));
+
+    client.archive_group(&group_id);
+    assert_eq!(
+        client.try_verify_non_membership(&group_id, &outsider, &proof),
+        Err(Ok(Error::GroupIsArchived))
+    );
+}
+
+#[test]
+fn test_non_membership_not_supported() {
+    let env = Env::default();
+    let (_, client, _) = group_with_members(&env, &[b"a"]);
+    let outsider = member_identity_commitment(&env, b"outsider");
+    assert_eq!(client.try_prove_non_membership(&GROUP_ID, &outsider), Err(Ok(Error::NonMembershipNotSupported)));
+    assert_eq!(client.try_prove_non_membership(&(GROUP_ID + 1), &outsider), Err(Ok(Error::GroupDoesNotExist)));
+}
+
+#[test]
+fn test_member_and_not_banned() {
+    let env = Env::default();
+    let (_, client, tree) = group_with_members(&env, &[b"alice", b"bob"]);
+    let banned = client.create_group(&Address::generate(&env), &tree_metadata(TreeKind::Indexed));
+    let (alice, bob) = (member_identity_commitment(&env, b"alice"), member_identity_commitment(&env, b"bob"));
+    client.add_member(&banned, &bob);
+
+    let allowed = |commitment: &Bytes, index: u32| {
+        let leaf = tree.hash_to_g1(&env, commitment.clone());
+        client.verify_proof(&GROUP_ID, &leaf, &client.get_proof(&GROUP_ID, &index))
+            && client
+                .try_prove_non_membership(&banned, commitment)
+                .is_ok_and(|proof| client.verify_non_membership(&banned, commitment, &proof.unwrap()))
+    };
+    assert!(allowed(&alice, 0));
+    assert!(!allowed(&bob, 1));
+}
+
+#[test]
+fn test_verify_proof_malformed() {
+    let env = Env::default();
+    let (_, client, tree) = group_with_members(&env, &[b"alice", b"bob"]);
+    let leaf = tree.hash_to_g1(&env, member_identity_commitment(&env, b"bob"));
+    let proof = client.get_proof(&GROUP_ID, &1);
+    assert!(client.verify_proof(&GROUP_ID, &leaf, &proof));
+
+    // a branch short, one too many, and more than a leaf index holds
+    let mut short = proof.clone();
+    short.0.pop_back();
+    assert_eq!(client.try_verify_proof(&GROUP_ID, &leaf, &short), Err(Ok(Error::InvalidProofLength)));
+    let mut long = proof.clone();
+    long.0.push_back(proof.0.last().unwrap());
+    assert_eq!(client.try_verify_proof(&GROUP_ID, &leaf, &long), Err(Ok(Error::InvalidProofLength)));
+    while long.0.len() < 100 {
+        long.0.push_back(proof.0.first().unwrap());
+    }
+    assert_eq!(long.leaf_index(), None);
+    assert_eq!(client.try_verify_proof(&GROUP_ID, &leaf, &long), Err(Ok(Error::InvalidProofLength)));
+
+    // the empty leaf after the members is in the tree, but no member's, and
+    // neither is the leaf a removed member left empty
+    let empty_leaf = Bytes::from_slice(&env, &EMPTY_LEAF);
+    assert_eq!(client.try_get_proof(&GROUP_ID, &2), Err(Ok(Error::LeafIndexOutOfRange)));
+    let unused = tree.proof(2).unwrap();
+    assert_eq!(client.try_verify_proof(&GROUP_ID, &empty_leaf, &unused), Err(Ok(Error::InvalidIdentityCommitment)));
+    client.remove_member(&GROUP_ID, &member_identity_commitment(&env, b"alice"));
+    let removed = client.get_proof(&GROUP_ID, &0);
+    assert_eq!(client.try_verify_proof(&GROUP_ID, &empty_leaf, &removed), Err(Ok(Error::InvalidIdentityCommitment)));
+}
+
+#[test]
+fn test_get_proof_errors() {
+    let env = Env::default();
+    let (_, client, _) = group_with_members(&env, &[b"alice"]);
+    assert_eq!(client.try_get_merkle_root(&(GROUP_ID + 1)), Err(Ok(Error::GroupDoesNotExist)));
+    assert_eq!(client.try_get_proof(&(GROUP_ID + 1), &0), Err(Ok(Error::GroupDoesNotExist)));
+    assert_eq!(client.try_get_proof(&GROUP_ID, &1), Err(Ok(Error::LeafIndexOutOfRange)));
+    assert_eq!(client.try_get_proof(&GROUP_ID, &u32::MAX), Err(Ok(Error::LeafIndexOutOfRange)));
+}
+
+#[test]
+fn test_unused_leaves_are_not_members() {
+    let env = Env::default();
+    let (contract_id, client, tree) = group_with_members(&env, &[b"alice"]);
+    let indexed_group = client.create_group(&Address::generate(&env), &tree_metadata(TreeKind::Indexed));
+    let (alice, outsider) = (member_identity_commitment(&env, b"alice"), member_identity_commitment(&env, b"outsider"));
+    client.add_member(&indexed_group, &alice);
+
+    // leaves past the ones handed out never count, whatever they hold: here
+    // the outsider's leaf, and a zero key linking to itself as if the tree
+    // were empty
+    let zero = BytesN::from_array(&env, &[0; 32]);
+    let hash = HashFunction::Keccak256;
+    // `get_proof` hands out no proof of them, so take the proofs from the trees
+    let (proof, indexed_proof) = env.as_contract(&contract_id, || {
+        let mut group: Group = storage::get(&env, &DataKey::Group(GROUP_ID)).unwrap();
+        group.merkle_tree.add_leaf(&env, 5, tree.hash_to_g1(&env, outsider.clone()));
+        storage::set(&env, &DataKey::Group(GROUP_ID), &group);
+        let mut indexed: Group = storage::get(&env, &DataKey::Group(indexed_group)).unwrap();
+        indexed.merkle_tree.add_leaf(&env, 5, indexed::leaf(&env, &hash, &zero, &zero));
+        storage::set(&env, &DataKey::Group(indexed_group), &indexed);
+        (group.merkle_tree.proof(5).unwrap(), indexed.merkle_tree.proof(5).unwrap())
+    });
+
+    let leaf = tree.hash_to_g1(&env, outsider.clone());
+    assert_eq!(client.try_verify_proof(&GROUP_ID, &leaf, &proof), Err(Ok(Error::LeafIndexOutOfRange)));
+    assert!(!client.verify_member_proof(&GROUP_ID, &outsider, &MemberProof::Merkle(proof)));
+
+    let forged = NonMembershipProof { low_key: zero.clone(), next_key: zero, proof: indexed_proof };
+    assert!(!client.verify_non_membership(&indexed_group, &alice, &forged));
+    assert!(client.verify_non_membership(&indexed_group, &outsider, &client.prove_non_membership(&indexed_group, &outsider)));
+}
+
+#[test]
+fn test_sparse_group() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let group_id = client.create_group(&admin, &tree_metadata(TreeKind::Sparse));
+    let members: std::vec::Vec<_> = (0..6u8).map(|i| member_identity_commitment(&env, &[i])).collect();
+    let key = |commitment: &Bytes| indexed::key(&env, commitment);
+
+    let mut tree = SparseMerkleTree::new(&env);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            event(&env, &contract_id, GroupTreeSelected { group_id, tree: Symbol::new(&env, "sparse"), hash: Symbol::new(&env, "keccak256") }),
+            event(&env, &contract_id, GroupCreated { group_id, admin: admin.clone(), depth: DEFAULT_DEPTH, root: tree.get_root() }),
+            event(&env, &contract_id, GroupAdminUpdated { group_id, old_admin: None, new_admin: admin.clone() }),
+        ]
+    );
+
+    // every way of changing members keeps the tree in step with a local copy
+    client.add_member(&group_id, &members[0]);
+    client.add_members(&group_id, &vec![&env, members[1].clone(), members[2].clone(), members[3].clone()]);
+    for member in &members[..4] {
+        tree.insert(&env, &key(member));
+    }
+    assert_eq!(client.get_merkle_root(&group_id), tree.get_root());
+    client.update_member(&group_id, &members[0], &members[4]);
+    client.update_members(&group_id, &vec![&env, (members[1].clone(), members[5].clone())]);
+    client.remove_member(&group_id, &members[2]);
+    client.remove_members(&group_id, &vec![&env, members[3].clone()]);
+    for (removed, added) in [(0, 4), (1, 5)] {
+        tree.remove(&env, &key(&members[removed]));
+        tree.insert(&env, &key(&members[added]));
+    }
+    tree.remove(&env, &key(&members[2]));
+    tree.remove(&env, &key(&members[3]));
+    let info = client.get_group(&group_id);
+    assert_eq!((info.tree, info.size, info.root), (TreeKind::Sparse, 2, tree.get_root()));
+
+    // proofs are addressed by commitment
+    for member in [&members[4], &members[5]] {
+        let proof = client.get_sparse_proof(&group_id, member);
+        assert_eq!(proof, tree.proof(&env, &key(member)).unwrap());
+        assert!(client.verify_sparse_proof(&group_id, member, &proof));
+        assert!(!client.verify_sparse_proof(&group_id, &members[0], &proof));
+    }
+    assert_eq!(client.try_get_sparse_proof(&group_id, &members[0]), Err(Ok(Error::MemberDoesNotExist)));
+
+    // leaf indexed operations do not apply
+    assert_eq!(client.try_get_proof(&group_id, &0), Err(Ok(Error::UnsupportedTreeKind)));
+    let proof = Proof(Vec::new(&env));
+    assert_eq!(client.try_verify_proof(&group_id, &members[4], &proof), Err(Ok(Error::UnsupportedTreeKind)));
+    assert_eq!(client.try_prove_non_membership(&group_id, &members[0]), Err(Ok(Error::NonMembershipNotSupported)));
+
+    // other trees have no sparse proofs
+    let incremental = client.create_group(&admin, &None);
+    assert_eq!(client.try_get_sparse_proof(&incremental, &members[0]), Err(Ok(Error::UnsupportedTreeKind)));
+}
+
+#[test]
+fn test_delete_sparse_group() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let group_id = client.create_group(&Address::generate(&env), &tree_metadata(TreeKind::Sparse));
+    let member = member_identity_commitment(&env, b"member1_secret");
+    client.add_member(&group_id, &member);
+    let proof = client.get_sparse_proof(&group_id, &member);
+
+    client.archive_group(&group_id);
+    assert_eq!(client.try_verify_sparse_proof(&group_id, &member, &proof), Err(Ok(Error::GroupIsArchived)));
+    assert_eq!(client.delete_group(&group_id, &vec![&env, member.clone()]), 0);
+    assert_eq!(client.try_get_group(&group_id), Err(Ok(Error::GroupDoesNotExist)));
+    assert!(!env.as_contract(&contract_id, || env.storage().persistent().has(&DataKey::SparseTree(group_id))));
+}
+
+#[test]
+fn test_sha256_group() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let metadata = GroupMetadata { hash: HashFunction::Sha256, ..tree_metadata(TreeKind::Incremental).unwrap() };
+    let group_id = client.create_group(&admin, &Some(metadata));
+
+    let mut tree = MerkleTree::with_hash(&env, DEFAULT_DEPTH, Bytes::from_slice(&env, &EMPTY_LEAF), HashFunction::Sha256);
+    assert_ne!(tree.get_root(), empty_tree(&env).get_root());
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            event(&env, &contract_id, GroupTreeSelected { group_id, tree: Symbol::new(&env, "incremental"), hash: Symbol::new(&env, "sha256") }),
+            event(&env, &contract_id, GroupCreated { group_id, admin: admin.clone(), depth: DEFAULT_DEPTH, root: tree.get_root() }),
+            event(&env, &contract_id, GroupAdminUpdated { group_id, old_admin: None, new_admin: admin.clone() }),
+        ]
+    );
+
+    let member = member_identity_commitment(&env, b"member1_secret");
+    client.add_member(&group_id, &member);
+    tree.insert(&env, &member, 0);
+    let info = client.get_group(&group_id);
+    assert_eq!((info.hash, info.root), (HashFunction::Sha256, tree.get_root()));
+
+    let leaf = tree.hash_to_g1(&env, member.clone());
+    let proof = client.get_proof(&group_id, &0);
+    assert!(client.verify_proof(&group_id, &leaf, &proof));
+    // the same path against a keccak256 group of the same members does not
+    let keccak = client.create_group(&admin, &None);
+    client.add_member(&keccak, &member);
+    assert!(!client.verify_proof(&keccak, &leaf, &proof));
+}
+
+#[test]
+fn test_poseidon_group() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let metadata = GroupMetadata { hash: HashFunction::Poseidon, ..tree_metadata(TreeKind::Incremental).unwrap() };
+    let group_id = client.create_group(&admin, &Some(metadata));
+
+    // Poseidon leaves are the commitments themselves, which must be nonzero field elements
+    let a = poseidon::field_to_bytes(&env, &1u64.into());
+    let b = poseidon::field_to_bytes(&env, &2u64.into());
+    for invalid in [Bytes::from_slice(&env, &[0xff; 32]), Bytes::from_slice(&env, &[1; 48]), Bytes::from_slice(&env, &[0; 32])] {
+        assert_eq!(client.try_add_member(&group_id, &invalid), Err(Ok(Error::InvalidIdentityCommitment)));
+    }
+    client.add_members(&group_id, &vec![&env, a.clone(), b.clone()]);
+
+    // the root hashes the two leaves up through the roots of empty subtrees
+    let (mut root, mut empty) = (poseidon::hash2(1u64.into(), 2u64.into()), poseidon::hash2(0u64.into(), 0u64.into()));
+    for _ in 1..DEFAULT_DEPTH {
+        root = poseidon::hash2(root, empty);
+        empty = poseidon::hash2(empty, empty);
+    }
+    assert_eq!(client.get_merkle_root(&group_id), poseidon::field_to_bytes(&env, &root));
+    assert_eq!(client.get_group(&group_id).hash, HashFunction::Poseidon);
+
+    let proof = client.get_proof(&group_id, &1);
+    assert!(client.verify_proof(&group_id, &b, &proof));
+    assert!(!client.verify_proof(&group_id, &a, &proof));
+    assert_eq!(client.try_update_member(&group_id, &a, &Bytes::from_slice(&env, &[0; 32])), Err(Ok(Error::InvalidIdentityCommitment)));
+}
+
+#[test]
+fn test_member_proof() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let members: std::vec::Vec<_> = (0..3u8).map(|i| member_identity_commitment(&env, &[i])).collect();
+    let outsider = member_identity_commitment(&env, b"outsider");
+
+    // one pair of entrypoints proves membership whatever the tree
+    let mut proofs = std::vec::Vec::new();
+    for (tree, hash) in [
+        (TreeKind::Incremental, HashFunction::Keccak256),
+        (TreeKind::Indexed, HashFunction::Sha256),
+        (TreeKind::Sparse, HashFunction::Sha256),
+    ] {
+        let metadata = GroupMetadata { hash, ..tree_metadata(tree).unwrap() };
+        let group_id = client.create_group(&admin, &Some(metadata));
+        client.add_members(&group_id, &vec![&env, members[0].clone(), members[1].clone(), members[2].clone()]);
+        client.remove_member(&group_id, &members[2]);
+
+        for member in &members[..2] {
+            let proof = client.get_member_proof(&group_id, member);
+            assert!(client.verify_member_proof(&group_id, member, &proof));
+            assert!(!client.verify_member_proof(&group_id, &outsider, &proof));
+        }
+        for absent in [&members[2], &outsider] {
+            assert_eq!(client.try_get_member_proof(&group_id, absent), Err(Ok(Error::MemberDoesNotExist)));
+        }
+        proofs.push((group_id, client.get_member_proof(&group_id, &members[0])));
+    }
+    assert!(matches!(proofs[0].1, MemberProof::Merkle(_)));
+    assert!(matches!(proofs[2].1, MemberProof::Sparse(_)));
+
+    // a proof of another group's shape does not verify
+    let (incremental, sparse) = (proofs[0].0, proofs[2].0);
+    assert!(!client.verify_member_proof(&incremental, &members[0], &proofs[2].1));
+    assert!(!client.verify_member_proof(&sparse, &members[0], &proofs[0].1));
+
+    assert_eq!(client.try_get_member_proof(&(sparse + 1), &members[0]), Err(Ok(Error::GroupDoesNotExist)));
+    client.archive_group(&sparse);
+    assert_eq!(
+        client.try_verify_member_proof(&sparse, &members[0], &proofs[2].1),
+        Err(Ok(Error::GroupIsArchived))
+    );
+}
@@ -0,0 +1,362 @@
+//! Trees a group can keep its members in, behind the operations the
+//! contract needs from every one of them.
+
+use crate::datatypes::{HashFunction, TreeKind};
+use crate::imt::MerkleTree;
+use crate::indexed::{self, IndexedTree};
+use crate::proof::MemberProof;
+use crate::smt::SparseMerkleTree;
+use crate::EMPTY_LEAF;
+use soroban_sdk::{Bytes, Env, Map, Vec};
+
+/// Membership changes and proofs every group tree supports. `index` is the
+/// leaf the contract assigned to the member; trees that place members by
+/// key ignore it
+pub trait GroupTree {
+    fn root(&self) -> Bytes;
+
+    /// Proof that the member with `identity_commitment` at `index` is in
+    /// the tree, `None` if there is none
+    fn proof(&self, env: &Env, identity_commitment: &Bytes, index: u32) -> Option<MemberProof>;
+
+    /// Check a proof that `identity_commitment` is in the tree, at one of
+    /// the first `leaf_count` leaves the contract handed out. A proof of
+    /// another shape than the tree's does not verify
+    fn verify(
+        &self,
+        env: &Env,
+        identity_commitment: &Bytes,
+        proof: &MemberProof,
+        leaf_count: u32,
+    ) -> bool;
+
+    fn insert(&mut self, env: &Env, identity_commitment: &Bytes, index: u32);
+
+    fn update(
+        &mut self,
+        env: &Env,
+        old_identity_commitment: &Bytes,
+        new_identity_commitment: &Bytes,
+        index: u32,
+    );
+
+    fn remove(&mut self, env: &Env, identity_commitment: &Bytes, index: u32);
+
+    /// Add members at consecutive leaves from `start_index`
+    fn insert_all(&mut self, env: &Env, identity_commitments: &Vec<Bytes>, start_index: u32) {
+        for (offset, identity_commitment) in identity_commitments.iter().enumerate() {
+            self.insert(env, &identity_commitment, start_index + offset as u32);
+        }
+    }
+
+    /// Apply `(old, new, index)` updates in order
+    fn update_all(&mut self, env: &Env, updates: &Vec<(Bytes, Bytes, u32)>) {
+        for (old_identity_commitment, new_identity_commitment, index) in updates.iter() {
+            self.update(
+                env,
+                &old_identity_commitment,
+                &new_identity_commitment,
+                index,
+            );
+        }
+    }
+
+    /// Remove `(identity_commitment, index)` members in order
+    fn remove_all(&mut self, env: &Env, members: &Vec<(Bytes, u32)>) {
+        for (identity_commitment, index) in members.iter() {
+            self.remove(env, &identity_commitment, index);
+        }
+    }
+}
+
+impl GroupTree for MerkleTree {
+    fn root(&self) -> Bytes {
+        self.get_root()
+    }
+
+    fn proof(&self, _: &Env, _: &Bytes, index: u32) -> Option<MemberProof> {
+        MerkleTree::proof(self, index as usize).map(MemberProof::Merkle)
+    }
+
+    fn verify(
+        &self,
+        env: &Env,
+        identity_commitment: &Bytes,
+        proof: &MemberProof,
+        leaf_count: u32,
+    ) -> bool {
+        let MemberProof::Merkle(proof) = proof else {
+            return false;
+        };
+        let Some(leaf) = self.leaf(env, identity_commitment) else {
+            return false;
+        };
+        self.verify_proof(env, &leaf, proof, leaf_count as usize) == Ok(true)
+    }
+
+    fn insert(&mut self, env: &Env, identity_commitment: &Bytes, index: u32) {
+        // the contract only adds commitments that `Tree::accepts`
+        let leaf = self.leaf(env, identity_commitment).unwrap();
+        self.add_leaf(env, index as usize, leaf);
+    }
+
+    fn update(&mut self, env: &Env, _: &Bytes, new_identity_commitment: &Bytes, index: u32) {
+        self.insert(env, new_identity_commitment, index);
+    }
+
+    fn remove(&mut self, env: &Env, _: &Bytes, index: u32) {
+        self.remove_leaf(env, index as usize);
+    }
+
+    fn insert_all(&mut self, env: &Env, identity_commitments: &Vec<Bytes>, start_index: u32) {
+        let mut leaves = Vec::new(env);
+        for identity_commitment in identity_commitments.iter() {
+            leaves.push_back(self.leaf(env, &identity_commitment).unwrap());
+        }
+        self.add_leaves(env, start_index as usize, &leaves);
+    }
+
+    fn update_all(&mut self, env: &Env, updates: &Vec<(Bytes, Bytes, u32)>) {
+        let mut leaves = Map::new(env);
+        for (_, new_identity_commitment, index) in updates.iter() {
+            leaves.set(index, self.leaf(env, &new_identity_commitment).unwrap());
+        }
+        self.set_leaves(env, &leaves);
+    }
+
+    fn remove_all(&mut self, env: &Env, members: &Vec<(Bytes, u32)>) {
+        let mut leaves = Map::new(env);
+        for (_, index) in members.iter() {
+            leaves.set(index, self.empty_leaf());
+        }
+        self.set_leaves(env, &leaves);
+    }
+}
+
+impl GroupTree for IndexedTree {
+    fn root(&self) -> Bytes {
+        self.tree.get_root()
+    }
+
+    fn proof(&self, _: &Env, _: &Bytes, index: u32) -> Option<MemberProof> {
+        self.tree.proof(index as usize).map(MemberProof::Merkle)
+    }
+
+    fn verify(
+        &self,
+        env: &Env,
+        identity_commitment: &Bytes,
+        proof: &MemberProof,
+        leaf_count: u32,
+    ) -> bool {
+        let MemberProof::Merkle(proof) = proof else {
+            return false;
+        };
+        let key = indexed::key(env, identity_commitment);
+        let Some(next_key) = self.sorted.link(env, &key) else {
+            return false;
+        };
+        let leaf = indexed::leaf(env, &self.tree.hash_function(), &key, &next_key);
+        self.tree
+            .verify_proof(env, &leaf, proof, leaf_count as usize)
+            == Ok(true)
+    }
+
+    fn insert(&mut self, env: &Env, identity_commitment: &Bytes, index: u32) {
+        let key = indexed::key(env, identity_commitment);
+        self.sorted.insert(env, &mut self.tree, key, index);
+    }
+
+    fn update(
+        &mut self,
+        env: &Env,
+        old_identity_commitment: &Bytes,
+        new_identity_commitment: &Bytes,
+        index: u32,
+    ) {
+        self.remove(env, old_identity_commitment, index);
+        self.insert(env, new_identity_commitment, index);
+    }
+
+    fn remove(&mut self, env: &Env, identity_commitment: &Bytes, _: u32) {
+        let key = indexed::key(env, identity_commitment);
+        self.sorted.remove(env, &mut self.tree, &key);
+    }
+}
+
+impl GroupTree for SparseMerkleTree {
+    fn root(&self) -> Bytes {
+        self.get_root()
+    }
+
+    fn proof(&self, env: &Env, identity_commitment: &Bytes, _: u32) -> Option<MemberProof> {
+        SparseMerkleTree::proof(self, env, &indexed::key(env, identity_commitment))
+            .map(MemberProof::Sparse)
+    }
+
+    fn verify(&self, env: &Env, identity_commitment: &Bytes, proof: &MemberProof, _: u32) -> bool {
+        let MemberProof::Sparse(proof) = proof else {
+            return false;
+        };
+        self.verify_proof(env, &indexed::key(env, identity_commitment), proof)
+    }
+
+    fn insert(&mut self, env: &Env, identity_commitment: &Bytes, _: u32) {
+        SparseMerkleTree::insert(self, env, &indexed::key(env, identity_commitment));
+    }
+
+    fn update(
+        &mut self,
+        env: &Env,
+        old_identity_commitment: &Bytes,
+        new_identity_commitment: &Bytes,
+        index: u32,
+    ) {
+        GroupTree::remove(self, env, old_identity_commitment, index);
+        GroupTree::insert(self, env, new_identity_commitment, index);
+    }
+
+    fn remove(&mut self, env: &Env, identity_commitment: &Bytes, _: u32) {
+        SparseMerkleTree::remove(self, env, &indexed::key(env, identity_commitment));
+    }
+}
+
+/// Tree of a group, of whichever kind it was created with
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tree {
+    Incremental(MerkleTree),
+    Indexed(IndexedTree),
+    Sparse(SparseMerkleTree),
+}
+
+impl Tree {
+    /// Empty tree of the given kind, combining its nodes with `hash`.
+    /// `depth` only applies to the kinds that place members by leaf index
+    pub fn new(env: &Env, kind: TreeKind, hash: HashFunction, depth: u32) -> Self {
+        let merkle_tree =
+            || MerkleTree::with_hash(env, depth, Bytes::from_slice(env, &EMPTY_LEAF), hash);
+        match kind {
+            TreeKind::Incremental => Self::Incremental(merkle_tree()),
+            TreeKind::Indexed => Self::Indexed(IndexedTree::new(env, merkle_tree())),
+            TreeKind::Sparse => Self::Sparse(SparseMerkleTree::with_hash(env, hash)),
+        }
+    }
+
+    pub fn kind(&self) -> TreeKind {
+        match self {
+            Self::Incremental(_) => TreeKind::Incremental,
+            Self::Indexed(_) => TreeKind::Indexed,
+            Self::Sparse(_) => TreeKind::Sparse,
+        }
+    }
+
+    pub fn hash_function(&self) -> HashFunction {
+        match self {
+            Self::Incremental(tree) => tree.hash_function(),
+            Self::Indexed(tree) => tree.tree.hash_function(),
+            Self::Sparse(tree) => tree.hash_function(),
+        }
+    }
+
+    /// The tree members are placed in by leaf index, `None` for sparse trees
+    pub fn merkle_tree(&self) -> Option<&MerkleTree> {
+        match self {
+            Self::Incremental(tree) => Some(tree),
+            Self::Indexed(tree) => Some(&tree.tree),
+            Self::Sparse(_) => None,
+        }
+    }
+
+    /// Whether `identity_commitment` can be a member. Incremental Poseidon
+    /// trees hold the commitments themselves, see [`crate::imt::leaf`]
+    pub fn accepts(&self, env: &Env, identity_commitment: &Bytes) -> bool {
+        match self {
+            Self::Incremental(tree) => tree.leaf(env, identity_commitment).is_some(),
+            _ => true,
+        }
+    }
+
+    /// First leaf index given to a member
+    pub fn first_index(&self) -> u32 {
+        match self {
+            // leaf 0 holds the zero key
+            Self::Indexed(_) => 1,
+            _ => 0,
+        }
+    }
+
+    /// Leaf indices members can be given, `None` for sparse trees, which
+    /// place members by key
+    pub fn capacity(&self) -> Option<u32> {
+        self.merkle_tree().map(|tree| tree.num_leaves() as u32)
+    }
+
+    fn inner(&self) -> &dyn GroupTree {
+        match self {
+            Self::Incremental(tree) => tree,
+            Self::Indexed(tree) => tree,
+            Self::Sparse(tree) => tree,
+        }
+    }
+
+    fn inner_mut(&mut self) -> &mut dyn GroupTree {
+        match self {
+            Self::Incremental(tree) => tree,
+            Self::Indexed(tree) => tree,
+            Self::Sparse(tree) => tree,
+        }
+    }
+}
+
+impl GroupTree for Tree {
+    fn root(&self) -> Bytes {
+        self.inner().root()
+    }
+
+    fn proof(&self, env: &Env, identity_commitment: &Bytes, index: u32) -> Option<MemberProof> {
+        self.inner().proof(env, identity_commitment, index)
+    }
+
+    fn verify(
+        &self,
+        env: &Env,
+        identity_commitment: &Bytes,
+        proof: &MemberProof,
+        leaf_count: u32,
+    ) -> bool {
+        self.inner()
+            .verify(env, identity_commitment, proof, leaf_count)
+    }
+
+    fn insert(&mut self, env: &Env, identity_commitment: &Bytes, index: u32) {
+        self.inner_mut().insert(env, identity_commitment, index);
+    }
+
+    fn update(
+        &mut self,
+        env: &Env,
+        old_identity_commitment: &Bytes,
+        new_identity_commitment: &Bytes,
+        index: u32,
+    ) {
+        self.inner_mut()
+            .update(env, old_identity_commitment, new_identity_commitment, index);
+    }
+
+    fn remove(&mut self, env: &Env, identity_commitment: &Bytes, index: u32) {
+        self.inner_mut().remove(env, identity_commitment, index);
+    }
+
+    fn insert_all(&mut self, env: &Env, identity_commitments: &Vec<Bytes>, start_index: u32) {
+        self.inner_mut()
+            .insert_all(env, identity_commitments, start_index);
+    }
+
+    fn update_all(&mut self, env: &Env, updates: &Vec<(Bytes, Bytes, u32)>) {
+        self.inner_mut().update_all(env, updates);
+    }
+
+    fn remove_all(&mut self, env: &Env, members: &Vec<(Bytes, u32)>) {
+        self.inner_mut().remove_all(env, members);
+    }
+}
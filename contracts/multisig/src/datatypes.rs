@@ -0,0 +1,28 @@
+use soroban_sdk::{contracterror, contracttype, BytesN};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(u32)]
+pub enum Error {
+    InvalidThreshold = 1,
+    SignerAlreadyExists = 2,
+    SignerDoesNotExist = 3,
+    UnknownSigner = 4,
+    SignaturesNotSorted = 5,
+    ThresholdNotMet = 6,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum DataKey {
+    Signers,   // Vec<BytesN<32>> of ed25519 public keys, sorted
+    Threshold, // number of signatures an authorization needs
+}
+
+/// One signer's ed25519 signature of the authorization payload
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signature {
+    pub public_key: BytesN<32>,
+    pub signature: BytesN<64>,
+}
@@ -0,0 +1,141 @@
+#![no_std]
+
+//! M-of-N account contract.
+//!
+//! The contract is an `Address` like any other, so it can be set as the admin
+//! of a group in the `group` or `semaphore` contracts. Its `require_auth`
+//! calls are answered by `__check_auth`, which needs ed25519 signatures of
+//! the authorization payload from at least `threshold` of the signers.
+//!
+//! Signers and the threshold are changed by the account itself: a change
+//! needs the same M-of-N authorization as any other call.
+
+use crate::datatypes::{DataKey, Error, Signature};
+use events::{ContractEvent, SignerAdded, SignerRemoved, ThresholdChanged};
+use soroban_sdk::{
+    auth::{Context, CustomAccountInterface},
+    contract, contractimpl,
+    crypto::Hash,
+    panic_with_error, BytesN, Env, Vec,
+};
+
+#[contract]
+pub struct MultisigAccount;
+
+#[contractimpl]
+impl MultisigAccount {
+    /// Create the account with its signers' ed25519 public keys
+    pub fn __constructor(env: Env, signers: Vec<BytesN<32>>, threshold: u32) {
+        let mut sorted = Vec::new(&env);
+        for signer in signers.iter() {
+            match sorted.binary_search(&signer) {
+                Ok(_) => panic_with_error!(&env, Error::SignerAlreadyExists),
+                Err(index) => sorted.insert(index, signer),
+            }
+        }
+        if threshold == 0 || threshold > sorted.len() {
+            panic_with_error!(&env, Error::InvalidThreshold);
+        }
+        env.storage().instance().set(&DataKey::Signers, &sorted);
+        env.storage()
+            .instance()
+            .set(&DataKey::Threshold, &threshold);
+    }
+
+    pub fn signers(env: Env) -> Vec<BytesN<32>> {
+        env.storage().instance().get(&DataKey::Signers).unwrap()
+    }
+
+    pub fn threshold(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::Threshold).unwrap()
+    }
+
+    pub fn set_threshold(env: Env, threshold: u32) -> Result<(), Error> {
+        env.current_contract_address().require_auth();
+        if threshold == 0 || threshold > Self::signers(env.clone()).len() {
+            return Err(Error::InvalidThreshold);
+        }
+
+        let old_threshold = Self::threshold(env.clone());
+        env.storage()
+            .instance()
+            .set(&DataKey::Threshold, &threshold);
+        ThresholdChanged {
+            old_threshold,
+            new_threshold: threshold,
+        }
+        .publish(&env);
+        Ok(())
+    }
+
+    pub fn add_signer(env: Env, public_key: BytesN<32>) -> Result<(), Error> {
+        env.current_contract_address().require_auth();
+        let mut signers = Self::signers(env.clone());
+        let index = match signers.binary_search(&public_key) {
+            Ok(_) => return Err(Error::SignerAlreadyExists),
+            Err(index) => index,
+        };
+
+        signers.insert(index, public_key.clone());
+        env.storage().instance().set(&DataKey::Signers, &signers);
+        SignerAdded { public_key }.publish(&env);
+        Ok(())
+    }
+
+    /// Remove a signer. Lower the threshold first if fewer than `threshold`
+    /// signers would be left
+    pub fn remove_signer(env: Env, public_key: BytesN<32>) -> Result<(), Error> {
+        env.current_contract_address().require_auth();
+        let mut signers = Self::signers(env.clone());
+        let index = signers
+            .binary_search(&public_key)
+            .map_err(|_| Error::SignerDoesNotExist)?;
+        if signers.len() - 1 < Self::threshold(env.clone()) {
+            return Err(Error::InvalidThreshold);
+        }
+
+        signers.remove(index);
+        env.storage().instance().set(&DataKey::Signers, &signers);
+        SignerRemoved { public_key }.publish(&env);
+        Ok(())
+    }
+}
+
+#[contractimpl]
+impl CustomAccountInterface for MultisigAccount {
+    type Signature = Vec<Signature>;
+    type Error = Error;
+
+    /// Signatures must be sorted by public key, which also rules out counting
+    /// a signer twice. An invalid signature traps in the host
+    #[allow(non_snake_case)]
+    fn __check_auth(
+        env: Env,
+        signature_payload: Hash<32>,
+        signatures: Vec<Signature>,
+        _auth_contexts: Vec<Context>,
+    ) -> Result<(), Error> {
+        let signers = Self::signers(env.clone());
+        let payload = signature_payload.to_bytes().into();
+        let mut previous: Option<BytesN<32>> = None;
+        for signature in signatures.iter() {
+            if previous.is_some_and(|previous| previous >= signature.public_key) {
+                return Err(Error::SignaturesNotSorted);
+            }
+            if signers.binary_search(&signature.public_key).is_err() {
+                return Err(Error::UnknownSigner);
+            }
+            env.crypto()
+                .ed25519_verify(&signature.public_key, &payload, &signature.signature);
+            previous = Some(signature.public_key);
+        }
+
+        if signatures.len() < Self::threshold(env) {
+            return Err(Error::ThresholdNotMet);
+        }
+        Ok(())
+    }
+}
+
+pub mod datatypes;
+mod test;
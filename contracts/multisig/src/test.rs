@@ -0,0 +1,312 @@
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use ed25519_dalek::{Signer, SigningKey};
+use events::SCHEMA_VERSION;
+use group::{SemaphoreGroupContract, SemaphoreGroupContractClient};
+use soroban_sdk::{
+    testutils::{Address as _, Events},
+    vec,
+    xdr::{
+        self, HashIdPreimage, HashIdPreimageSorobanAuthorization, InvokeContractArgs, Limits,
+        ScAddress, ScVal, SorobanAddressCredentials, SorobanAuthorizationEntry,
+        SorobanAuthorizedFunction, SorobanAuthorizedInvocation, SorobanCredentials, WriteXdr,
+    },
+    Address, Bytes, IntoVal, Symbol, TryFromVal, Val,
+};
+
+fn key(seed: u8) -> SigningKey {
+    SigningKey::from_bytes(&[seed; 32])
+}
+
+fn public_key(env: &Env, key: &SigningKey) -> BytesN<32> {
+    BytesN::from_array(env, &key.verifying_key().to_bytes())
+}
+
+/// Signatures of `payload` by `keys`, sorted by public key
+fn sign(env: &Env, payload: &[u8; 32], keys: &[&SigningKey]) -> Vec<Signature> {
+    let mut keys = std::vec::Vec::from(keys);
+    keys.sort_by_key(|key| key.verifying_key().to_bytes());
+    let mut signatures = Vec::new(env);
+    for key in keys {
+        signatures.push_back(Signature {
+            public_key: public_key(env, key),
+            signature: BytesN::from_array(env, &key.sign(payload).to_bytes()),
+        });
+    }
+    signatures
+}
+
+/// 2-of-3 account over `key(1)`, `key(2)` and `key(3)`
+fn setup(env: &Env) -> (Address, MultisigAccountClient<'_>) {
+    let signers = vec![
+        env,
+        public_key(env, &key(1)),
+        public_key(env, &key(2)),
+        public_key(env, &key(3)),
+    ];
+    let contract_id = env.register(MultisigAccount, (signers, 2u32));
+    (
+        contract_id.clone(),
+        MultisigAccountClient::new(env, &contract_id),
+    )
+}
+
+/// An authorization entry for `account` calling `function` on `contract`,
+/// signed by `keys`
+fn authorize(
+    env: &Env,
+    account: &Address,
+    keys: &[&SigningKey],
+    contract: &Address,
+    function: &str,
+    args: Vec<Val>,
+) -> SorobanAuthorizationEntry {
+    let invocation = SorobanAuthorizedInvocation {
+        function: SorobanAuthorizedFunction::ContractFn(InvokeContractArgs {
+            contract_address: ScAddress::from(contract),
+            function_name: function.try_into().unwrap(),
+            args: args
+                .iter()
+                .map(|arg| ScVal::try_from_val(env, &arg).unwrap())
+                .collect::<std::vec::Vec<_>>()
+                .try_into()
+                .unwrap(),
+        }),
+        sub_invocations: Default::default(),
+    };
+    let nonce = env.ledger().sequence() as i64;
+    let signature_expiration_ledger = env.ledger().sequence() + 100;
+    let preimage = HashIdPreimage::SorobanAuthorization(HashIdPreimageSorobanAuthorization {
+        network_id: xdr::Hash(env.ledger().network_id().to_array()),
+        nonce,
+        signature_expiration_ledger,
+        invocation: invocation.clone(),
+    });
+    let preimage = Bytes::from_slice(env, &preimage.to_xdr(Limits::none()).unwrap());
+    let payload = env.crypto().sha256(&preimage).to_array();
+
+    let signatures = sign(env, &payload, keys);
+    SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+            address: ScAddress::from(account),
+            nonce,
+            signature_expiration_ledger,
+            signature: ScVal::try_from_val(env, &signatures.to_val()).unwrap(),
+        }),
+        root_invocation: invocation,
+    }
+}
+
+fn check_auth(env: &Env, account: &Address, signatures: Vec<Signature>) -> Result<(), Error> {
+    env.try_invoke_contract_check_auth::<Error>(
+        account,
+        &BytesN::from_array(env, &[7; 32]),
+        signatures.into_val(env),
+        &vec![env],
+    )
+    .map_err(|err| err.unwrap())
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #1)")]
+fn test_constructor_threshold_above_signers() {
+    let env = Env::default();
+    let signers = vec![&env, public_key(&env, &key(1))];
+    env.register(MultisigAccount, (signers, 2u32));
+}
+
+#[test]
+fn test_check_auth() {
+    let env = Env::default();
+    let (account, client) = setup(&env);
+    assert_eq!(client.threshold(), 2);
+    assert_eq!(client.signers().len(), 3);
+
+    assert_eq!(
+        check_auth(&env, &account, sign(&env, &[7; 32], &[&key(1), &key(3)])),
+        Ok(())
+    );
+    assert_eq!(
+        check_auth(
+            &env,
+            &account,
+            sign(&env, &[7; 32], &[&key(1), &key(2), &key(3)])
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        check_auth(&env, &account, sign(&env, &[7; 32], &[&key(2)])),
+        Err(Error::ThresholdNotMet)
+    );
+    assert_eq!(
+        check_auth(&env, &account, sign(&env, &[7; 32], &[&key(1), &key(4)])),
+        Err(Error::UnknownSigner)
+    );
+
+    // the same signer twice does not count towards the threshold
+    let signature = sign(&env, &[7; 32], &[&key(1)]).get(0).unwrap();
+    assert_eq!(
+        check_auth(&env, &account, vec![&env, signature.clone(), signature]),
+        Err(Error::SignaturesNotSorted)
+    );
+
+    // a signature of another payload traps in the host
+    assert!(env
+        .try_invoke_contract_check_auth::<Error>(
+            &account,
+            &BytesN::from_array(&env, &[7; 32]),
+            sign(&env, &[8; 32], &[&key(1), &key(2)]).into_val(&env),
+            &vec![&env],
+        )
+        .is_err());
+}
+
+#[test]
+fn test_group_admin() {
+    let env = Env::default();
+    let (account, _) = setup(&env);
+    let group_contract = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let group = SemaphoreGroupContractClient::new(&env, &group_contract);
+    let group_id = group.create_group(&account, &None);
+
+    let commitment = Bytes::from_array(&env, &[1; 32]);
+    let args: Vec<Val> = (group_id, commitment.clone()).into_val(&env);
+
+    // one signature is not enough
+    env.set_auths(&[authorize(
+        &env,
+        &account,
+        &[&key(1)],
+        &group_contract,
+        "add_member",
+        args.clone(),
+    )]);
+    assert!(group.try_add_member(&group_id, &commitment).is_err());
+    assert!(!group.is_member(&group_id, &commitment));
+
+    env.set_auths(&[authorize(
+        &env,
+        &account,
+        &[&key(1), &key(3)],
+        &group_contract,
+        "add_member",
+        args,
+    )]);
+    group.add_member(&group_id, &commitment);
+    assert!(group.is_member(&group_id, &commitment));
+}
+
+#[test]
+fn test_set_threshold() {
+    let env = Env::default();
+    let (account, client) = setup(&env);
+
+    env.set_auths(&[authorize(
+        &env,
+        &account,
+        &[&key(1), &key(2)],
+        &account,
+        "set_threshold",
+        (3u32,).into_val(&env),
+    )]);
+    client.set_threshold(&3);
+    assert_eq!(client.threshold(), 3);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                account.clone(),
+                (Symbol::new(&env, "threshold_changed"), SCHEMA_VERSION).into_val(&env),
+                ThresholdChanged {
+                    old_threshold: 2,
+                    new_threshold: 3,
+                }
+                .into_val(&env),
+            ),
+        ]
+    );
+
+    // two signatures no longer pass
+    assert_eq!(
+        check_auth(&env, &account, sign(&env, &[7; 32], &[&key(1), &key(2)])),
+        Err(Error::ThresholdNotMet)
+    );
+
+    env.mock_all_auths();
+    assert_eq!(
+        client.try_set_threshold(&4),
+        Err(Ok(Error::InvalidThreshold))
+    );
+    assert_eq!(
+        client.try_set_threshold(&0),
+        Err(Ok(Error::InvalidThreshold))
+    );
+}
+
+#[test]
+fn test_set_threshold_requires_signers() {
+    let env = Env::default();
+    let (account, client) = setup(&env);
+
+    env.set_auths(&[authorize(
+        &env,
+        &account,
+        &[&key(1)],
+        &account,
+        "set_threshold",
+        (1u32,).into_val(&env),
+    )]);
+    assert!(client.try_set_threshold(&1).is_err());
+    assert_eq!(client.threshold(), 2);
+}
+
+#[test]
+fn test_add_and_remove_signer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (account, client) = setup(&env);
+    let new_signer = public_key(&env, &key(4));
+
+    client.add_signer(&new_signer);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            (
+                account.clone(),
+                (Symbol::new(&env, "signer_added"), SCHEMA_VERSION).into_val(&env),
+                SignerAdded {
+                    public_key: new_signer.clone(),
+                }
+                .into_val(&env),
+            ),
+        ]
+    );
+    assert_eq!(
+        client.try_add_signer(&new_signer),
+        Err(Ok(Error::SignerAlreadyExists))
+    );
+    assert_eq!(
+        check_auth(&env, &account, sign(&env, &[7; 32], &[&key(1), &key(4)])),
+        Ok(())
+    );
+
+    client.remove_signer(&public_key(&env, &key(1)));
+    client.remove_signer(&public_key(&env, &key(2)));
+    assert_eq!(
+        check_auth(&env, &account, sign(&env, &[7; 32], &[&key(1), &key(4)])),
+        Err(Error::UnknownSigner)
+    );
+    assert_eq!(
+        client.try_remove_signer(&public_key(&env, &key(1))),
+        Err(Ok(Error::SignerDoesNotExist))
+    );
+    // two signers left with a threshold of two
+    assert_eq!(
+        client.try_remove_signer(&public_key(&env, &key(3))),
+        Err(Ok(Error::InvalidThreshold))
+    );
+}
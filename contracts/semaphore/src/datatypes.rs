@@ -1,5 +1,6 @@
 use soroban_sdk::contracterror;
 use soroban_sdk::contracttype;
+use soroban_sdk::BytesN;
 
 #[contracterror]
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -15,21 +16,34 @@ pub enum Error {
     InvalidMerkleTreeDepth = 8,
     GroupHasNoMembers = 9,
     MerkleTreeRootNotInGroup = 10,
+    MerkleTreeRootExpired = 11,
+    NullifierAlreadyUsed = 12,
+    InvalidProof = 13,
+    UnsupportedSchemaVersion = 14,
+    ContractPaused = 15,
+    GroupIsArchived = 16,
+    GroupIsNotArchived = 17,
 }
 
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
 pub enum DataKey {
-    Admin(u32),        // maps group_id -> admin address
-    PendingAdmin(u32), // maps group_id -> pending admin address
-    Member(u32, u32),  // maps (group_id, identity_commitment) -> Member
-    MemberCount(u32),  // maps group_id -> number of members
+    Verifier,                    // address of the verifier contract
+    GroupCounter,                // next group id to assign
+    Group(u32),                  // maps group_id -> Group, persistent since schema 2
+    Nullifier(u32, BytesN<32>),  // maps (group_id, nullifier) -> used flag, persistent since schema 2
+    Owner,                       // contract owner, may upgrade the contract
+    SchemaVersion,               // version of the storage layout
+    Paused,                      // set while the owner has paused the contract
+    Status(u32),                 // maps group_id -> GroupStatus, persistent, active when unset
 }
 
 #[contracttype]
-#[derive(Clone, Debug, PartialEq)]
-pub struct Member {
-    pub identity_commitment: u32,
-    pub group_id: u32,
-    pub index: u32, // Position in the group
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GroupStatus {
+    Active,
+    /// Read-only, and proofs against it are rejected
+    Archived,
+    /// Removed; its nullifiers stay spent and the id is never reused
+    Deleted,
 }
@@ -1,54 +1,152 @@
 #![no_std]
 
-use crate::datatypes::{DataKey, Error, Member};
-use crate::interface::SemaphoreGroupInterface;
-use soroban_sdk::{contract, contractimpl, Address, BytesN, Env, Map, Symbol, Vec};
+use crate::datatypes::{DataKey, Error, GroupStatus};
+use events::{
+    ContractEvent, GroupAdminUpdated, GroupArchived, GroupCreated, GroupDeleted, GroupEvent,
+    MemberAdded, Paused, ProofValidated, Unpaused,
+};
+use crate::verifier::{groth16_proof, public_inputs, Groth16Proof, VerifierClient};
+use soroban_sdk::{contract, contractimpl, contracttype, Address, Bytes, BytesN, Env, Map, Vec};
 
 // Constants
 const MIN_DEPTH: u32 = 16;
 const MAX_DEPTH: u32 = 32;
 
+/// Version of the storage layout written by this code:
+/// 1. everything in instance storage
+/// 2. groups and nullifiers in persistent storage
+pub const STORAGE_SCHEMA_VERSION: u32 = 2;
+
+/// Ledgers in a day, at five seconds a ledger
+pub const DAY_IN_LEDGERS: u32 = 17_280;
+
+/// A persistent entry used with fewer ledgers than this left is extended
+pub const TTL_THRESHOLD: u32 = 30 * DAY_IN_LEDGERS;
+
+/// Ledgers an extended persistent entry lives for. Expired entries are
+/// archived rather than deleted, so a spent nullifier has to be restored
+/// before a proof using it can even be checked
+pub const TTL: u32 = 60 * DAY_IN_LEDGERS;
+
+#[contracttype]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SemaphoreProof {
-    merkle_tree_depth: u32,
-    merkle_tree_root: BytesN<32>,
-    nullifier: BytesN<32>,
-    message: BytesN<32>,
-    scope: BytesN<32>,
-    points: Vec<BytesN<32>>, // Contains 8 points
+    pub merkle_tree_depth: u32,
+    pub merkle_tree_root: BytesN<32>,
+    pub nullifier: BytesN<32>,
+    pub message: BytesN<32>,
+    pub scope: BytesN<32>,
+    pub points: Vec<BytesN<32>>, // Contains 8 points
 }
+
+#[contracttype]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Group {
-    admin: Address,
-    merkle_tree_duration: u64,
-    merkle_root_creation_dates: Map<BytesN<32>, u64>,
-    merkle_tree: MerkleTree, //IMT?
+    pub admin: Address,
+    pub merkle_tree_duration: u64,
+    pub merkle_root_creation_dates: Map<BytesN<32>, u64>,
+    pub merkle_tree: MerkleTree, //IMT?
 }
+
+#[contracttype]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MerkleTree {
-    depth: u32,
-    leaves: Vec<BytesN<32>>,
+    pub depth: u32,
+    pub leaves: Vec<BytesN<32>>,
 }
+
 #[contract]
-pub struct Semaphore {
-    verifier: Address, // Address of the verifier contract
-}
+pub struct Semaphore;
 
 #[contractimpl]
 impl Semaphore {
-    pub fn new(env: Env, verifier: Address) -> Self {
-        Self { verifier }
+    pub fn __constructor(env: Env, owner: Address, verifier: Address) {
+        // Owner of the contract, who may upgrade it
+        env.storage().instance().set(&DataKey::Owner, &owner);
+        // Address of the verifier contract
+        env.storage().instance().set(&DataKey::Verifier, &verifier);
+        env.storage()
+            .instance()
+            .set(&DataKey::SchemaVersion, &STORAGE_SCHEMA_VERSION);
+    }
+
+    pub fn owner(env: Env) -> Address {
+        env.storage().instance().get(&DataKey::Owner).unwrap()
+    }
+
+    /// Replace the contract's code, keeping its storage. Call `migrate`
+    /// afterwards if the new code uses a newer storage layout
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        Self::owner(env.clone()).require_auth();
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    /// Layout of the data in storage; 1 for data written before the version
+    /// was recorded
+    pub fn schema_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(1)
+    }
+
+    /// Convert the stored data to the layout of this code, one version at a
+    /// time. Nullifiers cannot be listed, so `nullifiers` lists the
+    /// `(group_id, nullifier)` pairs spent in layout 1, as the
+    /// `ProofValidated` events record them, to keep them spent
+    pub fn migrate(env: Env, nullifiers: Vec<(u32, BytesN<32>)>) -> Result<(), Error> {
+        Self::owner(env.clone()).require_auth();
+        let mut version = Self::schema_version(env.clone());
+        if version > STORAGE_SCHEMA_VERSION {
+            return Err(Error::UnsupportedSchemaVersion);
+        }
+
+        while version < STORAGE_SCHEMA_VERSION {
+            match version {
+                1 => migrate_to_persistent(&env, &nullifiers),
+                _ => unreachable!(),
+            }
+            version += 1;
+        }
+        env.storage().instance().set(&DataKey::SchemaVersion, &version);
+        Ok(())
+    }
+
+    /// Stop group changes and proof validation until `unpause`. Views keep
+    /// working
+    pub fn pause(env: Env) {
+        let owner = Self::owner(env.clone());
+        owner.require_auth();
+        env.storage().instance().set(&DataKey::Paused, &true);
+        Paused {
+            by: owner,
+            timestamp: env.ledger().timestamp(),
+        }
+        .publish(&env);
     }
 
-    pub fn create_group(
-        env: &Env,
-        admin: Address,
-        merkle_tree_duration: u64,
-    ) -> Result<u32, Error> {
+    pub fn unpause(env: Env) {
+        let owner = Self::owner(env.clone());
+        owner.require_auth();
+        env.storage().instance().remove(&DataKey::Paused);
+        Unpaused {
+            by: owner,
+            timestamp: env.ledger().timestamp(),
+        }
+        .publish(&env);
+    }
+
+    pub fn is_paused(env: Env) -> bool {
+        env.storage().instance().has(&DataKey::Paused)
+    }
+
+    pub fn create_group(env: Env, admin: Address, merkle_tree_duration: u64) -> Result<u32, Error> {
+        require_not_paused(&env)?;
+
         let group_id = env
             .storage()
             .instance()
-            .get::<_, u32>("group_counter")
+            .get::<_, u32>(&DataKey::GroupCounter)
             .unwrap_or(0);
 
         // Create new group
@@ -63,69 +161,114 @@ impl Semaphore {
         };
 
         // Store group
-        env.storage().instance().set(&group_id.to_string(), &group);
+        save_group(&env, group_id, &group);
         env.storage()
             .instance()
-            .set("group_counter", &(group_id + 1));
+            .set(&DataKey::GroupCounter, &(group_id + 1));
 
-        // Emit event
-        env.events().publish(
-            (Symbol::new(&env, "group_created"), group_id, admin),
+        // Emit events
+        GroupCreated {
             group_id,
-        );
+            admin: admin.clone(),
+            depth: MIN_DEPTH,
+            root: Bytes::from_slice(&env, &[0u8; 32]),
+        }
+        .publish(&env);
+
+        GroupAdminUpdated {
+            group_id,
+            old_admin: None,
+            new_admin: admin,
+        }
+        .publish(&env);
 
         Ok(group_id)
     }
 
-    pub fn update_group_admin(env: &Env, group_id: u32, new_admin: Address) -> Result<(), Error> {
-        let mut group: Group = env
-            .storage()
-            .instance()
-            .get(&group_id.to_string())
-            .ok_or(Error::GroupDoesNotExist)?;
+    pub fn update_group_admin(env: Env, group_id: u32, new_admin: Address) -> Result<(), Error> {
+        let mut group: Group = load_group(&env, group_id)?;
 
         // Verify caller is current admin
         group.admin.require_auth();
 
         // Update admin
+        let old_admin = group.admin.clone();
         group.admin = new_admin.clone();
-        env.storage().instance().set(&group_id.to_string(), &group);
+        save_group(&env, group_id, &group);
 
         // Emit event
-        env.events().publish(
-            (
-                Symbol::new(&env, "group_admin_updated"),
-                group_id,
-                new_admin,
-            ),
-            (),
-        );
+        GroupAdminUpdated {
+            group_id,
+            old_admin: Some(old_admin),
+            new_admin,
+        }
+        .publish(&env);
 
         Ok(())
     }
 
+    /// Make a group read-only; proofs against it are rejected from now on
+    pub fn archive_group(env: Env, group_id: u32) -> Result<(), Error> {
+        require_not_paused(&env)?;
+
+        let group = load_group(&env, group_id)?;
+        group.admin.require_auth();
+        require_active(&env, group_id)?;
+
+        set_status(&env, group_id, GroupStatus::Archived);
+        GroupArchived { group_id }.publish(&env);
+        Ok(())
+    }
+
+    /// Remove an archived group. Its nullifiers stay spent and its id is
+    /// never assigned again, so old proofs cannot be replayed
+    pub fn delete_group(env: Env, group_id: u32) -> Result<(), Error> {
+        require_not_paused(&env)?;
+
+        let group = load_group(&env, group_id)?;
+        group.admin.require_auth();
+        if Self::get_group_status(env.clone(), group_id)? != GroupStatus::Archived {
+            return Err(Error::GroupIsNotArchived);
+        }
+
+        env.storage().persistent().remove(&DataKey::Group(group_id));
+        set_status(&env, group_id, GroupStatus::Deleted);
+        GroupDeleted { group_id }.publish(&env);
+        Ok(())
+    }
+
+    pub fn get_group_status(env: Env, group_id: u32) -> Result<GroupStatus, Error> {
+        match env.storage().persistent().get(&DataKey::Status(group_id)) {
+            Some(status) => Ok(status),
+            None if env.storage().persistent().has(&DataKey::Group(group_id)) => {
+                Ok(GroupStatus::Active)
+            }
+            None => Err(Error::GroupDoesNotExist),
+        }
+    }
+
     pub fn add_member(
-        env: &Env,
+        env: Env,
         group_id: u32,
         identity_commitment: BytesN<32>,
     ) -> Result<(), Error> {
-        let mut group: Group = env
-            .storage()
-            .instance()
-            .get(&group_id.to_string())
-            .ok_or(Error::GroupDoesNotExist)?;
+        require_not_paused(&env)?;
+
+        let mut group: Group = load_group(&env, group_id)?;
+        require_active(&env, group_id)?;
 
         // Verify caller is admin
         group.admin.require_auth();
 
         // Add member to merkle tree
+        let index = group.merkle_tree.leaves.len();
         group
             .merkle_tree
             .leaves
             .push_back(identity_commitment.clone());
 
         // Calculate new root
-        let new_root = self.calculate_merkle_root(&group.merkle_tree)?;
+        let new_root = calculate_merkle_root(&env, &group.merkle_tree)?;
 
         // Update root creation date
         group
@@ -133,434 +276,258 @@ impl Semaphore {
             .set(new_root.clone(), env.ledger().timestamp());
 
         // Update group storage
-        env.storage().instance().set(&group_id.to_string(), &group);
+        save_group(&env, group_id, &group);
 
         // Emit event
-        env.events().publish(
-            (
-                Symbol::new(&env, "member_added"),
-                group_id,
-                identity_commitment,
-                new_root,
-            ),
-            (),
-        );
+        MemberAdded {
+            group_id,
+            identity_commitment: identity_commitment.into(),
+            index,
+            root: new_root.into(),
+        }
+        .publish(&env);
 
         Ok(())
     }
 
-    pub fn validate_proof(env: &Env, group_id: u32, proof: SemaphoreProof) -> Result<(), Error> {
-        let group: Group = env
-            .storage()
-            .instance()
-            .get(&group_id.to_string())
-            .ok_or(Error::GroupDoesNotExist)?;
+    pub fn validate_proof(env: Env, group_id: u32, proof: SemaphoreProof) -> Result<(), Error> {
+        require_not_paused(&env)?;
 
-        // Verify nullifier hasn't been used
-        let nullifier_key = format!("nullifier:{}:{}", group_id, proof.nullifier.to_hex());
-        if env.storage().instance().has(&nullifier_key) {
-            return Err(Error::NullifierAlreadyUsed);
-        }
-
-        // Verify merkle tree depth
-        if proof.merkle_tree_depth < MIN_DEPTH || proof.merkle_tree_depth > MAX_DEPTH {
-            return Err(Error::InvalidMerkleTreeDepth);
-        }
-
-        // Verify group has members
-        if group.merkle_tree.leaves.is_empty() {
-            return Err(Error::GroupHasNoMembers);
-        }
-
-        // Check merkle root validity and expiration
-        let current_root = self.calculate_merkle_root(&group.merkle_tree)?;
-        if proof.merkle_tree_root != current_root {
-            let creation_date = group
-                .merkle_root_creation_dates
-                .get(proof.merkle_tree_root.clone())
-                .ok_or(Error::MerkleTreeRootNotInGroup)?;
-
-            if env.ledger().timestamp() > creation_date + group.merkle_tree_duration {
-                return Err(Error::MerkleTreeRootExpired);
-            }
-        }
+        let group: Group = load_group(&env, group_id)?;
+        require_active(&env, group_id)?;
+        let current_root = calculate_merkle_root(&env, &group.merkle_tree).ok();
+        check_proof(&env, group_id, &group, &current_root, &proof)?;
 
         // Verify the proof using the verifier contract
-        let result = self.verify_proof(&proof)?;
-        if !result {
+        let groth16_proof = groth16_proof(&env, &proof).ok_or(Error::InvalidProof)?;
+        let result = verifier(&env).try_verify(
+            &proof.merkle_tree_depth,
+            &groth16_proof,
+            &public_inputs(&env, &proof),
+        );
+        if result != Ok(Ok(true)) {
             return Err(Error::InvalidProof);
         }
 
-        // Mark nullifier as used
-        env.storage().instance().set(&nullifier_key, &true);
-
-        // Emit event
-        env.events().publish(
-            (
-                Symbol::new(&env, "proof_validated"),
-                group_id,
-                proof.merkle_tree_root,
-                proof.nullifier,
-                proof.message,
-                proof.scope,
-            ),
-            (),
-        );
-
+        spend_nullifier(&env, group_id, proof);
         Ok(())
     }
 
-    // Internal helper functions
-
-    fn calculate_merkle_root(&self, tree: &MerkleTree) -> Result<BytesN<32>, Error> {
-        if tree.leaves.is_empty() {
-            return Err(Error::GroupHasNoMembers);
+    /// Validate several proofs for a group, with one batch verification per
+    /// tree depth. Either every proof is valid and all their nullifiers are
+    /// spent, or nothing is
+    pub fn validate_proofs(
+        env: Env,
+        group_id: u32,
+        proofs: Vec<SemaphoreProof>,
+    ) -> Result<(), Error> {
+        require_not_paused(&env)?;
+
+        let group: Group = load_group(&env, group_id)?;
+        require_active(&env, group_id)?;
+        let current_root = calculate_merkle_root(&env, &group.merkle_tree).ok();
+
+        // Proofs and public inputs by tree depth, one batch per verification key
+        let mut batches: Map<u32, (Vec<Groth16Proof>, Vec<Vec<BytesN<32>>>)> = Map::new(&env);
+        let mut nullifiers = Vec::new(&env);
+        for proof in proofs.iter() {
+            check_proof(&env, group_id, &group, &current_root, &proof)?;
+            // a nullifier can only be spent once within the batch too
+            if nullifiers.contains(&proof.nullifier) {
+                return Err(Error::NullifierAlreadyUsed);
+            }
+            nullifiers.push_back(proof.nullifier.clone());
+
+            let groth16_proof = groth16_proof(&env, &proof).ok_or(Error::InvalidProof)?;
+            let (mut batch, mut inputs) = batches
+                .get(proof.merkle_tree_depth)
+                .unwrap_or((Vec::new(&env), Vec::new(&env)));
+            batch.push_back(groth16_proof);
+            inputs.push_back(public_inputs(&env, &proof));
+            batches.set(proof.merkle_tree_depth, (batch, inputs));
         }
 
-        let mut current_level = tree.leaves.clone();
-
-        while current_level.len() > 1 {
-            let mut next_level = Vec::new(&self.env);
-
-            for i in (0..current_level.len()).step_by(2) {
-                let left = current_level.get(i).unwrap();
-                let right = if i + 1 < current_level.len() {
-                    current_level.get(i + 1).unwrap()
-                } else {
-                    left.clone()
-                };
-
-                next_level.push_back(self.hash_pair(&left, &right));
+        // Verify every batch before spending any nullifier
+        for (depth, (batch, inputs)) in batches.iter() {
+            match verifier(&env).try_verify_batch(&depth, &batch, &inputs) {
+                Ok(Ok(results)) if results.iter().all(|valid| valid) => {}
+                _ => return Err(Error::InvalidProof),
             }
-
-            current_level = next_level;
         }
 
-        Ok(current_level.get(0).unwrap())
-    }
-
-    fn hash_pair(&self, left: &BytesN<32>, right: &BytesN<32>) -> BytesN<32> {
-        let mut data = Vec::new(&self.env);
-        data.push_back(left.clone());
-        data.push_back(right.clone());
-        self.env.crypto().keccak256(&data.into())
-    }
-
-    fn verify_proof(&self, proof: &SemaphoreProof) -> Result<bool, Error> {
-        // Call external verifier contract
-        // Note: This is a simplified version. In practice, you'd need to implement
-        // the actual zero-knowledge proof verification logic or call an external verifier
-        Ok(true)
-    }
-}
-#[contract]
-pub struct SemaphoreGroupContract;
-
-#[contractimpl]
-impl SemaphoreGroupInterface for SemaphoreGroupContract {
-    fn create_group(env: Env, group_id: u32, admin: Address) -> Result<(), Error> {
-        let admin_key = DataKey::Admin(group_id);
-
-        // Check if group already exists
-        if env.storage().instance().has(&admin_key) {
-            return Err(Error::GroupAlreadyExists);
+        for proof in proofs.iter() {
+            spend_nullifier(&env, group_id, proof);
         }
-
-        // Initialize group
-        env.storage().instance().set(&admin_key, &admin);
-        env.storage()
-            .instance()
-            .set(&DataKey::MemberCount(group_id), &0u32);
-
-        // Emit events using Symbol for event names and proper tuple syntax
-        env.events()
-            .publish((Symbol::new(&env, "group_created"), group_id), group_id);
-
-        env.events()
-            .publish((Symbol::new(&env, "group_admin_updated"), group_id), admin);
-
         Ok(())
     }
+}
 
-    fn update_group_admin(env: Env, group_id: u32, new_admin: Address) -> Result<(), Error> {
-        let admin_key = DataKey::Admin(group_id);
-        let current_admin: Address = env
-            .storage()
-            .instance()
-            .get(&admin_key)
-            .ok_or(Error::GroupDoesNotExist)?;
-
-        // Verify caller is current admin
-        (&current_admin).require_auth();
-
-        // Set pending admin
-        let pending_admin_key = DataKey::PendingAdmin(group_id);
-        env.storage().instance().set(&pending_admin_key, &new_admin);
-
-        // Emit event with all information in topics
-        env.events().publish(
-            (
-                Symbol::new(&env, "group_admin_pending"),
-                group_id,
-                current_admin,
-                new_admin,
-            ),
-            (),
-        );
+// Internal helper functions
 
-        Ok(())
+fn require_not_paused(env: &Env) -> Result<(), Error> {
+    if env.storage().instance().has(&DataKey::Paused) {
+        return Err(Error::ContractPaused);
     }
+    Ok(())
+}
 
-    fn accept_group_admin(env: Env, group_id: u32) -> Result<(), Error> {
-        let pending_admin_key = DataKey::PendingAdmin(group_id);
-        let admin_key = DataKey::Admin(group_id);
-
-        // Verify group exists
-        let current_admin: Address = env
-            .storage()
-            .instance()
-            .get::<_, Address>(&admin_key)
-            .ok_or(Error::GroupDoesNotExist)?;
-
-        // Get and verify pending admin
-        let new_admin: Address = env
-            .storage()
-            .instance()
-            .get::<_, Address>(&pending_admin_key)
-            .ok_or(Error::CallerIsNotThePendingGroupAdmin)?;
-
-        // Verify caller is the pending admin
-        (&new_admin).require_auth();
-
-        // Update admin
-        env.storage().instance().set(&admin_key, &new_admin);
-        env.storage().instance().remove(&pending_admin_key);
-
-        // Emit event
-        env.events().publish(
-            (
-                Symbol::new(&env, "group_admin_updated"),
-                group_id,
-                current_admin,
-                new_admin,
-            ),
-            (),
-        );
-
-        Ok(())
-    }
-    fn get_pending_admin(env: Env, group_id: u32) -> Result<Address, Error> {
-        let pending_admin_key = DataKey::PendingAdmin(group_id);
-        // Try to get the pending admin; return an error if not set
-        env.storage()
-            .instance()
-            .get(&pending_admin_key)
-            .ok_or(Error::CallerIsNotThePendingGroupAdmin)
+/// Archived groups are read-only
+fn require_active(env: &Env, group_id: u32) -> Result<(), Error> {
+    match env.storage().persistent().get(&DataKey::Status(group_id)) {
+        Some(GroupStatus::Archived) => Err(Error::GroupIsArchived),
+        _ => Ok(()),
     }
+}
 
-    fn add_member(env: Env, group_id: u32, identity_commitment: u32) -> Result<(), Error> {
-        if identity_commitment == 0 {
-            return Err(Error::InvalidIdentityCommitment);
-        }
-
-        let admin_key = DataKey::Admin(group_id);
-        let admin = env
-            .storage()
-            .instance()
-            .get::<_, Address>(&admin_key)
-            .ok_or(Error::GroupDoesNotExist)?;
-
-        // Verify caller is admin
-        (&admin).require_auth();
-
-        let member_key = DataKey::Member(group_id, identity_commitment);
-        if env.storage().instance().has(&member_key) {
-            return Err(Error::MemberAlreadyExists);
-        }
-
-        // Get and increment member count
-        let count_key = DataKey::MemberCount(group_id);
-        let current_count: u32 = env.storage().instance().get(&count_key).unwrap_or(0);
-
-        // Create and store new member
-        let member = Member {
-            identity_commitment,
-            group_id,
-            index: current_count,
-        };
-
-        env.storage().instance().set(&member_key, &member);
-        env.storage()
-            .instance()
-            .set(&count_key, &(current_count + 1));
-
-        // Emit event
-        env.events().publish(
-            (
-                Symbol::new(&env, "MemberAdded"),
-                group_id,
-                identity_commitment,
-                current_count,
-            ),
-            (),
-        );
+fn load_group(env: &Env, group_id: u32) -> Result<Group, Error> {
+    let key = DataKey::Group(group_id);
+    let group = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .ok_or(Error::GroupDoesNotExist)?;
+    extend_ttl(env, &key);
+    Ok(group)
+}
 
-        Ok(())
-    }
+fn save_group(env: &Env, group_id: u32, group: &Group) {
+    let key = DataKey::Group(group_id);
+    env.storage().persistent().set(&key, group);
+    extend_ttl(env, &key);
+}
 
-    fn add_members(env: Env, group_id: u32, identity_commitments: Vec<u32>) -> Result<(), Error> {
-        // Get admin to verify authorization once for the whole operation
-        let admin_key = DataKey::Admin(group_id);
-        let admin = env
-            .storage()
-            .instance()
-            .get::<_, Address>(&admin_key)
-            .ok_or(Error::GroupDoesNotExist)?;
+fn set_status(env: &Env, group_id: u32, status: GroupStatus) {
+    let key = DataKey::Status(group_id);
+    env.storage().persistent().set(&key, &status);
+    extend_ttl(env, &key);
+}
 
-        // Verify caller is admin for the main add_members call
-        (&admin).require_auth();
+fn extend_ttl(env: &Env, key: &DataKey) {
+    env.storage()
+        .persistent()
+        .extend_ttl(key, TTL_THRESHOLD, TTL);
+}
 
-        // Add each member
-        for commitment in identity_commitments.iter() {
-            // Note: we don't need to require_auth again since we're in the same transaction
-            Self::add_member(env.clone(), group_id, commitment)?;
+/// Schema 1 to 2: every group and the listed nullifiers move out of instance
+/// storage, which is loaded in full on each call, into persistent entries of
+/// their own
+fn migrate_to_persistent(env: &Env, nullifiers: &Vec<(u32, BytesN<32>)>) {
+    let group_count: u32 = env
+        .storage()
+        .instance()
+        .get(&DataKey::GroupCounter)
+        .unwrap_or(0);
+    for group_id in 0..group_count {
+        let key = DataKey::Group(group_id);
+        if let Some(group) = env.storage().instance().get::<_, Group>(&key) {
+            save_group(env, group_id, &group);
+            env.storage().instance().remove(&key);
         }
-        Ok(())
     }
-
-    fn update_member(
-        env: Env,
-        group_id: u32,
-        old_identity_commitment: u32,
-        new_identity_commitment: u32,
-    ) -> Result<(), Error> {
-        if new_identity_commitment == 0 {
-            return Err(Error::InvalidIdentityCommitment);
+    for (group_id, nullifier) in nullifiers.iter() {
+        let key = DataKey::Nullifier(group_id, nullifier);
+        if env.storage().instance().has(&key) {
+            env.storage().persistent().set(&key, &true);
+            extend_ttl(env, &key);
+            env.storage().instance().remove(&key);
         }
+    }
+}
 
-        let admin_key = DataKey::Admin(group_id);
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&admin_key)
-            .ok_or(Error::GroupDoesNotExist)?;
-
-        // Verify caller is admin
-        (&admin).require_auth();
-
-        // Check if old member exists and new member doesn't
-        let old_member_key = DataKey::Member(group_id, old_identity_commitment);
-        let new_member_key = DataKey::Member(group_id, new_identity_commitment);
-
-        let old_member: Member = env
-            .storage()
-            .instance()
-            .get(&old_member_key)
-            .ok_or(Error::MemberDoesNotExist)?;
-
-        if env.storage().instance().has(&new_member_key) {
-            return Err(Error::MemberAlreadyExists);
-        }
-
-        // Create updated member
-        let new_member = Member {
-            identity_commitment: new_identity_commitment,
-            group_id,
-            index: old_member.index,
-        };
-
-        // Update storage
-        env.storage().instance().set(&new_member_key, &new_member);
-        env.storage().instance().remove(&old_member_key);
-
-        // Emit event
-        env.events().publish(
-            (
-                Symbol::new(&env, "MemberUpdated"),
-                group_id,
-                old_identity_commitment,
-                new_identity_commitment,
-            ),
-            (),
-        );
+/// Everything about a proof the contract can check itself: its nullifier is
+/// unspent, its depth is supported and its root is the group's current root
+/// or a root that has not expired. `current_root` is `None` for an empty group
+fn check_proof(
+    env: &Env,
+    group_id: u32,
+    group: &Group,
+    current_root: &Option<BytesN<32>>,
+    proof: &SemaphoreProof,
+) -> Result<(), Error> {
+    // Verify nullifier hasn't been used
+    let nullifier_key = DataKey::Nullifier(group_id, proof.nullifier.clone());
+    if env.storage().persistent().has(&nullifier_key) {
+        return Err(Error::NullifierAlreadyUsed);
+    }
 
-        Ok(())
+    // Verify merkle tree depth
+    if proof.merkle_tree_depth < MIN_DEPTH || proof.merkle_tree_depth > MAX_DEPTH {
+        return Err(Error::InvalidMerkleTreeDepth);
     }
 
-    fn remove_member(env: Env, group_id: u32, identity_commitment: u32) -> Result<(), Error> {
-        let admin_key = DataKey::Admin(group_id);
-        let admin: Address = env
-            .storage()
-            .instance()
-            .get(&admin_key)
-            .ok_or(Error::GroupDoesNotExist)?;
+    // Verify group has members
+    let current_root = current_root.as_ref().ok_or(Error::GroupHasNoMembers)?;
 
-        // Verify caller is admin
-        (&admin).require_auth();
+    // Check merkle root validity and expiration
+    if proof.merkle_tree_root != *current_root {
+        let creation_date = group
+            .merkle_root_creation_dates
+            .get(proof.merkle_tree_root.clone())
+            .ok_or(Error::MerkleTreeRootNotInGroup)?;
 
-        // Check if member exists
-        let member_key = DataKey::Member(group_id, identity_commitment);
-        if !env.storage().instance().has(&member_key) {
-            return Err(Error::MemberDoesNotExist);
+        if env.ledger().timestamp() > creation_date + group.merkle_tree_duration {
+            return Err(Error::MerkleTreeRootExpired);
         }
+    }
+    Ok(())
+}
 
-        // Decrement member count
-        let count_key = DataKey::MemberCount(group_id);
-        let current_count: u32 = env.storage().instance().get(&count_key).unwrap_or(1);
-
-        env.storage()
-            .instance()
-            .set(&count_key, &(current_count - 1));
-        env.storage().instance().remove(&member_key);
+/// Mark a validated proof's nullifier as used and emit `ProofValidated`
+fn spend_nullifier(env: &Env, group_id: u32, proof: SemaphoreProof) {
+    let key = DataKey::Nullifier(group_id, proof.nullifier.clone());
+    env.storage().persistent().set(&key, &true);
+    extend_ttl(env, &key);
+
+    ProofValidated {
+        group_id,
+        merkle_tree_depth: proof.merkle_tree_depth,
+        merkle_tree_root: proof.merkle_tree_root.into(),
+        nullifier: proof.nullifier.into(),
+        message: proof.message.into(),
+        scope: proof.scope.into(),
+    }
+    .publish(env);
+}
 
-        // Emit event
-        env.events().publish(
-            (
-                Symbol::new(&env, "MemberRemoved"),
-                group_id,
-                identity_commitment,
-            ),
-            (),
-        );
+fn verifier(env: &Env) -> VerifierClient<'_> {
+    let address: Address = env.storage().instance().get(&DataKey::Verifier).unwrap();
+    VerifierClient::new(env, &address)
+}
 
-        Ok(())
+fn calculate_merkle_root(env: &Env, tree: &MerkleTree) -> Result<BytesN<32>, Error> {
+    if tree.leaves.is_empty() {
+        return Err(Error::GroupHasNoMembers);
     }
 
-    fn get_group_admin(env: Env, group_id: u32) -> Result<Address, Error> {
-        let admin_key = DataKey::Admin(group_id);
-        env.storage()
-            .instance()
-            .get(&admin_key)
-            .ok_or(Error::GroupDoesNotExist)
-    }
+    let mut current_level = tree.leaves.clone();
 
-    fn get_member(env: Env, group_id: u32, identity_commitment: u32) -> Result<Member, Error> {
-        let member_key = DataKey::Member(group_id, identity_commitment);
-        env.storage()
-            .instance()
-            .get(&member_key)
-            .ok_or(Error::MemberDoesNotExist)
-    }
+    while current_level.len() > 1 {
+        let mut next_level = Vec::new(env);
 
-    fn get_member_count(env: Env, group_id: u32) -> Result<u32, Error> {
-        let count_key = DataKey::MemberCount(group_id);
-        env.storage()
-            .instance()
-            .get(&count_key)
-            .ok_or(Error::GroupDoesNotExist)
-    }
+        for i in (0..current_level.len()).step_by(2) {
+            let left = current_level.get(i).unwrap();
+            let right = if i + 1 < current_level.len() {
+                current_level.get(i + 1).unwrap()
+            } else {
+                left.clone()
+            };
 
-    fn is_member(env: Env, group_id: u32, identity_commitment: u32) -> Result<bool, Error> {
-        // Check if group exists first
-        let admin_key = DataKey::Admin(group_id);
-        if !env.storage().instance().has(&admin_key) {
-            return Err(Error::GroupDoesNotExist);
+            next_level.push_back(hash_pair(env, &left, &right));
         }
 
-        let member_key = DataKey::Member(group_id, identity_commitment);
-        Ok(env.storage().instance().has(&member_key))
+        current_level = next_level;
     }
+
+    Ok(current_level.get(0).unwrap())
 }
-mod datatypes;
-mod interface;
+
+fn hash_pair(env: &Env, left: &BytesN<32>, right: &BytesN<32>) -> BytesN<32> {
+    let mut data = Bytes::new(env);
+    data.append(&left.clone().into());
+    data.append(&right.clone().into());
+    env.crypto().keccak256(&data).into()
+}
+
+pub mod datatypes;
 mod test;
+pub mod verifier;
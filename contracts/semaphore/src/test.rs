@@ -1,21 +1,390 @@
 #![cfg(test)]
+extern crate std;
 
 use super::*;
-use soroban_sdk::{vec, Env, String};
+use events::SCHEMA_VERSION;
+use soroban_sdk::{
+    testutils::{storage::Persistent as _, Address as _, Events},
+    vec, IntoVal, Symbol, Val,
+};
+
+fn event<E: GroupEvent>(env: &Env, contract_id: &Address, event: E) -> (Address, Vec<Val>, Val) {
+    (
+        contract_id.clone(),
+        (Symbol::new(env, E::NAME), SCHEMA_VERSION, event.group_id()).into_val(env),
+        event.into_val(env),
+    )
+}
+
+/// Stands in for the Groth16 verifier: proofs whose `a` starts with `0xff`
+/// do not hold
+#[contract]
+struct MockVerifier;
+
+#[contractimpl]
+impl MockVerifier {
+    pub fn verify(_env: Env, _depth: u32, proof: Groth16Proof, _public_inputs: Vec<BytesN<32>>) -> bool {
+        proof.a.get(0) != Some(0xff)
+    }
+
+    pub fn verify_batch(
+        env: Env,
+        depth: u32,
+        proofs: Vec<Groth16Proof>,
+        public_inputs: Vec<Vec<BytesN<32>>>,
+    ) -> Vec<bool> {
+        let mut results = Vec::new(&env);
+        for (proof, inputs) in proofs.iter().zip(public_inputs.iter()) {
+            results.push_back(Self::verify(env.clone(), depth, proof, inputs));
+        }
+        results
+    }
+}
+
+fn setup(env: &Env) -> (Address, SemaphoreClient<'_>) {
+    let verifier = env.register(MockVerifier, ());
+    let contract_id = env.register(Semaphore, (Address::generate(env), verifier));
+    let client = SemaphoreClient::new(env, &contract_id);
+    (contract_id, client)
+}
+
+fn semaphore_proof(env: &Env, root: BytesN<32>, nullifier: u8) -> SemaphoreProof {
+    let mut points = Vec::new(env);
+    for i in 0..8 {
+        points.push_back(BytesN::from_array(env, &[i; 32]));
+    }
+    SemaphoreProof {
+        merkle_tree_depth: MIN_DEPTH,
+        merkle_tree_root: root,
+        nullifier: BytesN::from_array(env, &[nullifier; 32]),
+        message: BytesN::from_array(env, &[1u8; 32]),
+        scope: BytesN::from_array(env, &[2u8; 32]),
+        points,
+    }
+}
+
+/// A proof the verifier rejects
+fn invalid(env: &Env, mut proof: SemaphoreProof) -> SemaphoreProof {
+    proof.points.set(0, BytesN::from_array(env, &[0xff; 32]));
+    proof
+}
+
+/// Group with one member, whose commitment is also the root
+fn group_with_member(env: &Env, client: &SemaphoreClient) -> (u32, BytesN<32>) {
+    let group_id = client.create_group(&Address::generate(env), &3600);
+    let commitment = BytesN::from_array(env, &[7u8; 32]);
+    client.add_member(&group_id, &commitment);
+    (group_id, commitment)
+}
+
+#[test]
+fn test_create_group() {
+    let env = Env::default();
+    let (contract_id, client) = setup(&env);
+    let admin = Address::generate(&env);
+    assert_eq!(client.create_group(&admin, &3600), 0);
+    assert_eq!(
+        env.events().all(),
+        vec![
+            &env,
+            event(&env, &contract_id, GroupCreated { group_id: 0, admin: admin.clone(), depth: MIN_DEPTH, root: Bytes::from_slice(&env, &[0u8; 32]) }),
+            event(&env, &contract_id, GroupAdminUpdated { group_id: 0, old_admin: None, new_admin: admin.clone() }),
+        ]
+    );
+    assert_eq!(client.create_group(&admin, &3600), 1);
+}
 
 #[test]
-fn test() {
+fn test_update_group_admin() {
     let env = Env::default();
-    let contract_id = env.register_contract(None, HelloContract);
-    let client = HelloContractClient::new(&env, &contract_id);
+    env.mock_all_auths();
+    let (contract_id, client) = setup(&env);
+    let admin = Address::generate(&env);
+    let group_id = client.create_group(&admin, &3600);
+    let new_admin = Address::generate(&env);
+    client.update_group_admin(&group_id, &new_admin);
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
+        vec![&env, event(&env, &contract_id, GroupAdminUpdated { group_id, old_admin: Some(admin), new_admin })]
+    );
+}
 
-    let words = client.hello(&String::from_str(&env, "Dev"));
+#[test]
+fn test_add_member() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (contract_id, client) = setup(&env);
+    let admin = Address::generate(&env);
+    let group_id = client.create_group(&admin, &3600);
+    let commitment = BytesN::from_array(&env, &[7u8; 32]);
+    client.add_member(&group_id, &commitment);
     assert_eq!(
-        words,
+        vec![&env, env.events().all().last().unwrap()],
+        vec![&env, event(&env, &contract_id, MemberAdded { group_id, identity_commitment: commitment.clone().into(), index: 0, root: commitment.into() })]
+    );
+}
+
+#[test]
+fn test_validate_proof() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (contract_id, client) = setup(&env);
+    let admin = Address::generate(&env);
+    let group_id = client.create_group(&admin, &3600);
+    let commitment = BytesN::from_array(&env, &[7u8; 32]);
+    client.add_member(&group_id, &commitment);
+
+    let proof = semaphore_proof(&env, commitment, 9);
+    client.validate_proof(&group_id, &proof);
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
+        vec![&env, event(
+            &env,
+            &contract_id,
+            ProofValidated {
+                group_id,
+                merkle_tree_depth: proof.merkle_tree_depth,
+                merkle_tree_root: proof.merkle_tree_root.clone().into(),
+                nullifier: proof.nullifier.clone().into(),
+                message: proof.message.clone().into(),
+                scope: proof.scope.clone().into(),
+            }
+        )]
+    );
+    assert_eq!(client.try_validate_proof(&group_id, &proof), Err(Ok(Error::NullifierAlreadyUsed)));
+}
+
+#[test]
+fn test_validate_proof_root_not_in_group() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (_, client) = setup(&env);
+    let admin = Address::generate(&env);
+    let group_id = client.create_group(&admin, &3600);
+    client.add_member(&group_id, &BytesN::from_array(&env, &[7u8; 32]));
+    let proof = semaphore_proof(&env, BytesN::from_array(&env, &[8u8; 32]), 9);
+    assert_eq!(client.try_validate_proof(&group_id, &proof), Err(Ok(Error::MerkleTreeRootNotInGroup)));
+}
+
+#[test]
+fn test_validate_proof_invalid() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (_, client) = setup(&env);
+    let (group_id, root) = group_with_member(&env, &client);
+
+    let proof = semaphore_proof(&env, root.clone(), 9);
+    assert_eq!(client.try_validate_proof(&group_id, &invalid(&env, proof.clone())), Err(Ok(Error::InvalidProof)));
+
+    let mut truncated = proof.clone();
+    truncated.points.pop_back();
+    assert_eq!(client.try_validate_proof(&group_id, &truncated), Err(Ok(Error::InvalidProof)));
+
+    // the nullifier of a rejected proof is still unspent
+    client.validate_proof(&group_id, &proof);
+}
+
+#[test]
+fn test_public_inputs() {
+    let env = Env::default();
+    let proof = semaphore_proof(&env, BytesN::from_array(&env, &[3u8; 32]), 9);
+    let inputs = verifier::public_inputs(&env, &proof);
+    assert_eq!(inputs.get(0).unwrap(), proof.merkle_tree_root);
+    assert_eq!(inputs.get(1).unwrap(), proof.nullifier);
+    let digest = env.crypto().keccak256(&proof.message.clone().into()).to_array();
+    let message = inputs.get(2).unwrap().to_array();
+    assert_eq!(message[0], 0);
+    assert_eq!(message[1..], digest[..31]);
+}
+
+#[test]
+fn test_validate_proofs() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (contract_id, client) = setup(&env);
+    let (group_id, root) = group_with_member(&env, &client);
+
+    let mut deeper = semaphore_proof(&env, root.clone(), 10);
+    deeper.merkle_tree_depth = MIN_DEPTH + 1;
+    let proofs = vec![&env, semaphore_proof(&env, root.clone(), 9), deeper, semaphore_proof(&env, root.clone(), 11)];
+    client.validate_proofs(&group_id, &proofs);
+
+    let events = env.events().all();
+    let mut validated = Vec::new(&env);
+    for proof in proofs.iter() {
+        validated.push_back(
+event(
+            &env,
+            &contract_id,
+            ProofValidated {
+                group_id,
+                merkle_tree_depth: proof.merkle_tree_depth,
+                merkle_tree_root: proof.merkle_tree_root.clone().into(),
+                nullifier: proof.nullifier.clone().into(),
+                message: proof.message.clone().into(),
+                scope: proof.scope.clone().into(),
+            },
+        ));
+    }
+    assert_eq!(events.slice(events.len() - 3..), validated);
+
+    for proof in proofs.iter() {
+        assert_eq!(client.try_validate_proof(&group_id, &proof), Err(Ok(Error::NullifierAlreadyUsed)));
+    }
+}
+
+#[test]
+fn test_validate_proofs_is_atomic() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (_, client) = setup(&env);
+    let (group_id, root) = group_with_member(&env, &client);
+    let first = semaphore_proof(&env, root.clone(), 9);
+    let second = semaphore_proof(&env, root.clone(), 10);
+
+    // one invalid proof rejects the batch and spends no nullifier
+    let batch = vec![&env, first.clone(), invalid(&env, second.clone())];
+    assert_eq!(client.try_validate_proofs(&group_id, &batch), Err(Ok(Error::InvalidProof)));
+
+    // neither does a nullifier used twice
+    let batch = vec![&env, first.clone(), second.clone(), first.clone()];
+    assert_eq!(client.try_validate_proofs(&group_id, &batch), Err(Ok(Error::NullifierAlreadyUsed)));
+
+    // or a proof failing the contract's own checks
+    let batch = vec![&env, first.clone(), semaphore_proof(&env, BytesN::from_array(&env, &[8u8; 32]), 10)];
+    assert_eq!(client.try_validate_proofs(&group_id, &batch), Err(Ok(Error::MerkleTreeRootNotInGroup)));
+
+    client.validate_proofs(&group_id, &vec![&env, first, second]);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_upgrade_requires_owner() {
+    let env = Env::default();
+    let (_, client) = setup(&env);
+    client.upgrade(&BytesN::from_array(&env, &[1u8; 32]));
+}
+
+#[test]
+fn test_migrate() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (contract_id, client) = setup(&env);
+    let (group_id, root) = group_with_member(&env, &client);
+    assert_eq!(client.schema_version(), STORAGE_SCHEMA_VERSION);
+
+    // the same group as schema 1 stored it, with a nullifier spent then
+    let spent = semaphore_proof(&env, root.clone(), 10);
+    let nullifier_key = DataKey::Nullifier(group_id, spent.nullifier.clone());
+    env.as_contract(&contract_id, || {
+        let group = load_group(&env, group_id).unwrap();
+        env.storage().persistent().remove(&DataKey::Group(group_id));
+        env.storage().instance().set(&DataKey::Group(group_id), &group);
+        env.storage().instance().set(&nullifier_key, &true);
+        env.storage().instance().remove(&DataKey::SchemaVersion);
+    });
+    assert_eq!(client.schema_version(), 1);
+    assert_eq!(client.try_add_member(&group_id, &BytesN::from_array(&env, &[8u8; 32])), Err(Ok(Error::GroupDoesNotExist)));
+
+    client.migrate(&vec![&env, (group_id, spent.nullifier.clone())]);
+    assert_eq!(client.schema_version(), STORAGE_SCHEMA_VERSION);
+    env.as_contract(&contract_id, || {
+        assert!(!env.storage().instance().has(&DataKey::Group(group_id)));
+        assert!(!env.storage().instance().has(&nullifier_key));
+    });
+    client.validate_proof(&group_id, &semaphore_proof(&env, root, 9));
+    assert_eq!(client.try_validate_proof(&group_id, &spent), Err(Ok(Error::NullifierAlreadyUsed)));
+
+    // running it again changes nothing
+    client.migrate(&Vec::new(&env));
+    assert_eq!(client.schema_version(), STORAGE_SCHEMA_VERSION);
+
+    env.as_contract(&contract_id, || {
+        env.storage().instance().set(&DataKey::SchemaVersion, &(STORAGE_SCHEMA_VERSION + 1))
+    });
+    assert_eq!(client.try_migrate(&Vec::new(&env)), Err(Ok(Error::UnsupportedSchemaVersion)));
+}
+
+#[test]
+fn test_pause() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (contract_id, client) = setup(&env);
+    let (group_id, root) = group_with_member(&env, &client);
+    let owner = client.owner();
+
+    client.pause();
+    assert!(client.is_paused());
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
         vec![
             &env,
-            String::from_str(&env, "Hello"),
-            String::from_str(&env, "Dev"),
+            (
+                contract_id.clone(),
+                (Symbol::new(&env, "paused"), SCHEMA_VERSION).into_val(&env),
+                Paused { by: owner, timestamp: env.ledger().timestamp() }.into_val(&env)
+            )
         ]
     );
-}
\ No newline at end of file
+
+    let proof = semaphore_proof(&env, root, 9);
+    assert_eq!(client.try_create_group(&Address::generate(&env), &3600), Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_add_member(&group_id, &BytesN::from_array(&env, &[8u8; 32])), Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_validate_proof(&group_id, &proof), Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_validate_proofs(&group_id, &vec![&env, proof.clone()]), Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_archive_group(&group_id), Err(Ok(Error::ContractPaused)));
+
+    client.unpause();
+    assert!(!client.is_paused());
+    client.validate_proof(&group_id, &proof);
+}
+
+#[test]
+fn test_archive_group() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (contract_id, client) = setup(&env);
+    let (group_id, root) = group_with_member(&env, &client);
+
+    client.archive_group(&group_id);
+    assert_eq!(client.get_group_status(&group_id), GroupStatus::Archived);
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
+        vec![&env, event(&env, &contract_id, GroupArchived { group_id })]
+    );
+
+    let proof = semaphore_proof(&env, root, 9);
+    assert_eq!(client.try_validate_proof(&group_id, &proof), Err(Ok(Error::GroupIsArchived)));
+    assert_eq!(client.try_validate_proofs(&group_id, &vec![&env, proof]), Err(Ok(Error::GroupIsArchived)));
+    assert_eq!(client.try_add_member(&group_id, &BytesN::from_array(&env, &[8u8; 32])), Err(Ok(Error::GroupIsArchived)));
+    assert_eq!(client.try_archive_group(&group_id), Err(Ok(Error::GroupIsArchived)));
+}
+
+#[test]
+fn test_delete_group() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (contract_id, client) = setup(&env);
+    let (group_id, root) = group_with_member(&env, &client);
+    let proof = semaphore_proof(&env, root, 9);
+    client.validate_proof(&group_id, &proof);
+
+    assert_eq!(client.try_delete_group(&group_id), Err(Ok(Error::GroupIsNotArchived)));
+    client.archive_group(&group_id);
+    client.delete_group(&group_id);
+    assert_eq!(client.get_group_status(&group_id), GroupStatus::Deleted);
+    assert_eq!(
+        vec![&env, env.events().all().last().unwrap()],
+        vec![&env, event(&env, &contract_id, GroupDeleted { group_id })]
+    );
+    assert_eq!(client.try_validate_proof(&group_id, &proof), Err(Ok(Error::GroupDoesNotExist)));
+    assert_eq!(client.try_add_member(&group_id, &BytesN::from_array(&env, &[8u8; 32])), Err(Ok(Error::GroupDoesNotExist)));
+
+    // the id is not handed out again and the nullifier stays spent
+    assert_eq!(client.create_group(&Address::generate(&env), &3600), group_id + 1);
+    env.as_contract(&contract_id, || {
+        let key = DataKey::Nullifier(group_id, proof.nullifier.clone());
+        assert!(env.storage().persistent().has(&key));
+        assert_eq!(env.storage().persistent().get_ttl(&key), TTL);
+    });
+}
@@ -0,0 +1,68 @@
+//! Calls to the Groth16 verifier contract.
+//!
+//! The verifier is a separate contract, so its interface is declared here
+//! rather than linked in: `Groth16Proof` has the same fields as the
+//! verifier's `Proof` and is passed across as the same value.
+
+use crate::SemaphoreProof;
+use soroban_sdk::{contractclient, contracttype, Bytes, BytesN, Env, Vec};
+
+/// Proof points as the verifier takes them, in the EIP-197 layout
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Groth16Proof {
+    pub a: Bytes,
+    pub b: Bytes,
+    pub c: Bytes,
+}
+
+#[contractclient(name = "VerifierClient")]
+pub trait Verifier {
+    fn verify(env: Env, depth: u32, proof: Groth16Proof, public_inputs: Vec<BytesN<32>>) -> bool;
+
+    fn verify_batch(
+        env: Env,
+        depth: u32,
+        proofs: Vec<Groth16Proof>,
+        public_inputs: Vec<Vec<BytesN<32>>>,
+    ) -> Vec<bool>;
+}
+
+/// `a`, `b` and `c` from the proof's eight points, `None` for any other count
+pub fn groth16_proof(env: &Env, proof: &SemaphoreProof) -> Option<Groth16Proof> {
+    if proof.points.len() != 8 {
+        return None;
+    }
+    let concat = |from: u32, to: u32| {
+        let mut bytes = Bytes::new(env);
+        for i in from..to {
+            bytes.append(&proof.points.get(i).unwrap().into());
+        }
+        bytes
+    };
+    Some(Groth16Proof {
+        a: concat(0, 2),
+        b: concat(2, 6),
+        c: concat(6, 8),
+    })
+}
+
+/// Public signals of the circuit:
+/// `[merkle_tree_root, nullifier, hash(message), hash(scope)]`
+pub fn public_inputs(env: &Env, proof: &SemaphoreProof) -> Vec<BytesN<32>> {
+    soroban_sdk::vec![
+        env,
+        proof.merkle_tree_root.clone(),
+        proof.nullifier.clone(),
+        hash(env, &proof.message),
+        hash(env, &proof.scope),
+    ]
+}
+
+/// Semaphore's `hash`: keccak256 shifted right by 8 bits to fit in the field
+fn hash(env: &Env, value: &BytesN<32>) -> BytesN<32> {
+    let digest = env.crypto().keccak256(&value.clone().into()).to_array();
+    let mut shifted = [0u8; 32];
+    shifted[1..].copy_from_slice(&digest[..31]);
+    BytesN::from_array(env, &shifted)
+}
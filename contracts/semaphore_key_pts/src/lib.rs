@@ -1,13 +1,16 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contracterror, contractimpl, symbol_short, BytesN, Env, Vec
+    contract, contracterror, contractimpl, symbol_short, Address, BytesN, Env, Vec
 };
 
 /// Core constants for verification key points management
 const POINT_SIZE: usize = 32;
 const SET_SIZE: u32 = 8;
 
+/// Version of the storage layout written by this code
+pub const STORAGE_SCHEMA_VERSION: u32 = 1;
+
 /// Contract error for verification key points validation
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -15,6 +18,8 @@ const SET_SIZE: u32 = 8;
 pub enum Error {
     /// Error when verification key points invariant is violated
     VKPtBytesMaxDepthInvariantViolated = 1,
+    /// Error when storage was written by a newer version of the contract
+    UnsupportedSchemaVersion = 2,
 }
 
 #[contract]
@@ -22,6 +27,39 @@ pub struct SemaphoreVerifierKeyPts;
 
 #[contractimpl]
 impl SemaphoreVerifierKeyPts {
+    /// Sets the owner, who may upgrade the contract.
+    pub fn __constructor(env: Env, owner: Address) {
+        env.storage().instance().set(&symbol_short!("OWNER"), &owner);
+        env.storage().instance().set(&symbol_short!("VERSION"), &STORAGE_SCHEMA_VERSION);
+    }
+
+    /// Replaces the contract's code, keeping its storage.
+    /// Call `migrate` afterwards if the new code uses a newer storage layout.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        let owner: Address = env.storage().instance().get(&symbol_short!("OWNER")).unwrap();
+        owner.require_auth();
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+    }
+
+    /// Layout of the data in storage, 1 for data written before the version was recorded.
+    pub fn schema_version(env: Env) -> u32 {
+        env.storage().instance()
+            .get(&symbol_short!("VERSION"))
+            .unwrap_or(1)
+    }
+
+    /// Converts the stored data to the layout of this code.
+    /// Version 1 is the first layout, so there is nothing to convert yet.
+    pub fn migrate(env: Env) -> Result<(), Error> {
+        let owner: Address = env.storage().instance().get(&symbol_short!("OWNER")).unwrap();
+        owner.require_auth();
+        if Self::schema_version(env.clone()) > STORAGE_SCHEMA_VERSION {
+            return Err(Error::UnsupportedSchemaVersion);
+        }
+        env.storage().instance().set(&symbol_short!("VERSION"), &STORAGE_SCHEMA_VERSION);
+        Ok(())
+    }
+
     /// Initializes the verification key points storage.
     /// Stores a set of verification points used for zero-knowledge proof verification.
     pub fn initialize(env: Env) {
@@ -85,13 +123,13 @@ impl SemaphoreVerifierKeyPts {
 
 #[cfg(test)]
 mod tests {
-    use soroban_sdk::Env;
+    use soroban_sdk::{testutils::Address as _, Env};
     use super::*;  // Importante: necesitamos importar todo del módulo padre
 
     #[test]
     fn test_initialize() {
         let env = Env::default();
-        let contract_id = env.register(SemaphoreVerifierKeyPts, ());
+        let contract_id = env.register(SemaphoreVerifierKeyPts, (Address::generate(&env),));
         let client = SemaphoreVerifierKeyPtsClient::new(&env, &contract_id);
         
         client.initialize();
@@ -102,7 +140,7 @@ mod tests {
     #[test]
     fn test_get_pts() {
         let env = Env::default();
-        let contract_id = env.register(SemaphoreVerifierKeyPts, ());
+        let contract_id = env.register(SemaphoreVerifierKeyPts, (Address::generate(&env),));
         let client = SemaphoreVerifierKeyPtsClient::new(&env, &contract_id);
         
         client.initialize();
@@ -110,4 +148,25 @@ mod tests {
         assert!(!points.is_empty(), "Should return non-empty points array");
         assert_eq!(points.len(), 8, "Should return exactly 8 points");
     }
+
+    #[test]
+    fn test_migrate() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(SemaphoreVerifierKeyPts, (Address::generate(&env),));
+        let client = SemaphoreVerifierKeyPtsClient::new(&env, &contract_id);
+
+        assert_eq!(client.schema_version(), STORAGE_SCHEMA_VERSION);
+        client.migrate();
+        assert_eq!(client.schema_version(), STORAGE_SCHEMA_VERSION);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Auth, InvalidAction)")]
+    fn test_upgrade_requires_owner() {
+        let env = Env::default();
+        let contract_id = env.register(SemaphoreVerifierKeyPts, (Address::generate(&env),));
+        let client = SemaphoreVerifierKeyPtsClient::new(&env, &contract_id);
+        client.upgrade(&BytesN::from_array(&env, &[1u8; 32]));
+    }
 }
\ No newline at end of file
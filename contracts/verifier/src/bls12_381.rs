@@ -0,0 +1,130 @@
+//! BLS12-381 verification with the host's pairing functions.
+
+use crate::datatypes::{Error, Proof, VerificationKey};
+use soroban_sdk::{
+    crypto::bls12_381::{Bls12_381, Fr, G1Affine, G2Affine},
+    vec, Bytes, BytesN, Env, Vec, U256,
+};
+
+/// Order of the scalar field, big-endian
+pub const MODULUS: [u8; 32] = [
+    0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8, 0x05,
+    0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+];
+
+fn g1(point: &Bytes) -> G1Affine {
+    G1Affine::from_bytes(point.try_into().unwrap())
+}
+
+fn g2(point: &Bytes) -> G2Affine {
+    G2Affine::from_bytes(point.try_into().unwrap())
+}
+
+fn scalar(env: &Env, value: u128) -> Fr {
+    U256::from_u128(env, value).into()
+}
+
+/// The proof's points, which must be in the prime order subgroups
+fn points(bls: &Bls12_381, proof: &Proof) -> Result<(G1Affine, G2Affine, G1Affine), Error> {
+    let (a, b, c) = (g1(&proof.a), g2(&proof.b), g1(&proof.c));
+    if !bls.g1_is_in_subgroup(&a) || !bls.g2_is_in_subgroup(&b) || !bls.g1_is_in_subgroup(&c) {
+        return Err(Error::InvalidProof);
+    }
+    Ok((a, b, c))
+}
+
+/// Public inputs as scalars, rejecting values outside the field
+fn inputs(env: &Env, public_inputs: &Vec<BytesN<32>>) -> Result<Vec<Fr>, Error> {
+    let modulus = U256::from_be_bytes(env, &Bytes::from_array(env, &MODULUS));
+    let mut scalars = Vec::new(env);
+    for input in public_inputs.iter() {
+        let input = U256::from_be_bytes(env, input.as_ref());
+        if input >= modulus {
+            return Err(Error::InvalidPublicInputs);
+        }
+        scalars.push_back(Fr::from_u256(input));
+    }
+    Ok(scalars)
+}
+
+fn ic(vk: &VerificationKey) -> Vec<G1Affine> {
+    let mut points = Vec::new(vk.ic.env());
+    for point in vk.ic.iter() {
+        points.push_back(g1(&point));
+    }
+    points
+}
+
+/// Point lengths have been checked by the caller. Points off the curve trap
+/// in the host, failing the whole call instead of returning an [`Error`]
+pub fn verify(
+    env: &Env,
+    vk: &VerificationKey,
+    proof: &Proof,
+    public_inputs: &Vec<BytesN<32>>,
+) -> Result<bool, Error> {
+    verify_batch(
+        env,
+        vk,
+        &vec![env, proof.clone()],
+        &vec![env, public_inputs.clone()],
+        &vec![env, 1],
+    )
+}
+
+/// Check every proof at once, each equation scaled by its weight `r`:
+///
+/// `prod(e(-r a, b)) * e(sum(r) alpha, beta) * e(sum(r vk_x), gamma)
+/// * e(sum(r c), delta) == 1`
+///
+/// With a single proof and a weight of one this is the plain Groth16 check.
+pub fn verify_batch(
+    env: &Env,
+    vk: &VerificationKey,
+    proofs: &Vec<Proof>,
+    public_inputs: &Vec<Vec<BytesN<32>>>,
+    weights: &Vec<u128>,
+) -> Result<bool, Error> {
+    let bls = env.crypto().bls12_381();
+    let zero = scalar(env, 0);
+
+    let mut g1_points = Vec::new(env);
+    let mut g2_points = Vec::new(env);
+    let mut cs = Vec::new(env);
+    let mut rs = Vec::new(env);
+    // sum(r vk_x) = sum(r) ic[0] + sum(sum(r input[i]) ic[i + 1])
+    let mut ic_scalars = Vec::new(env);
+    for _ in vk.ic.iter() {
+        ic_scalars.push_back(zero.clone());
+    }
+
+    for (i, proof) in proofs.iter().enumerate() {
+        let i = i as u32;
+        let (a, b, c) = points(&bls, &proof)?;
+        let r = scalar(env, weights.get(i).unwrap());
+
+        g1_points.push_back(bls.g1_mul(&a, &bls.fr_sub(&zero, &r)));
+        g2_points.push_back(b);
+        cs.push_back(c);
+
+        ic_scalars.set(0, bls.fr_add(&ic_scalars.get(0).unwrap(), &r));
+        for (j, input) in inputs(env, &public_inputs.get(i).unwrap())?
+            .iter()
+            .enumerate()
+        {
+            let j = j as u32 + 1;
+            let term = bls.fr_mul(&r, &input);
+            ic_scalars.set(j, bls.fr_add(&ic_scalars.get(j).unwrap(), &term));
+        }
+        rs.push_back(r);
+    }
+
+    // alpha and ic[0] share the weight sum(r)
+    g1_points.push_back(bls.g1_mul(&g1(&vk.alpha), &ic_scalars.get(0).unwrap()));
+    g1_points.push_back(bls.g1_msm(ic(vk), ic_scalars));
+    g1_points.push_back(bls.g1_msm(cs, rs));
+    g2_points.push_back(g2(&vk.beta));
+    g2_points.push_back(g2(&vk.gamma));
+    g2_points.push_back(g2(&vk.delta));
+    Ok(bls.pairing_check(g1_points, g2_points))
+}
@@ -0,0 +1,116 @@
+//! BN254 verification in contract code.
+//!
+//! The host only provides BLS12-381 curve operations, so this is the fallback
+//! the verifier uses for every BN254 key. Points use the EIP-197 layout
+//! (`x || y`, G2 coordinates as `c1 || c0`), all zeros being the point at
+//! infinity, and are checked to be on the curve and in the subgroup.
+//!
+//! Only built with the `bn254` feature: the pairing runs in WASM and its cost
+//! has not been measured against the per-transaction instruction limit.
+
+use crate::datatypes::{Error, Proof, VerificationKey};
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G1Projective, G2Affine};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::{BigInt, PrimeField, Zero};
+use soroban_sdk::{vec, Bytes, BytesN, Vec};
+
+/// Field element from 32 big-endian bytes, `None` unless canonical
+fn field<F: PrimeField<BigInt = BigInt<4>>>(bytes: &[u8]) -> Option<F> {
+    let mut limbs = [0u64; 4];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        let start = 32 - 8 * (i + 1);
+        *limb = u64::from_be_bytes(bytes[start..start + 8].try_into().unwrap());
+    }
+    F::from_bigint(BigInt(limbs))
+}
+
+fn g1(point: &Bytes) -> Option<G1Affine> {
+    let mut bytes = [0u8; 64];
+    point.copy_into_slice(&mut bytes);
+    if bytes == [0; 64] {
+        return Some(G1Affine::zero());
+    }
+    let point = G1Affine::new_unchecked(field(&bytes[..32])?, field(&bytes[32..])?);
+    (point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve()).then_some(point)
+}
+
+fn g2(point: &Bytes) -> Option<G2Affine> {
+    let mut bytes = [0u8; 128];
+    point.copy_into_slice(&mut bytes);
+    if bytes == [0; 128] {
+        return Some(G2Affine::zero());
+    }
+    let fq2 = |c1: &[u8], c0: &[u8]| Some(Fq2::new(field::<Fq>(c0)?, field::<Fq>(c1)?));
+    let point = G2Affine::new_unchecked(
+        fq2(&bytes[..32], &bytes[32..64])?,
+        fq2(&bytes[64..96], &bytes[96..])?,
+    );
+    (point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve()).then_some(point)
+}
+
+/// Point lengths have been checked by the caller
+pub fn verify(
+    vk: &VerificationKey,
+    proof: &Proof,
+    public_inputs: &Vec<BytesN<32>>,
+) -> Result<bool, Error> {
+    let env = vk.ic.env();
+    verify_batch(
+        vk,
+        &vec![env, proof.clone()],
+        &vec![env, public_inputs.clone()],
+        &vec![env, 1],
+    )
+}
+
+/// Check every proof at once, each equation scaled by its weight `r`, as in
+/// [`crate::bls12_381::verify_batch`]
+pub fn verify_batch(
+    vk: &VerificationKey,
+    proofs: &Vec<Proof>,
+    public_inputs: &Vec<Vec<BytesN<32>>>,
+    weights: &Vec<u128>,
+) -> Result<bool, Error> {
+    let key_g1 = |point: &Bytes| g1(point).ok_or(Error::InvalidVerificationKey);
+    let key_g2 = |point: &Bytes| g2(point).ok_or(Error::InvalidVerificationKey);
+    let ic = vk
+        .ic
+        .iter()
+        .map(|point| key_g1(&point))
+        .collect::<Result<alloc::vec::Vec<_>, _>>()?;
+
+    let mut g1_points = alloc::vec::Vec::new();
+    let mut g2_points = alloc::vec::Vec::new();
+    let mut weight_sum = Fr::zero();
+    let mut vk_x = G1Projective::zero();
+    let mut c_sum = G1Projective::zero();
+    for (i, proof) in proofs.iter().enumerate() {
+        let a = g1(&proof.a).ok_or(Error::InvalidProof)?;
+        let b = g2(&proof.b).ok_or(Error::InvalidProof)?;
+        let c = g1(&proof.c).ok_or(Error::InvalidProof)?;
+        let r = Fr::from(weights.get(i as u32).unwrap());
+
+        // r * (ic[0] + sum(ic[j + 1] * input[j]))
+        let mut proof_vk_x = G1Projective::from(ic[0]);
+        for (j, input) in public_inputs.get(i as u32).unwrap().iter().enumerate() {
+            let input: Fr = field(&input.to_array()).ok_or(Error::InvalidPublicInputs)?;
+            proof_vk_x += ic[j + 1] * input;
+        }
+
+        g1_points.push(-(a * r).into_affine());
+        g2_points.push(b);
+        weight_sum += r;
+        vk_x += proof_vk_x * r;
+        c_sum += c * r;
+    }
+
+    // prod(e(-r a, b)) * e(sum(r) alpha, beta) * e(sum(r vk_x), gamma)
+    // * e(sum(r c), delta) == 1
+    g1_points.extend([
+        (key_g1(&vk.alpha)? * weight_sum).into_affine(),
+        vk_x.into_affine(),
+        c_sum.into_affine(),
+    ]);
+    g2_points.extend([key_g2(&vk.beta)?, key_g2(&vk.gamma)?, key_g2(&vk.delta)?]);
+    Ok(Bn254::multi_pairing(g1_points, g2_points).is_zero())
+}
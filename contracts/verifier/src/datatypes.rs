@@ -0,0 +1,76 @@
+use soroban_sdk::{contracterror, contracttype, Bytes, Vec};
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(u32)]
+pub enum Error {
+    VerificationKeyNotFound = 1,
+    InvalidVerificationKey = 2,
+    InvalidProof = 3,
+    InvalidPublicInputs = 4,
+    UnsupportedCurve = 5,
+}
+
+/// Curve a verification key was generated on
+#[contracttype]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Curve {
+    /// Verified with the host's pairing functions
+    Bls12381 = 0,
+    /// The curve of the Semaphore ceremony, verified in contract code with
+    /// the `bn254` feature
+    Bn254 = 1,
+}
+
+impl Curve {
+    /// Whether proofs on the curve can be checked by this build
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Curve::Bls12381 => true,
+            Curve::Bn254 => cfg!(feature = "bn254"),
+        }
+    }
+
+    /// Length of an uncompressed G1 point
+    pub fn g1_len(&self) -> u32 {
+        match self {
+            Curve::Bls12381 => 96,
+            Curve::Bn254 => 64,
+        }
+    }
+
+    /// Length of an uncompressed G2 point
+    pub fn g2_len(&self) -> u32 {
+        2 * self.g1_len()
+    }
+}
+
+/// Groth16 verification key
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerificationKey {
+    pub curve: Curve,
+    pub alpha: Bytes,
+    pub beta: Bytes,
+    pub gamma: Bytes,
+    pub delta: Bytes,
+    /// One point per public input, plus one
+    pub ic: Vec<Bytes>,
+}
+
+/// Groth16 proof, on the curve of the key it is checked against
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+    pub a: Bytes,
+    pub b: Bytes,
+    pub c: Bytes,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum DataKey {
+    Admin,                // may set verification keys
+    VerificationKey(u32), // maps tree depth -> VerificationKey
+}
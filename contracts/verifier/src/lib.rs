@@ -1,14 +1,216 @@
-pub fn example() -> &'static str {
-    "Hello, Verifier!"
+#![no_std]
+
+//! Groth16 verifier for Semaphore proofs.
+//!
+//! Verification keys are stored per tree depth and record the curve they were
+//! generated on, so keys from the official Semaphore ceremony (BN254) and
+//! keys from a BLS12-381 setup can live side by side. BLS12-381 proofs are
+//! checked with the host's pairing functions. The host has no BN254
+//! primitives in this protocol version, so BN254 proofs can only be checked
+//! in contract code by the `bn254` module, the one to switch over once it
+//! does. That pairing has not been measured against the network's
+//! instruction limit, so it sits behind the `bn254` feature, on by default;
+//! builds without it reject BN254 keys in `set_verification_key` with
+//! [`Error::UnsupportedCurve`].
+//!
+//! Points are uncompressed, big-endian coordinates with the imaginary part of
+//! G2 coordinates first: the host encoding for BLS12-381 and the EIP-197
+//! layout for BN254.
+
+extern crate alloc;
+
+use crate::datatypes::{Curve, DataKey, Error, Proof, VerificationKey};
+use soroban_sdk::{contract, contractimpl, Address, Bytes, BytesN, Env, Vec};
+
+#[contract]
+pub struct Verifier;
+
+#[contractimpl]
+impl Verifier {
+    pub fn __constructor(env: Env, admin: Address) {
+        env.storage().instance().set(&DataKey::Admin, &admin);
+    }
+
+    /// Set the key proofs for trees of depth `depth` are checked against
+    pub fn set_verification_key(env: Env, depth: u32, vk: VerificationKey) -> Result<(), Error> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        if !vk.curve.is_supported() {
+            return Err(Error::UnsupportedCurve);
+        }
+        let g1 = |point: &Bytes| point.len() == vk.curve.g1_len();
+        let g2 = |point: &Bytes| point.len() == vk.curve.g2_len();
+        if !g1(&vk.alpha)
+            || !g2(&vk.beta)
+            || !g2(&vk.gamma)
+            || !g2(&vk.delta)
+            || vk.ic.is_empty()
+            || !vk.ic.iter().all(|point| g1(&point))
+        {
+            return Err(Error::InvalidVerificationKey);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::VerificationKey(depth), &vk);
+        Ok(())
+    }
+
+    pub fn get_verification_key(env: Env, depth: u32) -> Result<VerificationKey, Error> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::VerificationKey(depth))
+            .ok_or(Error::VerificationKeyNotFound)
+    }
+
+    /// Check a proof against the key for `depth`. Points of the wrong length,
+    /// the wrong number of inputs and points outside the subgroup are
+    /// errors, a well formed proof that does not hold is `false`. BLS12-381
+    /// points that are not on the curve at all trap in the host, so such a
+    /// call fails rather than returning an [`Error`]
+    pub fn verify(
+        env: Env,
+        depth: u32,
+        proof: Proof,
+        public_inputs: Vec<BytesN<32>>,
+    ) -> Result<bool, Error> {
+        let vk = Self::get_verification_key(env.clone(), depth)?;
+        check_shape(&vk, &proof, &public_inputs)?;
+
+        match vk.curve {
+            Curve::Bls12381 => bls12_381::verify(&env, &vk, &proof, &public_inputs),
+            Curve::Bn254 => bn254::verify(&vk, &proof, &public_inputs),
+        }
+    }
+
+    /// Check many proofs against the key for `depth` with a single
+    /// multi-pairing, the `i`th proof with the `i`th list of public inputs.
+    ///
+    /// The proofs' equations are combined with weights hashed from the whole
+    /// batch, so it holds only if every proof does, except with negligible
+    /// probability.
+    /// When it does not, every proof is checked on its own and the result
+    /// tells which ones failed.
+    pub fn verify_batch(
+        env: Env,
+        depth: u32,
+        proofs: Vec<Proof>,
+        public_inputs: Vec<Vec<BytesN<32>>>,
+    ) -> Result<Vec<bool>, Error> {
+        let vk = Self::get_verification_key(env.clone(), depth)?;
+        if proofs.len() != public_inputs.len() {
+            return Err(Error::InvalidPublicInputs);
+        }
+        for (proof, inputs) in proofs.iter().zip(public_inputs.iter()) {
+            check_shape(&vk, &proof, &inputs)?;
+        }
+        let weights = weights(&env, &vk, &proofs, &public_inputs);
+
+        let holds = proofs.is_empty()
+            || match vk.curve {
+                Curve::Bls12381 => {
+                    bls12_381::verify_batch(&env, &vk, &proofs, &public_inputs, &weights)?
+                }
+                Curve::Bn254 => bn254::verify_batch(&vk, &proofs, &public_inputs, &weights)?,
+            };
+
+        let mut results = Vec::new(&env);
+        for (proof, inputs) in proofs.iter().zip(public_inputs.iter()) {
+            results.push_back(
+                holds
+                    || match vk.curve {
+                        Curve::Bls12381 => bls12_381::verify(&env, &vk, &proof, &inputs)?,
+                        Curve::Bn254 => bn254::verify(&vk, &proof, &inputs)?,
+                    },
+            );
+        }
+        Ok(results)
+    }
+}
+
+/// Public input count and point lengths against the key
+fn check_shape(
+    vk: &VerificationKey,
+    proof: &Proof,
+    public_inputs: &Vec<BytesN<32>>,
+) -> Result<(), Error> {
+    if public_inputs.len() + 1 != vk.ic.len() {
+        return Err(Error::InvalidPublicInputs);
+    }
+    let g1 = vk.curve.g1_len();
+    if proof.a.len() != g1 || proof.b.len() != vk.curve.g2_len() || proof.c.len() != g1 {
+        return Err(Error::InvalidProof);
+    }
+    Ok(())
+}
+
+/// Non-zero weight of every proof in a batch, derived from a hash of the key,
+/// the proofs and their inputs so a prover cannot pick proofs whose errors
+/// cancel out under them. 128 bits keep the chance of an invalid batch
+/// passing negligible
+fn weights(
+    env: &Env,
+    vk: &VerificationKey,
+    proofs: &Vec<Proof>,
+    public_inputs: &Vec<Vec<BytesN<32>>>,
+) -> Vec<u128> {
+    // every point and input has a fixed length once shapes are checked
+    let mut transcript = Bytes::from_slice(env, b"SEMAPHORE-VERIFIER-BATCH");
+    transcript.push_back(vk.curve as u8);
+    for point in [&vk.alpha, &vk.beta, &vk.gamma, &vk.delta] {
+        transcript.append(point);
+    }
+    for point in vk.ic.iter() {
+        transcript.append(&point);
+    }
+    for (proof, inputs) in proofs.iter().zip(public_inputs.iter()) {
+        transcript.append(&proof.a);
+        transcript.append(&proof.b);
+        transcript.append(&proof.c);
+        for input in inputs.iter() {
+            transcript.append(&input.into());
+        }
+    }
+    let seed = env.crypto().sha256(&transcript).to_array();
+
+    let mut weights = Vec::new(env);
+    for i in 0..proofs.len() {
+        let mut preimage = Bytes::from_array(env, &seed);
+        preimage.extend_from_array(&i.to_be_bytes());
+        let hash = env.crypto().sha256(&preimage).to_array();
+        weights.push_back(u128::from_be_bytes(hash[..16].try_into().unwrap()) | 1);
+    }
+    weights
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+mod bls12_381;
+#[cfg(feature = "bn254")]
+mod bn254;
+pub mod datatypes;
+mod test;
+
+/// Stand-in for the BN254 verifier when the `bn254` feature is off. Keys on
+/// the curve cannot be set then, but may have been stored by a build with it
+#[cfg(not(feature = "bn254"))]
+mod bn254 {
+    use crate::datatypes::{Error, Proof, VerificationKey};
+    use soroban_sdk::{BytesN, Vec};
+
+    pub fn verify(
+        _vk: &VerificationKey,
+        _proof: &Proof,
+        _public_inputs: &Vec<BytesN<32>>,
+    ) -> Result<bool, Error> {
+        Err(Error::UnsupportedCurve)
+    }
 
-    #[test]
-    fn test_example_verifier() {
-        let result = example();
-        assert_eq!(result, "Hello, Verifier!");
+    pub fn verify_batch(
+        _vk: &VerificationKey,
+        _proofs: &Vec<Proof>,
+        _public_inputs: &Vec<Vec<BytesN<32>>>,
+        _weights: &Vec<u128>,
+    ) -> Result<bool, Error> {
+        Err(Error::UnsupportedCurve)
     }
 }
@@ -1,7 +1,387 @@
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        assert_eq!(2 - 2, 0); 
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+#[cfg(feature = "bn254")]
+use ark_ec::CurveGroup;
+#[cfg(feature = "bn254")]
+use ark_ff::Field;
+use converter::{
+    encoding::{encode_fr, encode_g1, encode_g2},
+    snarkjs::{public_signals, ProofJson, VerificationKeyJson},
+};
+use soroban_sdk::{
+    crypto::bls12_381::{Fr, G1Affine, G2Affine},
+    testutils::Address as _,
+    vec, U256,
+};
+
+const DEPTH: u32 = 10;
+
+fn setup(env: &Env) -> VerifierClient<'_> {
+    env.mock_all_auths();
+    let contract_id = env.register(Verifier, (Address::generate(env),));
+    VerifierClient::new(env, &contract_id)
+}
+
+fn words(env: &Env, words: &[[u8; 32]]) -> Bytes {
+    let mut bytes = Bytes::new(env);
+    for word in words {
+        bytes.extend_from_array(word);
+    }
+    bytes
+}
+
+/// snarkjs proof of `3 * 11 = 33` from the converter's test vectors
+fn bn254_fixture(env: &Env) -> (VerificationKey, Proof, Vec<BytesN<32>>) {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../crates/converter/test-vectors");
+    let read = |name| std::fs::read_to_string(dir.join(name)).unwrap();
+    let vk = VerificationKeyJson::parse(&read("verification_key.json"))
+        .unwrap()
+        .to_verifying_key()
+        .unwrap();
+    let proof = ProofJson::parse(&read("proof.json"))
+        .unwrap()
+        .to_proof()
+        .unwrap();
+
+    let mut ic = Vec::new(env);
+    for point in &vk.gamma_abc_g1 {
+        ic.push_back(words(env, &encode_g1(point)));
     }
+    let mut inputs = Vec::new(env);
+    for signal in public_signals(&read("public.json")).unwrap() {
+        inputs.push_back(BytesN::from_array(env, &encode_fr(&signal)));
+    }
+    (
+        VerificationKey {
+            curve: Curve::Bn254,
+            alpha: words(env, &encode_g1(&vk.alpha_g1)),
+            beta: words(env, &encode_g2(&vk.beta_g2)),
+            gamma: words(env, &encode_g2(&vk.gamma_g2)),
+            delta: words(env, &encode_g2(&vk.delta_g2)),
+            ic,
+        },
+        Proof {
+            a: words(env, &encode_g1(&proof.a)),
+            b: words(env, &encode_g2(&proof.b)),
+            c: words(env, &encode_g1(&proof.c)),
+        },
+        inputs,
+    )
+}
+
+fn scalar(env: &Env, value: u32) -> Fr {
+    U256::from_u32(env, value).into()
+}
+
+/// A BLS12-381 key with known discrete logs and a proof for the public input
+/// `input`, built by solving the verification equation for `a`
+fn bls12_381_fixture(env: &Env, input: u32) -> (VerificationKey, Proof, Vec<BytesN<32>>) {
+    let bls = env.crypto().bls12_381();
+    let dst = Bytes::from_slice(env, b"SEMAPHORE-VERIFIER-TEST");
+    let p = bls.hash_to_g1(&Bytes::from_slice(env, b"g1"), &dst);
+    let h = bls.hash_to_g2(&Bytes::from_slice(env, b"g2"), &dst);
+    let g1 = |k: u32| -> Bytes { bls.g1_mul(&p, &scalar(env, k)).to_bytes().into() };
+    let g2 = |k: u32| -> Bytes { bls.g2_mul(&h, &scalar(env, k)).to_bytes().into() };
+    let (alpha, beta, gamma, delta, ic0, ic1, c) = (3, 5, 7, 11, 13, 17, 19);
+
+    // a = alpha * beta + gamma * (ic0 + ic1 * input) + c * delta
+    let vk_x = bls.fr_add(
+        &scalar(env, ic0),
+        &bls.fr_mul(&scalar(env, ic1), &scalar(env, input)),
+    );
+    let a = bls.fr_add(
+        &bls.fr_add(
+            &bls.fr_mul(&scalar(env, alpha), &scalar(env, beta)),
+            &bls.fr_mul(&scalar(env, gamma), &vk_x),
+        ),
+        &bls.fr_mul(&scalar(env, c), &scalar(env, delta)),
+    );
+
+    (
+        VerificationKey {
+            curve: Curve::Bls12381,
+            alpha: g1(alpha),
+            beta: g2(beta),
+            gamma: g2(gamma),
+            delta: g2(delta),
+            ic: vec![env, g1(ic0), g1(ic1)],
+        },
+        Proof {
+            a: bls.g1_mul(&p, &a).to_bytes().into(),
+            b: G2Affine::to_bytes(&h).into(),
+            c: g1(c),
+        },
+        vec![
+            env,
+            U256::from_u32(env, input).to_be_bytes().try_into().unwrap(),
+        ],
+    )
+}
+
+#[test]
+#[cfg(feature = "bn254")]
+fn test_bn254() {
+    let env = Env::default();
+    let client = setup(&env);
+    let (vk, proof, inputs) = bn254_fixture(&env);
+    client.set_verification_key(&DEPTH, &vk);
+    assert_eq!(client.get_verification_key(&DEPTH), vk);
+
+    assert!(client.verify(&DEPTH, &proof, &inputs));
+
+    let wrong = vec![&env, BytesN::from_array(&env, &encode_fr(&34u64.into()))];
+    assert!(!client.verify(&DEPTH, &proof, &wrong));
+
+    // G2 coordinates in snarkjs order, real part first
+    let mut swapped = proof.clone();
+    swapped.b = proof.b.slice(32..64);
+    swapped.b.append(&proof.b.slice(0..32));
+    swapped.b.append(&proof.b.slice(64..128));
+    assert_eq!(
+        client.try_verify(&DEPTH, &swapped, &inputs),
+        Err(Ok(Error::InvalidProof))
+    );
+
+    // not in the scalar field
+    let too_large = vec![&env, BytesN::from_array(&env, &[0xff; 32])];
+    assert_eq!(
+        client.try_verify(&DEPTH, &proof, &too_large),
+        Err(Ok(Error::InvalidPublicInputs))
+    );
+}
+
+#[test]
+#[cfg(not(feature = "bn254"))]
+fn test_bn254_unsupported() {
+    let env = Env::default();
+    let client = setup(&env);
+    let (vk, _, _) = bn254_fixture(&env);
+    assert_eq!(
+        client.try_set_verification_key(&DEPTH, &vk),
+        Err(Ok(Error::UnsupportedCurve))
+    );
+}
+
+#[test]
+fn test_bls12_381() {
+    let env = Env::default();
+    env.budget().reset_unlimited();
+    let client = setup(&env);
+    let (vk, proof, inputs) = bls12_381_fixture(&env, 42);
+    client.set_verification_key(&DEPTH, &vk);
+
+    assert!(client.verify(&DEPTH, &proof, &inputs));
+
+    let (_, _, wrong) = bls12_381_fixture(&env, 43);
+    assert!(!client.verify(&DEPTH, &proof, &wrong));
+
+    let modulus = vec![&env, BytesN::from_array(&env, &bls12_381::MODULUS)];
+    assert_eq!(
+        client.try_verify(&DEPTH, &proof, &modulus),
+        Err(Ok(Error::InvalidPublicInputs))
+    );
+}
+
+/// The same statement proved again: `(a t, b / t, c)` is a valid proof
+/// whenever `(a, b, c)` is
+#[cfg(feature = "bn254")]
+fn rerandomize(env: &Env, proof: &Proof, t: u64) -> Proof {
+    let t = ark_bn254::Fr::from(t);
+    let proof = converter::encoding::decode_proof(&words_of(proof)).unwrap();
+    let a = (proof.a * t).into_affine();
+    let b = (proof.b * t.inverse().unwrap()).into_affine();
+    Proof {
+        a: words(env, &encode_g1(&a)),
+        b: words(env, &encode_g2(&b)),
+        c: words(env, &encode_g1(&proof.c)),
+    }
+}
+
+#[cfg(feature = "bn254")]
+fn words_of(proof: &Proof) -> [[u8; 32]; 8] {
+    let mut bytes = proof.a.clone();
+    bytes.append(&proof.b);
+    bytes.append(&proof.c);
+    let mut words = [[0; 32]; 8];
+    for (i, word) in words.iter_mut().enumerate() {
+        bytes
+            .slice(32 * i as u32..32 * (i as u32 + 1))
+            .copy_into_slice(word);
+    }
+    words
+}
+
+#[test]
+#[cfg(feature = "bn254")]
+fn test_verify_batch_bn254() {
+    let env = Env::default();
+    let client = setup(&env);
+    let (vk, proof, inputs) = bn254_fixture(&env);
+    client.set_verification_key(&DEPTH, &vk);
+
+    let proofs = vec![
+        &env,
+        proof.clone(),
+        rerandomize(&env, &proof, 2),
+        rerandomize(&env, &proof, 3),
+    ];
+    let all_inputs = vec![&env, inputs.clone(), inputs.clone(), inputs.clone()];
+    assert_eq!(
+        client.verify_batch(&DEPTH, &proofs, &all_inputs),
+        vec![&env, true, true, true]
+    );
+
+    // the failing proof is found by checking each one
+    let mut wrong_inputs = all_inputs.clone();
+    wrong_inputs.set(
+        1,
+        vec![&env, BytesN::from_array(&env, &encode_fr(&34u64.into()))],
+    );
+    assert_eq!(
+        client.verify_batch(&DEPTH, &proofs, &wrong_inputs),
+        vec![&env, true, false, true]
+    );
+
+    assert_eq!(
+        client.verify_batch(&DEPTH, &Vec::new(&env), &Vec::new(&env)),
+        Vec::new(&env)
+    );
+    assert_eq!(
+        client.try_verify_batch(&DEPTH, &proofs, &vec![&env, inputs]),
+        Err(Ok(Error::InvalidPublicInputs))
+    );
+}
+
+#[test]
+fn test_verify_batch_bls12_381() {
+    let env = Env::default();
+    env.budget().reset_unlimited();
+    let client = setup(&env);
+    let (vk, proof_42, inputs_42) = bls12_381_fixture(&env, 42);
+    let (_, proof_43, inputs_43) = bls12_381_fixture(&env, 43);
+    client.set_verification_key(&DEPTH, &vk);
+
+    let proofs = vec![&env, proof_42, proof_43];
+    assert_eq!(
+        client.verify_batch(
+            &DEPTH,
+            &proofs,
+            &vec![&env, inputs_42.clone(), inputs_43.clone()]
+        ),
+        vec![&env, true, true]
+    );
+    assert_eq!(
+        client.verify_batch(&DEPTH, &proofs, &vec![&env, inputs_42.clone(), inputs_42]),
+        vec![&env, true, false]
+    );
+    assert_eq!(
+        client.verify_batch(&DEPTH, &proofs, &vec![&env, inputs_43.clone(), inputs_43]),
+        vec![&env, false, true]
+    );
+}
+
+#[test]
+fn test_verify_batch_errors_do_not_cancel() {
+    let env = Env::default();
+    env.budget().reset_unlimited();
+    let client = setup(&env);
+    let (vk, proof, inputs) = bls12_381_fixture(&env, 42);
+    client.set_verification_key(&DEPTH, &vk);
+
+    // c + d and c - d are both wrong, but under one weight for both proofs
+    // their sum is that of two valid proofs
+    let bls = env.crypto().bls12_381();
+    let c = G1Affine::from_bytes(proof.c.clone().try_into().unwrap());
+    let d = bls.hash_to_g1(
+        &Bytes::from_slice(&env, b"d"),
+        &Bytes::from_slice(&env, b"dst"),
+    );
+    let minus_d = bls.g1_mul(&d, &bls.fr_sub(&scalar(&env, 0), &scalar(&env, 1)));
+    let shifted = |delta: &G1Affine| Proof {
+        c: bls.g1_add(&c, delta).to_bytes().into(),
+        ..proof.clone()
+    };
+    let proofs = vec![&env, shifted(&d), shifted(&minus_d)];
+    let inputs = vec![&env, inputs.clone(), inputs];
+    let fixed = vec![&env, 7u128, 7u128];
+    assert!(env.as_contract(&client.address, || {
+        bls12_381::verify_batch(&env, &vk, &proofs, &inputs, &fixed).unwrap()
+    }));
+
+    assert_eq!(
+        client.verify_batch(&DEPTH, &proofs, &inputs),
+        vec![&env, false, false]
+    );
+}
+
+#[test]
+#[cfg(feature = "bn254")]
+fn test_curve_is_part_of_the_key() {
+    let env = Env::default();
+    env.budget().reset_unlimited();
+    let client = setup(&env);
+    let (bn254_vk, bn254_proof, bn254_inputs) = bn254_fixture(&env);
+    let (bls_vk, bls_proof, bls_inputs) = bls12_381_fixture(&env, 42);
+    client.set_verification_key(&DEPTH, &bn254_vk);
+    client.set_verification_key(&(DEPTH + 1), &bls_vk);
+
+    assert_eq!(client.get_verification_key(&DEPTH).curve, Curve::Bn254);
+    assert_eq!(
+        client.get_verification_key(&(DEPTH + 1)).curve,
+        Curve::Bls12381
+    );
+    assert!(client.verify(&DEPTH, &bn254_proof, &bn254_inputs));
+    assert!(client.verify(&(DEPTH + 1), &bls_proof, &bls_inputs));
+
+    // a proof on the other curve has the wrong point sizes
+    assert_eq!(
+        client.try_verify(&DEPTH, &bls_proof, &bn254_inputs),
+        Err(Ok(Error::InvalidProof))
+    );
+
+    // a BN254 key labelled BLS12-381 is rejected
+    let mut mislabelled = bn254_vk;
+    mislabelled.curve = Curve::Bls12381;
+    assert_eq!(
+        client.try_set_verification_key(&DEPTH, &mislabelled),
+        Err(Ok(Error::InvalidVerificationKey))
+    );
+}
+
+#[test]
+#[cfg(feature = "bn254")]
+fn test_verify_errors() {
+    let env = Env::default();
+    let client = setup(&env);
+    let (vk, proof, inputs) = bn254_fixture(&env);
+    assert_eq!(
+        client.try_verify(&DEPTH, &proof, &inputs),
+        Err(Ok(Error::VerificationKeyNotFound))
+    );
+
+    let mut no_ic = vk.clone();
+    no_ic.ic = Vec::new(&env);
+    assert_eq!(
+        client.try_set_verification_key(&DEPTH, &no_ic),
+        Err(Ok(Error::InvalidVerificationKey))
+    );
+
+    client.set_verification_key(&DEPTH, &vk);
+    assert_eq!(
+        client.try_verify(&DEPTH, &proof, &Vec::new(&env)),
+        Err(Ok(Error::InvalidPublicInputs))
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_set_verification_key_requires_admin() {
+    let env = Env::default();
+    let contract_id = env.register(Verifier, (Address::generate(&env),));
+    let client = VerifierClient::new(&env, &contract_id);
+    let (vk, _, _) = bn254_fixture(&env);
+    client.set_verification_key(&DEPTH, &vk);
 }
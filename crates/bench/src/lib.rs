@@ -0,0 +1,207 @@
+//! Budget benchmarks for the contract entrypoints.
+//!
+//! `measure` runs one call in a test `Env` with the budget reset and the
+//! storage footprint cleared beforehand, so the CPU instructions, memory,
+//! ledger entries and bytes it reports belong to that call alone. The
+//! benchmarks in the tests record every entrypoint at several group sizes
+//! into a `Report`, written to `budget_report.json` by
+//!
+//! ```text
+//! cargo test -p bench --release -- --ignored
+//! ```
+//!
+//! The contracts run natively, so only host functions are metered: code
+//! inside a contract, such as the BN254 arithmetic of `verifier`, costs far
+//! less here than as WASM. The numbers are lower bounds to compare between
+//! revisions rather than exact fees.
+
+use std::path::Path;
+use std::rc::Rc;
+
+use serde::Serialize;
+use soroban_env_host::{
+    budget::Budget,
+    storage::{AccessType, Footprint, StorageMap},
+};
+use soroban_sdk::{
+    testutils::EnvTestConfig,
+    xdr::{LedgerKey, Limits, ScAddress, WriteXdr},
+    Address, Env,
+};
+
+/// CPU instructions a transaction may use, the default budget of a test `Env`
+pub const CPU_INSTRUCTION_LIMIT: u64 = 100_000_000;
+
+/// Memory a transaction may use, the default budget of a test `Env`
+pub const MEMORY_BYTES_LIMIT: u64 = 40 * 1024 * 1024;
+
+/// Resources used by a single call
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Cost {
+    pub cpu_instructions: u64,
+    pub memory_bytes: u64,
+    /// Ledger entries in the footprint, read-write ones included
+    pub read_entries: u32,
+    pub write_entries: u32,
+    /// Size of the footprint's entries before the call
+    pub read_bytes: u32,
+    /// Size of the read-write entries after the call
+    pub write_bytes: u32,
+}
+
+impl Cost {
+    pub fn within_limits(&self) -> bool {
+        self.cpu_instructions <= CPU_INSTRUCTION_LIMIT && self.memory_bytes <= MEMORY_BYTES_LIMIT
+    }
+}
+
+/// A test `Env` with all auths mocked and an unlimited budget, that writes
+/// no test snapshot when dropped, which for large groups would take tens of
+/// megabytes
+pub fn new_env() -> Env {
+    let env = Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    env
+}
+
+/// Copy the ledger entries of `contract` from `from` into `to`, where the
+/// contract must be registered already. The host keeps every object until
+/// its `Env` is dropped, so large groups are filled across several `Env`s
+pub fn copy_contract_data(from: &Env, to: &Env, contract: &Address) {
+    let contract = ScAddress::from(contract);
+    let budget = from.host().budget_cloned();
+    let entries: Vec<_> = from
+        .host()
+        .with_mut_storage(|storage| {
+            Ok(storage
+                .map
+                .iter(&budget)?
+                .filter(|(key, _)| {
+                    matches!(key.as_ref(), LedgerKey::ContractData(data) if data.contract == contract)
+                })
+                .cloned()
+                .collect())
+        })
+        .unwrap();
+
+    let budget = to.host().budget_cloned();
+    to.host()
+        .with_mut_storage(|storage| {
+            for (key, value) in entries {
+                if let Some((entry, live_until)) = value {
+                    storage.put(&key, &entry, live_until, &budget)?;
+                }
+            }
+            Ok(())
+        })
+        .unwrap();
+}
+
+/// Run `call` and return its result with the resources it used
+pub fn measure<T>(env: &Env, call: impl FnOnce() -> T) -> (T, Cost) {
+    let host = env.host();
+    let budget = host.budget_cloned();
+    let before = host
+        .with_mut_storage(|storage| {
+            storage.footprint = Footprint::default();
+            Ok(storage.map.clone())
+        })
+        .unwrap();
+
+    env.budget().reset_unlimited();
+    let result = call();
+    let mut cost = Cost {
+        cpu_instructions: env.budget().cpu_instruction_cost(),
+        memory_bytes: env.budget().memory_bytes_cost(),
+        ..Cost::default()
+    };
+
+    host.with_mut_storage(|storage| {
+        for (key, access) in storage.footprint.0.iter(&budget)? {
+            cost.read_entries += 1;
+            cost.read_bytes += entry_size(&before, key, &budget);
+            if *access == AccessType::ReadWrite {
+                cost.write_entries += 1;
+                cost.write_bytes += entry_size(&storage.map, key, &budget);
+            }
+        }
+        Ok(())
+    })
+    .unwrap();
+    (result, cost)
+}
+
+/// XDR size of the entry under `key`, 0 when there is none
+fn entry_size(map: &StorageMap, key: &Rc<LedgerKey>, budget: &Budget) -> u32 {
+    match map.get::<Rc<LedgerKey>>(key, budget).unwrap() {
+        Some(Some((entry, _))) => entry.to_xdr(Limits::none()).unwrap().len() as u32,
+        _ => 0,
+    }
+}
+
+/// Cost of one entrypoint of one contract
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Measurement {
+    pub contract: &'static str,
+    pub entrypoint: &'static str,
+    /// Tree kind of the group, or curve of the verification key
+    pub variant: Option<&'static str>,
+    /// Members the benchmark's group started with, `None` without a group
+    pub group_size: Option<u32>,
+    #[serde(flatten)]
+    pub cost: Cost,
+    pub within_limits: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub cpu_instruction_limit: u64,
+    pub memory_bytes_limit: u64,
+    pub measurements: Vec<Measurement>,
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self {
+            cpu_instruction_limit: CPU_INSTRUCTION_LIMIT,
+            memory_bytes_limit: MEMORY_BYTES_LIMIT,
+            measurements: Vec::new(),
+        }
+    }
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(
+        &mut self,
+        contract: &'static str,
+        entrypoint: &'static str,
+        variant: Option<&'static str>,
+        group_size: Option<u32>,
+        cost: Cost,
+    ) {
+        self.measurements.push(Measurement {
+            contract,
+            entrypoint,
+            variant,
+            group_size,
+            within_limits: cost.within_limits(),
+            cost,
+        });
+    }
+
+    /// Write the report as pretty-printed JSON
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        std::fs::write(path, json)
+    }
+}
+
+mod test;
@@ -0,0 +1,519 @@
+#![cfg(test)]
+
+use super::*;
+use group::{
+    datatypes::{GroupMetadata, HashFunction, TreeKind},
+    imt::hash_to_g1,
+    SemaphoreGroupContract, SemaphoreGroupContractClient, DEFAULT_DEPTH, MAX_DEPTH,
+};
+use semaphore::{
+    datatypes::DataKey, verifier::Groth16Proof, Group, MerkleTree, Semaphore, SemaphoreClient,
+    SemaphoreProof,
+};
+use soroban_sdk::{
+    contract, contractimpl,
+    crypto::bls12_381::G2Affine,
+    testutils::{Address as _, Ledger},
+    vec, Address, Bytes, BytesN, Map, Vec, U256,
+};
+use verifier::{
+    datatypes::{Curve, Proof, VerificationKey},
+    Verifier, VerifierClient,
+};
+
+/// Group sizes benchmarked. With the members the benchmarks add on top, the
+/// largest still fits a tree of `MAX_DEPTH`
+const GROUP_SIZES: [u32; 3] = [1, 100, 1_000];
+
+/// Members per call of the batch entrypoints
+const BATCH: u32 = 10;
+
+/// Members added per `Env` when filling a group before measuring
+const FILL_BATCH: u32 = 50;
+
+/// Semaphore groups start at this depth
+const SEMAPHORE_DEPTH: u32 = 16;
+
+/// Records the calls of one benchmark into the report
+struct Run<'a> {
+    env: &'a Env,
+    report: &'a mut Report,
+    contract: &'static str,
+    variant: Option<&'static str>,
+    group_size: Option<u32>,
+}
+
+impl Run<'_> {
+    fn call<T>(&mut self, entrypoint: &'static str, call: impl FnOnce() -> T) -> T {
+        let (result, cost) = measure(self.env, call);
+        self.report.record(
+            self.contract,
+            entrypoint,
+            self.variant,
+            self.group_size,
+            cost,
+        );
+        result
+    }
+}
+
+fn commitment(env: &Env, i: u32) -> Bytes {
+    let mut bytes = [1u8; 32];
+    bytes[28..].copy_from_slice(&i.to_be_bytes());
+    Bytes::from_array(env, &bytes)
+}
+
+fn commitments(env: &Env, range: core::ops::Range<u32>) -> Vec<Bytes> {
+    let mut commitments = Vec::new(env);
+    for i in range {
+        commitments.push_back(commitment(env, i));
+    }
+    commitments
+}
+
+/// Members a benchmark adds on top of a group of `size`, and the zero key
+/// of indexed trees
+fn leaves_for(size: u32) -> u32 {
+    size + 2 * BATCH + 2
+}
+
+/// Smallest depth, at least `DEFAULT_DEPTH`, with room for `leaves_for(size)`,
+/// and no deeper than `MAX_DEPTH`. Sparse trees ignore it
+fn depth_for(size: u32) -> u32 {
+    let mut depth = DEFAULT_DEPTH;
+    while 1u32 << depth < leaves_for(size) && depth < MAX_DEPTH {
+        depth += 1;
+    }
+    depth
+}
+
+fn metadata(tree: TreeKind, size: u32) -> GroupMetadata {
+    GroupMetadata {
+        name: None,
+        description_uri: None,
+        depth: Some(depth_for(size)),
+        tree,
+        hash: HashFunction::Keccak256,
+    }
+}
+
+/// A fresh `Env` with the storage of the group contract `contract` of `env`
+fn reopen(env: &Env, contract: &str) -> Env {
+    let fresh = new_env();
+    let contract_id = Address::from_str(&fresh, contract);
+    fresh.register_at(
+        &contract_id,
+        SemaphoreGroupContract,
+        (Address::generate(&fresh),),
+    );
+    copy_contract_data(env, &fresh, &contract_id);
+    fresh
+}
+
+/// Create a group of `tree` with members `0..size`, recording the cost of
+/// `create_group`. Returns the `Env` holding it, the contract id and the
+/// group id
+fn filled_group(
+    report: &mut Report,
+    tree: TreeKind,
+    variant: &'static str,
+    size: u32,
+) -> (Env, String, u32) {
+    let mut env = new_env();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let (group_id, cost) = measure(&env, || {
+        client.create_group(&admin, &Some(metadata(tree, size)))
+    });
+    report.record("group", "create_group", Some(variant), Some(size), cost);
+    let contract = contract_id.to_string().to_string();
+
+    for start in (0..size).step_by(FILL_BATCH as usize) {
+        let members = commitments(&env, start..size.min(start + FILL_BATCH));
+        SemaphoreGroupContractClient::new(&env, &Address::from_str(&env, &contract))
+            .add_members(&group_id, &members);
+        env = reopen(&env, &contract);
+    }
+    (env, contract, group_id)
+}
+
+/// Every entrypoint of an incremental group
+fn bench_group(report: &mut Report, size: u32) {
+    let (env, contract, group_id) =
+        filled_group(report, TreeKind::Incremental, "incremental", size);
+    let client = SemaphoreGroupContractClient::new(&env, &Address::from_str(&env, &contract));
+    let mut run = Run {
+        env: &env,
+        report,
+        contract: "group",
+        variant: Some("incremental"),
+        group_size: Some(size),
+    };
+
+    let member = commitment(&env, 0);
+    run.call("get_group", || client.get_group(&group_id));
+    run.call("get_group_status", || client.get_group_status(&group_id));
+    run.call("get_group_admin", || client.get_group_admin(&group_id));
+    run.call("get_member_count", || client.get_member_count(&group_id));
+    let index = run
+        .call("get_member", || client.get_member(&group_id, &member))
+        .index;
+    run.call("is_member", || client.is_member(&group_id, &member));
+    run.call("get_merkle_root", || client.get_merkle_root(&group_id));
+    let proof = run.call("get_proof", || client.get_proof(&group_id, &index));
+    let leaf = hash_to_g1(&env, member.clone());
+    assert!(run.call("verify_proof", || client
+        .verify_proof(&group_id, &leaf, &proof)));
+    let proof = run.call("get_member_proof", || {
+        client.get_member_proof(&group_id, &member)
+    });
+    assert!(run.call("verify_member_proof", || {
+        client.verify_member_proof(&group_id, &member, &proof)
+    }));
+
+    // members added here are updated and removed again below
+    let added = commitments(&env, size..size + BATCH);
+    let updated = commitments(&env, size + BATCH..size + 2 * BATCH);
+    let mut pairs = Vec::new(&env);
+    for (old, new) in added.iter().zip(updated.iter()) {
+        pairs.push_back((old, new));
+    }
+    let single = commitment(&env, size + 2 * BATCH);
+    run.call("add_member", || client.add_member(&group_id, &single));
+    run.call("add_members", || client.add_members(&group_id, &added));
+    run.call("update_member", || {
+        client.update_member(&group_id, &member, &commitment(&env, size + 2 * BATCH + 1))
+    });
+    run.call("update_members", || {
+        client.update_members(&group_id, &pairs)
+    });
+    run.call("remove_member", || client.remove_member(&group_id, &single));
+    run.call("remove_members", || {
+        client.remove_members(&group_id, &updated)
+    });
+
+    let new_admin = Address::generate(&env);
+    let expiration_ledger = env.ledger().sequence() + 100;
+    run.call("update_group_admin", || {
+        client.update_group_admin(&group_id, &new_admin, &expiration_ledger)
+    });
+    run.call("get_pending_admin", || client.get_pending_admin(&group_id));
+    run.call("cancel_admin_transfer", || {
+        client.cancel_admin_transfer(&group_id)
+    });
+    client.update_group_admin(&group_id, &new_admin, &expiration_ledger);
+    run.call("accept_group_admin", || {
+        client.accept_group_admin(&group_id)
+    });
+    run.call("archive_group", || client.archive_group(&group_id));
+    let deleted = commitments(&env, 1..size.min(BATCH + 1));
+    run.call("delete_group", || client.delete_group(&group_id, &deleted));
+}
+
+/// Entrypoints whose cost depends on an indexed tree
+fn bench_indexed_group(report: &mut Report, size: u32) {
+    let (env, contract, group_id) = filled_group(report, TreeKind::Indexed, "indexed", size);
+    let client = SemaphoreGroupContractClient::new(&env, &Address::from_str(&env, &contract));
+    let mut run = Run {
+        env: &env,
+        report,
+        contract: "group",
+        variant: Some("indexed"),
+        group_size: Some(size),
+    };
+
+    let member = commitment(&env, 0);
+    let outsider = commitment(&env, size);
+    let proof = run.call("prove_non_membership", || {
+        client.prove_non_membership(&group_id, &outsider)
+    });
+    assert!(run.call("verify_non_membership", || {
+        client.verify_non_membership(&group_id, &outsider, &proof)
+    }));
+    let proof = run.call("get_member_proof", || {
+        client.get_member_proof(&group_id, &member)
+    });
+    assert!(run.call("verify_member_proof", || {
+        client.verify_member_proof(&group_id, &member, &proof)
+    }));
+    run.call("add_member", || client.add_member(&group_id, &outsider));
+    run.call("update_member", || {
+        client.update_member(&group_id, &outsider, &commitment(&env, size + 1))
+    });
+    run.call("remove_member", || client.remove_member(&group_id, &member));
+    run.call("renounce_admin", || client.renounce_admin(&group_id));
+}
+
+/// Entrypoints whose cost depends on a sparse tree
+fn bench_sparse_group(report: &mut Report, size: u32) {
+    let (env, contract, group_id) = filled_group(report, TreeKind::Sparse, "sparse", size);
+    let client = SemaphoreGroupContractClient::new(&env, &Address::from_str(&env, &contract));
+    let mut run = Run {
+        env: &env,
+        report,
+        contract: "group",
+        variant: Some("sparse"),
+        group_size: Some(size),
+    };
+
+    let member = commitment(&env, 0);
+    let proof = run.call("get_sparse_proof", || {
+        client.get_sparse_proof(&group_id, &member)
+    });
+    assert!(run.call("verify_sparse_proof", || {
+        client.verify_sparse_proof(&group_id, &member, &proof)
+    }));
+    let proof = run.call("get_member_proof", || {
+        client.get_member_proof(&group_id, &member)
+    });
+    assert!(run.call("verify_member_proof", || {
+        client.verify_member_proof(&group_id, &member, &proof)
+    }));
+    let outsider = commitment(&env, size);
+    run.call("add_member", || client.add_member(&group_id, &outsider));
+    run.call("update_member", || {
+        client.update_member(&group_id, &outsider, &commitment(&env, size + 1))
+    });
+    run.call("remove_member", || client.remove_member(&group_id, &member));
+}
+
+/// Accepts every proof, so `semaphore` is measured without the pairing
+#[contract]
+struct MockVerifier;
+
+#[contractimpl]
+impl MockVerifier {
+    pub fn verify(
+        _env: Env,
+        _depth: u32,
+        _proof: Groth16Proof,
+        _public_inputs: Vec<BytesN<32>>,
+    ) -> bool {
+        true
+    }
+
+    pub fn verify_batch(
+        env: Env,
+        _depth: u32,
+        proofs: Vec<Groth16Proof>,
+        _public_inputs: Vec<Vec<BytesN<32>>>,
+    ) -> Vec<bool> {
+        let mut results = Vec::new(&env);
+        for _ in proofs.iter() {
+            results.push_back(true);
+        }
+        results
+    }
+}
+
+fn semaphore_proof(env: &Env, root: &BytesN<32>, nullifier: u8) -> SemaphoreProof {
+    let mut points = Vec::new(env);
+    for _ in 0..8 {
+        points.push_back(BytesN::from_array(env, &[1; 32]));
+    }
+    SemaphoreProof {
+        merkle_tree_depth: SEMAPHORE_DEPTH,
+        merkle_tree_root: root.clone(),
+        nullifier: BytesN::from_array(env, &[nullifier; 32]),
+        message: BytesN::from_array(env, &[2; 32]),
+        scope: BytesN::from_array(env, &[3; 32]),
+        points,
+    }
+}
+
+/// `semaphore` recomputes its root from every leaf, so its group is written
+/// to storage directly rather than filled one member at a time
+fn bench_semaphore(report: &mut Report, size: u32) {
+    let env = new_env();
+    env.ledger().set_timestamp(1_000);
+    let verifier = env.register(MockVerifier, ());
+    let contract_id = env.register(Semaphore, (Address::generate(&env), verifier));
+    let client = SemaphoreClient::new(&env, &contract_id);
+    let mut run = Run {
+        env: &env,
+        report,
+        contract: "semaphore",
+        variant: None,
+        group_size: Some(size),
+    };
+
+    let group_id = run.call("create_group", || {
+        client.create_group(&Address::generate(&env), &3600)
+    });
+    // proofs are against an earlier root, so only its creation date is needed
+    let root = BytesN::from_array(&env, &[9; 32]);
+    env.as_contract(&contract_id, || {
+        let key = DataKey::Group(group_id);
+        let mut group: Group = env.storage().persistent().get(&key).unwrap();
+        let mut leaves = Vec::new(&env);
+        for i in 0..size {
+            leaves.push_back(BytesN::try_from(commitment(&env, i)).unwrap());
+        }
+        group.merkle_tree = MerkleTree {
+            depth: SEMAPHORE_DEPTH,
+            leaves,
+        };
+        group.merkle_root_creation_dates = Map::from_array(&env, [(root.clone(), 1_000)]);
+        env.storage().persistent().set(&key, &group);
+    });
+
+    let member = BytesN::try_from(commitment(&env, size)).unwrap();
+    run.call("add_member", || client.add_member(&group_id, &member));
+    let proof = semaphore_proof(&env, &root, 1);
+    run.call("validate_proof", || {
+        client.validate_proof(&group_id, &proof)
+    });
+    let proofs = Vec::from_array(
+        &env,
+        [2, 3, 4, 5].map(|nullifier| semaphore_proof(&env, &root, nullifier)),
+    );
+    run.call("validate_proofs", || {
+        client.validate_proofs(&group_id, &proofs)
+    });
+    run.call("archive_group", || client.archive_group(&group_id));
+}
+
+/// A BLS12-381 key with known discrete logs and a proof for the public input
+/// `input`, built by solving the verification equation for `a`
+fn bls12_381_fixture(env: &Env, input: u32) -> (VerificationKey, Proof, Vec<BytesN<32>>) {
+    let bls = env.crypto().bls12_381();
+    let scalar = |value: u32| U256::from_u32(env, value).into();
+    let dst = Bytes::from_slice(env, b"SEMAPHORE-VERIFIER-TEST");
+    let p = bls.hash_to_g1(&Bytes::from_slice(env, b"g1"), &dst);
+    let h = bls.hash_to_g2(&Bytes::from_slice(env, b"g2"), &dst);
+    let g1 = |k: u32| -> Bytes { bls.g1_mul(&p, &scalar(k)).to_bytes().into() };
+    let g2 = |k: u32| -> Bytes { bls.g2_mul(&h, &scalar(k)).to_bytes().into() };
+    let (alpha, beta, gamma, delta, ic0, ic1, c) = (3, 5, 7, 11, 13, 17, 19);
+
+    // a = alpha * beta + gamma * (ic0 + ic1 * input) + c * delta
+    let vk_x = bls.fr_add(&scalar(ic0), &bls.fr_mul(&scalar(ic1), &scalar(input)));
+    let a = bls.fr_add(
+        &bls.fr_add(
+            &bls.fr_mul(&scalar(alpha), &scalar(beta)),
+            &bls.fr_mul(&scalar(gamma), &vk_x),
+        ),
+        &bls.fr_mul(&scalar(c), &scalar(delta)),
+    );
+
+    (
+        VerificationKey {
+            curve: Curve::Bls12381,
+            alpha: g1(alpha),
+            beta: g2(beta),
+            gamma: g2(gamma),
+            delta: g2(delta),
+            ic: vec![env, g1(ic0), g1(ic1)],
+        },
+        Proof {
+            a: bls.g1_mul(&p, &a).to_bytes().into(),
+            b: G2Affine::to_bytes(&h).into(),
+            c: g1(c),
+        },
+        vec![
+            env,
+            U256::from_u32(env, input).to_be_bytes().try_into().unwrap(),
+        ],
+    )
+}
+
+/// Single and batch verification with a key of each curve
+fn bench_verifier(report: &mut Report) {
+    let env = new_env();
+    let contract_id = env.register(Verifier, (Address::generate(&env),));
+    let client = VerifierClient::new(&env, &contract_id);
+
+    // BN254 pairings run in contract code, which the host budget does not
+    // meter natively, so only BLS12-381 is measured
+    let fixtures = [("bls12_381", bls12_381_fixture(&env, 42))];
+    for (depth, (curve, (vk, proof, inputs))) in (1..).zip(fixtures) {
+        let mut run = Run {
+            env: &env,
+            report: &mut *report,
+            contract: "verifier",
+            variant: Some(curve),
+            group_size: None,
+        };
+        run.call("set_verification_key", || {
+            client.set_verification_key(&depth, &vk)
+        });
+        assert!(run.call("verify", || client.verify(&depth, &proof, &inputs)));
+        let proofs = Vec::from_array(&env, [proof.clone(), proof.clone(), proof.clone(), proof]);
+        let inputs = Vec::from_array(
+            &env,
+            [inputs.clone(), inputs.clone(), inputs.clone(), inputs],
+        );
+        let results = run.call("verify_batch", || {
+            client.verify_batch(&depth, &proofs, &inputs)
+        });
+        assert!(results.iter().all(|valid| valid));
+    }
+}
+
+fn bench_all(report: &mut Report, sizes: &[u32]) {
+    for &size in sizes {
+        assert!(leaves_for(size) <= 1 << MAX_DEPTH, "no room for {size} members");
+        bench_group(report, size);
+        bench_indexed_group(report, size);
+        bench_sparse_group(report, size);
+        bench_semaphore(report, size);
+    }
+    bench_verifier(report);
+}
+
+fn cost<'a>(report: &'a Report, entrypoint: &str, variant: Option<&str>) -> &'a Cost {
+    &report
+        .measurements
+        .iter()
+        .find(|m| m.entrypoint == entrypoint && m.variant == variant)
+        .unwrap()
+        .cost
+}
+
+#[test]
+fn test_measure() {
+    let mut report = Report::new();
+    bench_all(&mut report, &[1]);
+    assert!(report.measurements.iter().all(|m| m.within_limits));
+
+    // a group's entries are persistent, views only read them
+    let view = cost(&report, "get_merkle_root", Some("incremental"));
+    assert!(view.cpu_instructions > 0 && view.memory_bytes > 0);
+    assert!(view.read_entries > 0 && view.read_bytes > 0);
+    assert_eq!((view.write_entries, view.write_bytes), (0, 0));
+    let update = cost(&report, "add_member", Some("incremental"));
+    assert!(update.write_entries > 0 && update.write_bytes > view.read_bytes);
+
+    // groups of `semaphore` have their own persistent entry
+    let add_member = cost(&report, "add_member", None);
+    assert!(add_member.read_entries > add_member.write_entries);
+}
+
+#[test]
+fn test_max_depth() {
+    let env = new_env();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let metadata = GroupMetadata {
+        depth: Some(MAX_DEPTH),
+        ..metadata(TreeKind::Incremental, 1)
+    };
+    let (group_id, cost) = measure(&env, || {
+        client.create_group(&Address::generate(&env), &Some(metadata))
+    });
+    assert!(cost.within_limits());
+    let (_, cost) = measure(&env, || client.add_member(&group_id, &commitment(&env, 0)));
+    assert!(cost.within_limits());
+}
+
+/// Regenerates `budget_report.json`, see the crate docs. Most of its time
+/// goes to filling the largest groups
+#[test]
+#[ignore]
+fn budget_report() {
+    let mut report = Report::new();
+    bench_all(&mut report, &GROUP_SIZES);
+    report
+        .write(&Path::new(env!("CARGO_MANIFEST_DIR")).join("budget_report.json"))
+        .unwrap();
+}
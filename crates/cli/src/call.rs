@@ -0,0 +1,150 @@
+//! Contract calls, either encoded as XDR or run against a local snapshot.
+
+use std::path::{Path, PathBuf};
+
+use group::SemaphoreGroupContract;
+use semaphore::Semaphore;
+use soroban_sdk::{
+    testutils::EnvTestConfig,
+    xdr::{
+        Hash, HostFunction, InvokeContractArgs, InvokeHostFunctionOp, Limits, Operation,
+        OperationBody, ScAddress, ScSymbol, ScVal, WriteXdr,
+    },
+    Address, Env, InvokeError, Symbol, TryFromVal, Val, Vec,
+};
+
+use crate::Result;
+
+/// Contract a call is sent to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Group,
+    Semaphore,
+}
+
+/// A single contract invocation with arguments living in `env`
+pub struct Call {
+    pub target: Target,
+    pub function: &'static str,
+    pub args: Vec<Val>,
+}
+
+/// Ids both contracts are registered at in an offline ledger
+pub struct Contracts {
+    pub group: String,
+    pub semaphore: String,
+}
+
+/// Environment that is only used to build values, never saved as a snapshot
+pub fn local_env() -> Env {
+    Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    })
+}
+
+/// Strkey of a contract id made of one repeated byte, used for offline runs
+pub fn placeholder_contract_id(byte: u8) -> String {
+    let env = local_env();
+    let address = ScVal::Address(ScAddress::Contract(Hash([byte; 32])));
+    Address::try_from_val(&env, &address)
+        .unwrap()
+        .to_string()
+        .to_string()
+}
+
+/// Encode `call` as an unsigned `InvokeHostFunction` operation in base64 XDR
+pub fn operation_xdr(env: &Env, contract_id: &str, call: &Call) -> Result<String> {
+    let contract = Address::from_str(env, contract_id).to_val();
+    let contract_address = match ScVal::try_from_val(env, &contract) {
+        Ok(ScVal::Address(address)) => address,
+        _ => return Err(format!("invalid contract id {contract_id}").into()),
+    };
+    let args = call
+        .args
+        .iter()
+        .map(|arg| ScVal::try_from_val(env, &arg).map_err(|err| format!("{err:?}")))
+        .collect::<std::result::Result<std::vec::Vec<_>, _>>()?;
+
+    let operation = Operation {
+        source_account: None,
+        body: OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+            host_function: HostFunction::InvokeContract(InvokeContractArgs {
+                contract_address,
+                function_name: ScSymbol(call.function.try_into()?),
+                args: args.try_into()?,
+            }),
+            auth: Default::default(),
+        }),
+    };
+    Ok(operation.to_xdr_base64(Limits::none())?)
+}
+
+/// A local ledger loaded from, and saved back to, a snapshot file
+pub struct Offline {
+    pub env: Env,
+    snapshot: PathBuf,
+}
+
+impl Offline {
+    /// Load `snapshot` (or start from an empty ledger) and register both
+    /// contracts at the given ids; storage from earlier runs is kept
+    pub fn load(snapshot: &Path, contracts: &Contracts) -> Self {
+        let env = if snapshot.exists() {
+            let mut env = Env::from_snapshot_file(snapshot);
+            // the ledger is saved explicitly, never as a test snapshot
+            env.set_config(EnvTestConfig {
+                capture_snapshot_at_drop: false,
+            });
+            env
+        } else {
+            local_env()
+        };
+        env.mock_all_auths();
+        env.budget().reset_unlimited();
+
+        // every authorization is mocked, so the owner is only a placeholder
+        let owner = Address::from_str(&env, &placeholder_contract_id(0xfe));
+        let group = Address::from_str(&env, &contracts.group);
+        env.register_at(&group, SemaphoreGroupContract, (owner.clone(),));
+        let semaphore = Address::from_str(&env, &contracts.semaphore);
+        let verifier = Address::from_str(&env, &placeholder_contract_id(0xff));
+        env.register_at(&semaphore, Semaphore, (owner, verifier));
+
+        Self {
+            env,
+            snapshot: snapshot.to_path_buf(),
+        }
+    }
+
+    /// Run `call` and return its result, or the contract error it failed with
+    pub fn invoke(&self, contract_id: &str, call: &Call) -> std::result::Result<Val, String> {
+        let contract = Address::from_str(&self.env, contract_id);
+        match self.env.try_invoke_contract::<Val, soroban_sdk::Error>(
+            &contract,
+            &Symbol::new(&self.env, call.function),
+            call.args.clone(),
+        ) {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(_)) => Err("unexpected return value".into()),
+            Err(Ok(err)) => Err(contract_error(call.target, err)),
+            Err(Err(InvokeError::Contract(code))) => Err(format!("contract error #{code}")),
+            Err(Err(InvokeError::Abort)) => Err("contract aborted".into()),
+        }
+    }
+
+    /// Persist the ledger so the next run continues from it
+    pub fn save(&self) {
+        self.env.to_snapshot_file(&self.snapshot);
+    }
+}
+
+fn contract_error(target: Target, err: soroban_sdk::Error) -> String {
+    match target {
+        Target::Group => group::datatypes::Error::try_from(err)
+            .map(|err| format!("{err:?}"))
+            .unwrap_or_else(|_| format!("{err:?}")),
+        Target::Semaphore => semaphore::datatypes::Error::try_from(err)
+            .map(|err| format!("{err:?}"))
+            .unwrap_or_else(|_| format!("{err:?}")),
+    }
+}
@@ -0,0 +1,98 @@
+//! `circuit-input`: the Semaphore circuit's `input.json` for one member.
+//! Only groups hashed with Poseidon can be proven, see the `witness` crate.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use group::{
+    datatypes::{GroupInfo, Member},
+    proof::Proof,
+};
+use identity::Identity;
+use soroban_sdk::{vec, Env, IntoVal, TryFromVal};
+use witness::{CircuitInput, GroupRoot};
+
+use crate::call::{Call, Offline, Target};
+use crate::input::parse_bytes32;
+use crate::{HashArg, Result};
+
+#[derive(Args)]
+pub struct CircuitInputArgs {
+    #[arg(long)]
+    group_id: u32,
+    /// Identity as exported by Semaphore (base64 private key)
+    #[arg(long, env = "SEMAPHORE_IDENTITY")]
+    identity: String,
+    /// Message, hex encoded, at most 32 bytes
+    #[arg(long)]
+    message: String,
+    /// Scope, hex encoded, at most 32 bytes
+    #[arg(long)]
+    scope: String,
+    /// Depth the circuit was compiled for, defaults to the proof length
+    #[arg(long)]
+    depth: Option<u32>,
+    /// Merkle proof as printed by `proof`, read from the group with --offline
+    #[arg(long, requires = "root")]
+    pub proof: Option<PathBuf>,
+    /// Group root in hex, read from the group with --offline
+    #[arg(long, requires = "proof")]
+    pub root: Option<String>,
+    /// Hash function of the group tree, read from the group with --offline
+    #[arg(long, value_enum, default_value_t = HashArg::Poseidon)]
+    pub hash: HashArg,
+}
+
+impl CircuitInputArgs {
+    pub fn identity(&self) -> Result<Identity> {
+        Ok(Identity::import(&self.identity)?)
+    }
+
+    /// Proof for the identity's leaf, the current root and the group's hash
+    /// function, read from the group contract of an offline ledger
+    pub fn fetch(&self, offline: &Offline, group_contract: &str) -> Result<(Proof, GroupRoot)> {
+        let env = &offline.env;
+        let identity = self.identity()?;
+        let commitment = identity.commitment_bytes(env);
+        let call = |function, args| Call {
+            target: Target::Group,
+            function,
+            args,
+        };
+        let group_id = self.group_id.into_val(env);
+
+        let member = offline.invoke(
+            group_contract,
+            &call("get_member", vec![env, group_id, commitment.into_val(env)]),
+        )?;
+        let member = Member::try_from_val(env, &member).map_err(|err| format!("{err:?}"))?;
+        let proof = offline.invoke(
+            group_contract,
+            &call("get_proof", vec![env, group_id, member.index.into_val(env)]),
+        )?;
+        let group = offline.invoke(group_contract, &call("get_group", vec![env, group_id]))?;
+        let group = GroupInfo::try_from_val(env, &group).map_err(|err| format!("{err:?}"))?;
+
+        Ok((
+            Proof::try_from_val(env, &proof).map_err(|err| format!("{err:?}"))?,
+            GroupRoot {
+                root: group.root,
+                hash: group.hash,
+            },
+        ))
+    }
+
+    /// `input.json` for `proof`, checked against the group's root
+    pub fn build(&self, env: &Env, proof: &Proof, group: &GroupRoot) -> Result<String> {
+        let input = CircuitInput::new(
+            env,
+            &self.identity()?,
+            proof,
+            group,
+            &parse_bytes32(&self.message)?,
+            &parse_bytes32(&self.scope)?,
+            self.depth.unwrap_or(proof.0.len()),
+        )?;
+        Ok(input.to_json())
+    }
+}
@@ -0,0 +1,39 @@
+//! `import-proof` and `import-vk`: snarkjs JSON to the words the contracts take.
+
+use std::path::Path;
+
+use converter::encoding::{
+    compress_proof, compress_verifying_key, encode_proof, encode_verifying_key, Word,
+};
+use converter::snarkjs::{ProofJson, VerificationKeyJson};
+
+use crate::Result;
+
+/// Points of a snarkjs `proof.json`, as the hex words of `points` in a proof
+/// file
+pub fn import_proof(path: &Path, compressed: bool) -> Result<String> {
+    let proof = ProofJson::parse(&std::fs::read_to_string(path)?)?.to_proof()?;
+    let words = if compressed {
+        compress_proof(&proof).to_vec()
+    } else {
+        encode_proof(&proof).to_vec()
+    };
+    render(&words)
+}
+
+/// Points of a snarkjs `verification_key.json`, in the order
+/// `semaphore_key_pts` stores them
+pub fn import_vk(path: &Path, compressed: bool) -> Result<String> {
+    let vk = VerificationKeyJson::parse(&std::fs::read_to_string(path)?)?.to_verifying_key()?;
+    let words = if compressed {
+        compress_verifying_key(&vk)
+    } else {
+        encode_verifying_key(&vk)
+    };
+    render(&words)
+}
+
+fn render(words: &[Word]) -> Result<String> {
+    let words: Vec<String> = words.iter().map(hex::encode).collect();
+    Ok(serde_json::to_string_pretty(&words)?)
+}
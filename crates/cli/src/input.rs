@@ -0,0 +1,124 @@
+//! Parsing of member lists and proof files.
+
+use std::path::Path;
+
+use group::proof::{Branch, Proof};
+use semaphore::SemaphoreProof;
+use serde::Deserialize;
+use soroban_sdk::{Bytes, BytesN, Env, Vec};
+
+use crate::Result;
+
+/// Decode a hex string, with or without a `0x` prefix
+pub fn parse_hex(value: &str) -> Result<std::vec::Vec<u8>> {
+    let value = value.trim();
+    let value = value.strip_prefix("0x").unwrap_or(value);
+    hex::decode(value).map_err(|err| format!("invalid hex {value:?}: {err}").into())
+}
+
+/// Identity commitments from a JSON array of hex strings, or from a CSV file
+/// whose first column holds the commitments (a non-hex header line is skipped)
+pub fn parse_members(contents: &str) -> Result<std::vec::Vec<std::vec::Vec<u8>>> {
+    if contents.trim_start().starts_with('[') {
+        let members: std::vec::Vec<String> = serde_json::from_str(contents)?;
+        return members.iter().map(|member| parse_hex(member)).collect();
+    }
+
+    let mut members = std::vec::Vec::new();
+    for (line_number, line) in contents.lines().enumerate() {
+        let column = line.split(',').next().unwrap_or_default().trim();
+        if column.is_empty() {
+            continue;
+        }
+        match parse_hex(column) {
+            Ok(member) => members.push(member),
+            Err(_) if line_number == 0 => continue,
+            Err(err) => return Err(format!("line {}: {err}", line_number + 1).into()),
+        }
+    }
+    Ok(members)
+}
+
+pub fn read_members(path: &Path) -> Result<std::vec::Vec<std::vec::Vec<u8>>> {
+    let members = parse_members(&std::fs::read_to_string(path)?)?;
+    if members.is_empty() {
+        return Err(format!("no members in {}", path.display()).into());
+    }
+    Ok(members)
+}
+
+/// Value of at most 32 bytes in hex, left padded with zeros
+pub fn parse_bytes32(value: &str) -> Result<[u8; 32]> {
+    let bytes = parse_hex(value)?;
+    if bytes.len() > 32 {
+        return Err(format!("{value:?} is longer than 32 bytes").into());
+    }
+    let mut padded = [0u8; 32];
+    padded[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(padded)
+}
+
+/// Merkle proof in the layout the `proof` command prints
+pub fn read_merkle_proof(env: &Env, path: &Path) -> Result<Proof> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum BranchFile {
+        Left(String),
+        Right(String),
+    }
+
+    let branches: std::vec::Vec<BranchFile> =
+        serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let mut proof = Vec::new(env);
+    for branch in branches {
+        proof.push_back(match branch {
+            BranchFile::Left(sibling) => {
+                Branch::Left(Bytes::from_slice(env, &parse_hex(&sibling)?))
+            }
+            BranchFile::Right(sibling) => {
+                Branch::Right(Bytes::from_slice(env, &parse_hex(&sibling)?))
+            }
+        });
+    }
+    Ok(Proof(proof))
+}
+
+/// `proof.json` layout, every 32 byte value hex encoded
+#[derive(Deserialize)]
+pub struct ProofFile {
+    pub merkle_tree_depth: u32,
+    pub merkle_tree_root: String,
+    pub nullifier: String,
+    pub message: String,
+    pub scope: String,
+    pub points: [String; 8],
+}
+
+impl ProofFile {
+    pub fn read(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn to_proof(&self, env: &Env) -> Result<SemaphoreProof> {
+        let mut points = Vec::new(env);
+        for point in &self.points {
+            points.push_back(bytes32(env, point)?);
+        }
+        Ok(SemaphoreProof {
+            merkle_tree_depth: self.merkle_tree_depth,
+            merkle_tree_root: bytes32(env, &self.merkle_tree_root)?,
+            nullifier: bytes32(env, &self.nullifier)?,
+            message: bytes32(env, &self.message)?,
+            scope: bytes32(env, &self.scope)?,
+            points,
+        })
+    }
+}
+
+fn bytes32(env: &Env, value: &str) -> Result<BytesN<32>> {
+    let bytes = parse_hex(value)?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|bytes: std::vec::Vec<u8>| format!("expected 32 bytes, got {}", bytes.len()))?;
+    Ok(BytesN::from_array(env, &bytes))
+}
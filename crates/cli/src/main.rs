@@ -0,0 +1,362 @@
+//! Command line client for the `group` and `semaphore` contracts.
+//!
+//! Every subcommand is turned into one or more contract calls. By default each
+//! call is printed, one per line, as an unsigned `InvokeHostFunction`
+//! operation in base64 XDR, ready to be wrapped in a transaction, simulated
+//! and signed. With `--offline` the calls run against a local ledger snapshot
+//! instead, which is updated after every successful command so a sequence of
+//! commands can be dry run.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use group::datatypes::{GroupMetadata, HashFunction, TreeKind};
+use group::proof::{Branch, Proof};
+use serde_json::json;
+use soroban_sdk::{vec, Address, Bytes, Env, IntoVal, TryFromVal, Val, Vec};
+use witness::GroupRoot;
+
+use call::{local_env, operation_xdr, placeholder_contract_id, Call, Contracts, Offline, Target};
+use circuit_input::CircuitInputArgs;
+use import::{import_proof, import_vk};
+use input::{parse_hex, read_members, read_merkle_proof, ProofFile};
+
+mod call;
+mod circuit_input;
+mod import;
+mod input;
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[command(name = "semaphore-stellar", version, about)]
+struct Cli {
+    /// Id of the group contract
+    #[arg(long, env = "SEMAPHORE_GROUP_CONTRACT", global = true)]
+    group_contract: Option<String>,
+
+    /// Id of the semaphore contract
+    #[arg(long, env = "SEMAPHORE_CONTRACT", global = true)]
+    semaphore_contract: Option<String>,
+
+    /// Simulate against a local ledger snapshot instead of printing XDR
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Ledger snapshot used by `--offline`, created on first use
+    #[arg(long, default_value = "semaphore-stellar.snapshot.json", global = true)]
+    snapshot: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// `TreeKind` as a command line value
+#[derive(Clone, Copy, ValueEnum)]
+enum TreeArg {
+    /// Members in insertion order
+    Incremental,
+    /// Can also prove that a commitment is not a member
+    Indexed,
+    /// Members addressed by commitment, removals leave no holes
+    Sparse,
+}
+
+/// `HashFunction` as a command line value
+#[derive(Clone, Copy, ValueEnum)]
+enum HashArg {
+    Keccak256,
+    Sha256,
+    Poseidon,
+}
+
+impl From<HashArg> for HashFunction {
+    fn from(hash: HashArg) -> Self {
+        match hash {
+            HashArg::Keccak256 => Self::Keccak256,
+            HashArg::Sha256 => Self::Sha256,
+            HashArg::Poseidon => Self::Poseidon,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a group administered by `admin`; the contract assigns its id
+    CreateGroup {
+        /// Account or contract strkey
+        #[arg(long)]
+        admin: String,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        description_uri: Option<String>,
+        /// Tree depth, the contract's default when omitted
+        #[arg(long)]
+        depth: Option<u32>,
+        /// Kind of group tree
+        #[arg(long, value_enum, default_value_t = TreeArg::Incremental)]
+        tree: TreeArg,
+        /// Hash function of the group tree
+        #[arg(long, value_enum, default_value_t = HashArg::Keccak256)]
+        hash: HashArg,
+    },
+    /// Add the identity commitments listed in a CSV or JSON file
+    AddMembers {
+        #[arg(long)]
+        group_id: u32,
+        file: PathBuf,
+    },
+    /// Remove a member by identity commitment (hex)
+    RemoveMember {
+        #[arg(long)]
+        group_id: u32,
+        identity_commitment: String,
+    },
+    /// Current Merkle root of a group
+    Root {
+        #[arg(long)]
+        group_id: u32,
+    },
+    /// Merkle proof for the leaf at `leaf`
+    Proof {
+        #[arg(long)]
+        group_id: u32,
+        leaf: u32,
+    },
+    /// Validate a Semaphore proof read from a JSON file
+    ValidateProof {
+        #[arg(long)]
+        group_id: u32,
+        proof: PathBuf,
+    },
+    /// Print the Semaphore circuit's input.json for a member of a group. The
+    /// circuit hashes with Poseidon, so proofs from it do not match the group
+    /// root yet
+    CircuitInput(CircuitInputArgs),
+    /// Convert a snarkjs proof.json to the points of a proof file
+    ImportProof {
+        file: PathBuf,
+        /// Compressed points, four words instead of eight
+        #[arg(long)]
+        compressed: bool,
+    },
+    /// Convert a snarkjs verification_key.json to verification key points
+    ImportVk {
+        file: PathBuf,
+        /// Compressed points, one word per G1 and two per G2 point
+        #[arg(long)]
+        compressed: bool,
+    },
+}
+
+impl Command {
+    /// Contract calls for this command, in the order they are sent
+    fn calls(&self, env: &Env) -> Result<std::vec::Vec<Call>> {
+        let (target, function, args): (_, _, Vec<Val>) = match self {
+            Command::CreateGroup {
+                admin,
+                name,
+                description_uri,
+                depth,
+                tree,
+                hash,
+            } => {
+                let string = |value: &Option<String>| {
+                    value
+                        .as_ref()
+                        .map(|value| soroban_sdk::String::from_str(env, value))
+                };
+                let metadata = GroupMetadata {
+                    name: string(name),
+                    description_uri: string(description_uri),
+                    depth: *depth,
+                    tree: match tree {
+                        TreeArg::Incremental => TreeKind::Incremental,
+                        TreeArg::Indexed => TreeKind::Indexed,
+                        TreeArg::Sparse => TreeKind::Sparse,
+                    },
+                    hash: (*hash).into(),
+                };
+                let unset = metadata == GroupMetadata::default();
+                (
+                    Target::Group,
+                    "create_group",
+                    vec![
+                        env,
+                        Address::from_str(env, admin).into_val(env),
+                        (!unset).then_some(metadata).into_val(env),
+                    ],
+                )
+            }
+            Command::AddMembers { group_id, file } => {
+                let mut members = Vec::new(env);
+                for member in read_members(file)? {
+                    members.push_back(Bytes::from_slice(env, &member));
+                }
+                (
+                    Target::Group,
+                    "add_members",
+                    vec![env, group_id.into_val(env), members.into_val(env)],
+                )
+            }
+            Command::RemoveMember {
+                group_id,
+                identity_commitment,
+            } => (
+                Target::Group,
+                "remove_member",
+                vec![
+                    env,
+                    group_id.into_val(env),
+                    Bytes::from_slice(env, &parse_hex(identity_commitment)?).into_val(env),
+                ],
+            ),
+            Command::Root { group_id } => (
+                Target::Group,
+                "get_merkle_root",
+                vec![env, group_id.into_val(env)],
+            ),
+            Command::Proof { group_id, leaf } => (
+                Target::Group,
+                "get_proof",
+                vec![env, group_id.into_val(env), leaf.into_val(env)],
+            ),
+            Command::ValidateProof { group_id, proof } => (
+                Target::Semaphore,
+                "validate_proof",
+                vec![
+                    env,
+                    group_id.into_val(env),
+                    ProofFile::read(proof)?.to_proof(env)?.into_val(env),
+                ],
+            ),
+            Command::CircuitInput(_) | Command::ImportProof { .. } | Command::ImportVk { .. } => {
+                unreachable!("runs locally, without contract calls")
+            }
+        };
+        Ok(std::vec![Call {
+            target,
+            function,
+            args,
+        }])
+    }
+
+    /// Render the value returned by an offline call
+    fn output(&self, env: &Env, value: Val) -> Result<String> {
+        Ok(match self {
+            Command::CreateGroup { .. } => {
+                let group_id = u32::try_from_val(env, &value).map_err(|err| format!("{err:?}"))?;
+                group_id.to_string()
+            }
+            Command::Root { .. } => {
+                let root = Bytes::try_from_val(env, &value).map_err(|err| format!("{err:?}"))?;
+                hex::encode(to_vec(&root))
+            }
+            Command::Proof { .. } => {
+                let proof = Proof::try_from_val(env, &value).map_err(|err| format!("{err:?}"))?;
+                let branches: std::vec::Vec<_> = proof
+                    .0
+                    .iter()
+                    .map(|branch| match branch {
+                        Branch::Left(sibling) => json!({ "left": hex::encode(to_vec(&sibling)) }),
+                        Branch::Right(sibling) => json!({ "right": hex::encode(to_vec(&sibling)) }),
+                    })
+                    .collect();
+                serde_json::to_string_pretty(&branches)?
+            }
+            _ => "ok".into(),
+        })
+    }
+}
+
+fn to_vec(bytes: &Bytes) -> std::vec::Vec<u8> {
+    bytes.iter().collect()
+}
+
+/// Contract id from the command line; offline runs fall back to a fixed id
+fn contract_id(cli: &Cli, target: Target) -> Result<String> {
+    let (id, flag, placeholder) = match target {
+        Target::Group => (&cli.group_contract, "group-contract", 1),
+        Target::Semaphore => (&cli.semaphore_contract, "semaphore-contract", 2),
+    };
+    match id {
+        Some(id) => Ok(id.clone()),
+        None if cli.offline => Ok(placeholder_contract_id(placeholder)),
+        None => Err(format!("missing --{flag}").into()),
+    }
+}
+
+/// Offline ledger with both contracts registered
+fn offline(cli: &Cli) -> Result<Offline> {
+    let contracts = Contracts {
+        group: contract_id(cli, Target::Group)?,
+        semaphore: contract_id(cli, Target::Semaphore)?,
+    };
+    Ok(Offline::load(&cli.snapshot, &contracts))
+}
+
+fn circuit_input(cli: &Cli, args: &CircuitInputArgs) -> Result<String> {
+    match (&args.proof, &args.root) {
+        (Some(proof), Some(root)) => {
+            let env = local_env();
+            let proof = read_merkle_proof(&env, proof)?;
+            let group = GroupRoot {
+                root: Bytes::from_slice(&env, &parse_hex(root)?),
+                hash: args.hash.into(),
+            };
+            args.build(&env, &proof, &group)
+        }
+        _ if cli.offline => {
+            let offline = offline(cli)?;
+            let (proof, group) = args.fetch(&offline, &contract_id(cli, Target::Group)?)?;
+            args.build(&offline.env, &proof, &group)
+        }
+        _ => Err("circuit-input needs --proof and --root, or --offline".into()),
+    }
+}
+
+fn run(cli: &Cli) -> Result<String> {
+    match &cli.command {
+        Command::CircuitInput(args) => return circuit_input(cli, args),
+        Command::ImportProof { file, compressed } => return import_proof(file, *compressed),
+        Command::ImportVk { file, compressed } => return import_vk(file, *compressed),
+        _ => {}
+    }
+
+    if !cli.offline {
+        let env = local_env();
+        let operations = cli
+            .command
+            .calls(&env)?
+            .iter()
+            .map(|call| operation_xdr(&env, &contract_id(cli, call.target)?, call))
+            .collect::<Result<std::vec::Vec<_>>>()?;
+        return Ok(operations.join("\n"));
+    }
+
+    let offline = offline(cli)?;
+    // the snapshot is only saved once every call succeeded
+    let mut value = Val::VOID.to_val();
+    for call in cli.command.calls(&offline.env)? {
+        value = offline.invoke(&contract_id(cli, call.target)?, &call)?;
+    }
+    offline.save();
+    cli.command.output(&offline.env, value)
+}
+
+fn main() -> ExitCode {
+    match run(&Cli::parse()) {
+        Ok(output) => {
+            println!("{output}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+mod test;
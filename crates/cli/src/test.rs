@@ -0,0 +1,271 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::xdr::{HostFunction, Limits, Operation, OperationBody, ReadXdr, ScAddress, ScVal};
+
+const ADMIN: &str = "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF";
+
+fn cli(args: &[&str]) -> Cli {
+    Cli::try_parse_from(std::iter::once("semaphore-stellar").chain(args.iter().copied())).unwrap()
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("semaphore-stellar-{name}"));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_parse_members() {
+    let csv = "identity_commitment,label\n0x0102,alice\n\n0304,bob\n";
+    assert_eq!(
+        input::parse_members(csv).unwrap(),
+        [std::vec![1, 2], std::vec![3, 4]]
+    );
+
+    let json = r#"["0x0102", "0304"]"#;
+    assert_eq!(
+        input::parse_members(json).unwrap(),
+        [std::vec![1, 2], std::vec![3, 4]]
+    );
+
+    assert!(input::parse_members("0102\nzz\n").is_err());
+}
+
+#[test]
+fn test_operation_xdr() {
+    let group_contract = placeholder_contract_id(7);
+    let output = run(&cli(&[
+        "--group-contract",
+        &group_contract,
+        "create-group",
+        "--admin",
+        ADMIN,
+        "--depth",
+        "3",
+    ]))
+    .unwrap();
+
+    let operation = Operation::from_xdr_base64(output, Limits::none()).unwrap();
+    let OperationBody::InvokeHostFunction(op) = operation.body else {
+        panic!("not an invoke host function operation");
+    };
+    let HostFunction::InvokeContract(invoke) = op.host_function else {
+        panic!("not a contract invocation");
+    };
+    assert_eq!(
+        invoke.contract_address,
+        ScAddress::Contract(soroban_sdk::xdr::Hash([7; 32]))
+    );
+    assert_eq!(invoke.function_name.0.to_string(), "create_group");
+    assert_eq!(invoke.args.len(), 2);
+    let ScVal::Map(Some(metadata)) = &invoke.args[1] else {
+        panic!("metadata is not a map");
+    };
+    assert!(metadata.iter().any(
+        |entry| entry.key == ScVal::Symbol("depth".try_into().unwrap())
+            && entry.val == ScVal::U32(3)
+    ));
+}
+
+#[test]
+fn test_missing_contract_id() {
+    assert!(run(&cli(&["root", "--group-id", "0"])).is_err());
+}
+
+#[test]
+fn test_offline_session() {
+    let snapshot = temp_path("session.json");
+    let members = temp_path("members.csv");
+    std::fs::write(&members, "commitment\n0101\n0202\n").unwrap();
+    let snapshot_arg = snapshot.to_str().unwrap();
+    let offline = |args: &[&str]| {
+        let mut all = std::vec!["--offline", "--snapshot", snapshot_arg];
+        all.extend_from_slice(args);
+        run(&cli(&all))
+    };
+
+    let empty_root = offline(&["root", "--group-id", "0"]);
+    assert!(empty_root.is_err());
+
+    assert_eq!(offline(&["create-group", "--admin", ADMIN]).unwrap(), "0");
+    let empty_root = offline(&["root", "--group-id", "0"]).unwrap();
+    offline(&["add-members", "--group-id", "0", members.to_str().unwrap()]).unwrap();
+
+    // the snapshot keeps the members between runs
+    let root = offline(&["root", "--group-id", "0"]).unwrap();
+    assert_ne!(root, empty_root);
+    let proof: serde_json::Value =
+        serde_json::from_str(&offline(&["proof", "--group-id", "0", "1"]).unwrap()).unwrap();
+    assert_eq!(
+        proof.as_array().unwrap().len(),
+        group::DEFAULT_DEPTH as usize
+    );
+
+    offline(&["remove-member", "--group-id", "0", "0x0202"]).unwrap();
+    assert_ne!(offline(&["root", "--group-id", "0"]).unwrap(), root);
+
+    // contract errors are reported by name and leave the snapshot untouched
+    let err = offline(&["remove-member", "--group-id", "0", "0202"]).unwrap_err();
+    assert_eq!(err.to_string(), "MemberDoesNotExist");
+}
+
+#[test]
+fn test_validate_proof_file() {
+    let proof = temp_path("proof.json");
+    let hex32 = |byte: u8| hex::encode([byte; 32]);
+    std::fs::write(
+        &proof,
+        serde_json::json!({
+            "merkle_tree_depth": 16,
+            "merkle_tree_root": hex32(1),
+            "nullifier": hex32(2),
+            "message": hex32(3),
+            "scope": hex32(4),
+            "points": (0..8).map(hex32).collect::<std::vec::Vec<_>>(),
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let snapshot = temp_path("proof-session.json");
+
+    // the call reaches the semaphore contract, which has no such group
+    let err = run(&cli(&[
+        "--offline",
+        "--snapshot",
+        snapshot.to_str().unwrap(),
+        "validate-proof",
+        "--group-id",
+        "0",
+        proof.to_str().unwrap(),
+    ]))
+    .unwrap_err();
+    assert_eq!(err.to_string(), "GroupDoesNotExist");
+    assert!(!snapshot.exists());
+}
+
+#[test]
+fn test_circuit_input() {
+    let snapshot = temp_path("circuit-input.json");
+    let members = temp_path("circuit-input-members.json");
+    let identities: std::vec::Vec<_> = (0..3u8)
+        .map(|seed| identity::Identity::new(&[seed]))
+        .collect();
+    let commitments: std::vec::Vec<_> = identities
+        .iter()
+        .map(|identity| hex::encode(identity::field_to_bytes(&identity.commitment())))
+        .collect();
+    std::fs::write(&members, serde_json::to_string(&commitments).unwrap()).unwrap();
+
+    let snapshot_arg = snapshot.to_str().unwrap();
+    let offline = |args: &[&str]| {
+        let mut all = std::vec!["--offline", "--snapshot", snapshot_arg];
+        all.extend_from_slice(args);
+        run(&cli(&all))
+    };
+    offline(&["create-group", "--admin", ADMIN, "--hash", "poseidon"]).unwrap();
+    offline(&["add-members", "--group-id", "0", members.to_str().unwrap()]).unwrap();
+
+    let exported = identities[1].export();
+    let circuit_input = [
+        "circuit-input",
+        "--group-id",
+        "0",
+        "--identity",
+        &exported,
+        "--message",
+        "0x01",
+        "--scope",
+        "0x02",
+        "--depth",
+        "16",
+    ];
+    let input: witness::CircuitInput =
+        serde_json::from_str(&offline(&circuit_input).unwrap()).unwrap();
+    assert_eq!(input.merkle_proof_index, "1");
+    assert_eq!(input.merkle_proof_siblings.len(), 16);
+    assert_eq!(input.secret, identities[1].secret_scalar().to_string());
+
+    // the same input from the output of `proof` and `root`
+    let proof = temp_path("circuit-input-proof.json");
+    std::fs::write(&proof, offline(&["proof", "--group-id", "0", "1"]).unwrap()).unwrap();
+    let root = offline(&["root", "--group-id", "0"]).unwrap();
+    let mut args = circuit_input.to_vec();
+    args.extend_from_slice(&["--proof", proof.to_str().unwrap(), "--root", &root]);
+    assert_eq!(
+        serde_json::from_str::<witness::CircuitInput>(&run(&cli(&args)).unwrap()).unwrap(),
+        input
+    );
+
+    // a stale root is rejected
+    let mut args = circuit_input.to_vec();
+    let stale_root = hex::encode([0u8; 32]);
+    args.extend_from_slice(&["--proof", proof.to_str().unwrap(), "--root", &stale_root]);
+    assert!(run(&cli(&args))
+        .unwrap_err()
+        .to_string()
+        .starts_with("root mismatch"));
+
+    // an identity outside the group has no leaf
+    let outsider = identity::Identity::new(b"outsider").export();
+    let mut args = circuit_input.to_vec();
+    args[4] = &outsider;
+    assert_eq!(
+        offline(&args).unwrap_err().to_string(),
+        "MemberDoesNotExist"
+    );
+
+    // a keccak256 group cannot be proven by the circuit, offline or given --hash
+    offline(&["create-group", "--admin", ADMIN]).unwrap();
+    offline(&["add-members", "--group-id", "1", members.to_str().unwrap()]).unwrap();
+    let mut args = circuit_input.to_vec();
+    args[2] = "1";
+    let unsupported = "group tree is hashed with Keccak256, the circuit needs Poseidon";
+    assert_eq!(offline(&args).unwrap_err().to_string(), unsupported);
+    std::fs::write(&proof, offline(&["proof", "--group-id", "1", "1"]).unwrap()).unwrap();
+    let root = offline(&["root", "--group-id", "1"]).unwrap();
+    args.extend_from_slice(&["--proof", proof.to_str().unwrap(), "--root", &root]);
+    args.extend_from_slice(&["--hash", "keccak256"]);
+    assert_eq!(run(&cli(&args)).unwrap_err().to_string(), unsupported);
+}
+
+#[test]
+fn test_import() {
+    let vectors = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../converter/test-vectors");
+    let proof_json = vectors.join("proof.json");
+    let vk_json = vectors.join("verification_key.json");
+    let proof_json = proof_json.to_str().unwrap();
+    let vk_json = vk_json.to_str().unwrap();
+    let words = |args: &[&str]| -> std::vec::Vec<String> {
+        serde_json::from_str(&run(&cli(args)).unwrap()).unwrap()
+    };
+
+    // the uncompressed words are the points of a proof file
+    let points = words(&["import-proof", proof_json]);
+    assert_eq!(points.len(), 8);
+    let proof_file = ProofFile {
+        merkle_tree_depth: 10,
+        merkle_tree_root: format!("{:064x}", 1),
+        nullifier: format!("{:064x}", 2),
+        message: format!("{:064x}", 3),
+        scope: format!("{:064x}", 4),
+        points: points.clone().try_into().unwrap(),
+    };
+    let env = local_env();
+    let proof = proof_file.to_proof(&env).unwrap();
+    assert_eq!(
+        proof.points.get(2).unwrap().to_array().to_vec(),
+        parse_hex(&points[2]).unwrap()
+    );
+
+    assert_eq!(
+        words(&["import-proof", "--compressed", proof_json]).len(),
+        4
+    );
+    // one public signal: 14 words of fixed points and two `ic` points
+    assert_eq!(words(&["import-vk", vk_json]).len(), 18);
+    assert_eq!(words(&["import-vk", "--compressed", vk_json]).len(), 9);
+
+    let err = run(&cli(&["import-vk", proof_json])).unwrap_err();
+    assert!(err.to_string().starts_with("invalid json"));
+}
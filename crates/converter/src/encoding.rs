@@ -0,0 +1,249 @@
+//! Byte layout of BN254 points as the contracts take them.
+//!
+//! Every base field element is 32 bytes, big-endian. A G1 point is `x || y`.
+//! A G2 point is `x.c1 || x.c0 || y.c1 || y.c0`, imaginary part first, as in
+//! EIP-197 and Semaphore's packed proofs. A Groth16 proof is the eight
+//! words `a.x, a.y, b.x.c1, b.x.c0, b.y.c1, b.y.c0, c.x, c.y`, the layout
+//! of `SemaphoreProof::points`. The point at infinity is all zeros.
+//!
+//! The compressed layout is gnark's: only `x` is kept, in the same order, and
+//! the two top bits of the first byte say whether `y` is the lexicographically
+//! largest of its two candidates (`0b11`), the smallest (`0b10`) or the point
+//! is at infinity (`0b01`). A G1 point takes one word, a G2 point two.
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::{
+    short_weierstrass::{Affine, SWCurveConfig},
+    AffineRepr,
+};
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{Proof, VerifyingKey};
+
+use crate::Error;
+
+pub type Word = [u8; 32];
+
+const FLAG_MASK: u8 = 0b11 << 6;
+const COMPRESSED_SMALLEST: u8 = 0b10 << 6;
+const COMPRESSED_LARGEST: u8 = 0b11 << 6;
+const COMPRESSED_INFINITY: u8 = 0b01 << 6;
+
+pub fn encode_fq(value: &Fq) -> Word {
+    value.into_bigint().to_bytes_be().try_into().unwrap()
+}
+
+pub fn encode_fr(value: &Fr) -> Word {
+    value.into_bigint().to_bytes_be().try_into().unwrap()
+}
+
+/// Reject values that are not canonical, so an encoding has one meaning
+pub fn decode_fq(word: &Word) -> Result<Fq, Error> {
+    let value = Fq::from_be_bytes_mod_order(word);
+    if encode_fq(&value) != *word {
+        return Err(Error::InvalidEncoding("field element is not reduced"));
+    }
+    Ok(value)
+}
+
+pub fn encode_g1(point: &G1Affine) -> [Word; 2] {
+    if point.is_zero() {
+        return [[0; 32]; 2];
+    }
+    [encode_fq(&point.x), encode_fq(&point.y)]
+}
+
+pub fn decode_g1(words: &[Word; 2]) -> Result<G1Affine, Error> {
+    if words.iter().all(|word| *word == [0; 32]) {
+        return Ok(G1Affine::zero());
+    }
+    let point = G1Affine::new_unchecked(decode_fq(&words[0])?, decode_fq(&words[1])?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(Error::InvalidEncoding("G1 point is not on the curve"));
+    }
+    Ok(point)
+}
+
+pub fn encode_g2(point: &G2Affine) -> [Word; 4] {
+    if point.is_zero() {
+        return [[0; 32]; 4];
+    }
+    [
+        encode_fq(&point.x.c1),
+        encode_fq(&point.x.c0),
+        encode_fq(&point.y.c1),
+        encode_fq(&point.y.c0),
+    ]
+}
+
+pub fn decode_g2(words: &[Word; 4]) -> Result<G2Affine, Error> {
+    if words.iter().all(|word| *word == [0; 32]) {
+        return Ok(G2Affine::zero());
+    }
+    let x = Fq2::new(decode_fq(&words[1])?, decode_fq(&words[0])?);
+    let y = Fq2::new(decode_fq(&words[3])?, decode_fq(&words[2])?);
+    let point = G2Affine::new_unchecked(x, y);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(Error::InvalidEncoding("G2 point is not on the curve"));
+    }
+    Ok(point)
+}
+
+pub fn encode_proof(proof: &Proof<Bn254>) -> [Word; 8] {
+    let [ax, ay] = encode_g1(&proof.a);
+    let [bx1, bx0, by1, by0] = encode_g2(&proof.b);
+    let [cx, cy] = encode_g1(&proof.c);
+    [ax, ay, bx1, bx0, by1, by0, cx, cy]
+}
+
+pub fn decode_proof(words: &[Word; 8]) -> Result<Proof<Bn254>, Error> {
+    Ok(Proof {
+        a: decode_g1(&[words[0], words[1]])?,
+        b: decode_g2(&[words[2], words[3], words[4], words[5]])?,
+        c: decode_g1(&[words[6], words[7]])?,
+    })
+}
+
+/// `alpha, beta, gamma, delta` followed by the `ic` points, one per public
+/// signal plus one
+pub fn encode_verifying_key(vk: &VerifyingKey<Bn254>) -> Vec<Word> {
+    let mut words = encode_g1(&vk.alpha_g1).to_vec();
+    words.extend(encode_g2(&vk.beta_g2));
+    words.extend(encode_g2(&vk.gamma_g2));
+    words.extend(encode_g2(&vk.delta_g2));
+    for point in &vk.gamma_abc_g1 {
+        words.extend(encode_g1(point));
+    }
+    words
+}
+
+pub fn decode_verifying_key(words: &[Word]) -> Result<VerifyingKey<Bn254>, Error> {
+    // at least one `ic` point, and whole G1 points after the fixed part
+    if words.len() < 16 || !words.len().is_multiple_of(2) {
+        return Err(Error::InvalidEncoding("verifying key has the wrong length"));
+    }
+    let g2 = |at: usize| decode_g2(&words[at..at + 4].try_into().unwrap());
+    Ok(VerifyingKey {
+        alpha_g1: decode_g1(&[words[0], words[1]])?,
+        beta_g2: g2(2)?,
+        gamma_g2: g2(6)?,
+        delta_g2: g2(10)?,
+        gamma_abc_g1: words[14..]
+            .chunks(2)
+            .map(|point| decode_g1(&[point[0], point[1]]))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Flag for a point whose `y` coordinate is `y`
+fn flag<F: PartialOrd + core::ops::Neg<Output = F> + Copy>(y: F) -> u8 {
+    if y > -y {
+        COMPRESSED_LARGEST
+    } else {
+        COMPRESSED_SMALLEST
+    }
+}
+
+/// Point from its `x` coordinate and compression flag
+fn decompress<P: SWCurveConfig>(x: P::BaseField, flag: u8) -> Result<Affine<P>, Error> {
+    let point = Affine::<P>::get_point_from_x_unchecked(x, flag == COMPRESSED_LARGEST).ok_or(
+        Error::InvalidEncoding("x is not the coordinate of a curve point"),
+    )?;
+    if !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(Error::InvalidEncoding("point is not in the subgroup"));
+    }
+    Ok(point)
+}
+
+/// Split the flag off the first byte of a compressed point
+fn split_flag(word: &Word) -> Result<(u8, Word), Error> {
+    let mut word = *word;
+    let flag = word[0] & FLAG_MASK;
+    word[0] &= !FLAG_MASK;
+    match flag {
+        COMPRESSED_INFINITY if word != [0; 32] => {
+            Err(Error::InvalidEncoding("point at infinity has a coordinate"))
+        }
+        COMPRESSED_INFINITY | COMPRESSED_SMALLEST | COMPRESSED_LARGEST => Ok((flag, word)),
+        _ => Err(Error::InvalidEncoding("point is not compressed")),
+    }
+}
+
+pub fn compress_g1(point: &G1Affine) -> Word {
+    if point.is_zero() {
+        let mut word = [0; 32];
+        word[0] = COMPRESSED_INFINITY;
+        return word;
+    }
+    let mut word = encode_fq(&point.x);
+    word[0] |= flag(point.y);
+    word
+}
+
+pub fn decompress_g1(word: &Word) -> Result<G1Affine, Error> {
+    match split_flag(word)? {
+        (COMPRESSED_INFINITY, _) => Ok(G1Affine::zero()),
+        (flag, x) => decompress(decode_fq(&x)?, flag),
+    }
+}
+
+pub fn compress_g2(point: &G2Affine) -> [Word; 2] {
+    if point.is_zero() {
+        let mut word = [0; 32];
+        word[0] = COMPRESSED_INFINITY;
+        return [word, [0; 32]];
+    }
+    let mut x1 = encode_fq(&point.x.c1);
+    x1[0] |= flag(point.y);
+    [x1, encode_fq(&point.x.c0)]
+}
+
+pub fn decompress_g2(words: &[Word; 2]) -> Result<G2Affine, Error> {
+    match split_flag(&words[0])? {
+        (COMPRESSED_INFINITY, _) if words[1] == [0; 32] => Ok(G2Affine::zero()),
+        (COMPRESSED_INFINITY, _) => {
+            Err(Error::InvalidEncoding("point at infinity has a coordinate"))
+        }
+        (flag, x1) => decompress(Fq2::new(decode_fq(&words[1])?, decode_fq(&x1)?), flag),
+    }
+}
+
+/// `a`, `b` and `c` compressed, four words
+pub fn compress_proof(proof: &Proof<Bn254>) -> [Word; 4] {
+    let [bx1, bx0] = compress_g2(&proof.b);
+    [compress_g1(&proof.a), bx1, bx0, compress_g1(&proof.c)]
+}
+
+pub fn decompress_proof(words: &[Word; 4]) -> Result<Proof<Bn254>, Error> {
+    Ok(Proof {
+        a: decompress_g1(&words[0])?,
+        b: decompress_g2(&[words[1], words[2]])?,
+        c: decompress_g1(&words[3])?,
+    })
+}
+
+/// Same order as [`encode_verifying_key`], every point compressed
+pub fn compress_verifying_key(vk: &VerifyingKey<Bn254>) -> Vec<Word> {
+    let mut words = vec![compress_g1(&vk.alpha_g1)];
+    words.extend(compress_g2(&vk.beta_g2));
+    words.extend(compress_g2(&vk.gamma_g2));
+    words.extend(compress_g2(&vk.delta_g2));
+    words.extend(vk.gamma_abc_g1.iter().map(compress_g1));
+    words
+}
+
+pub fn decompress_verifying_key(words: &[Word]) -> Result<VerifyingKey<Bn254>, Error> {
+    if words.len() < 8 {
+        return Err(Error::InvalidEncoding("verifying key has the wrong length"));
+    }
+    let g2 = |at: usize| decompress_g2(&[words[at], words[at + 1]]);
+    Ok(VerifyingKey {
+        alpha_g1: decompress_g1(&words[0])?,
+        beta_g2: g2(1)?,
+        gamma_g2: g2(3)?,
+        delta_g2: g2(5)?,
+        gamma_abc_g1: words[7..]
+            .iter()
+            .map(decompress_g1)
+            .collect::<Result<_, _>>()?,
+    })
+}
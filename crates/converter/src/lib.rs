@@ -0,0 +1,54 @@
+//! Groth16 proofs and verifying keys between snarkjs and the contracts.
+//!
+//! [`snarkjs`] reads and writes the JSON files snarkjs produces, [`encoding`]
+//! packs points into the 32 byte words `semaphore_key_pts` stores and
+//! `SemaphoreProof::points` carries, uncompressed or compressed. Every point
+//! is checked to be on the curve and in the prime order subgroup on the way
+//! in, so a key or proof with swapped G2 coordinates is rejected instead of
+//! being uploaded.
+
+use std::fmt;
+
+pub use encoding::{decode_proof, encode_proof};
+
+pub mod encoding;
+pub mod snarkjs;
+
+#[derive(Debug)]
+pub enum Error {
+    Json(serde_json::Error),
+    /// Not a decimal number, or not in the field
+    InvalidNumber(String),
+    /// Bytes or coordinates that are not a valid point or field element
+    InvalidEncoding(&'static str),
+    /// Proof system other than Groth16
+    UnsupportedProtocol(String),
+    /// Curve other than BN254
+    UnsupportedCurve(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Json(err) => write!(f, "invalid json: {err}"),
+            Error::InvalidNumber(value) => write!(f, "invalid field element: {value}"),
+            Error::InvalidEncoding(msg) => write!(f, "invalid encoding: {msg}"),
+            Error::UnsupportedProtocol(protocol) => {
+                write!(f, "unsupported protocol {protocol}, expected groth16")
+            }
+            Error::UnsupportedCurve(curve) => {
+                write!(f, "unsupported curve {curve}, expected bn128")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+mod test;
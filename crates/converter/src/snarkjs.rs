@@ -0,0 +1,193 @@
+//! snarkjs `proof.json`, `public.json` and `verification_key.json`.
+//!
+//! snarkjs writes points in Jacobian coordinates as decimal strings, `[x, y, z]`
+//! for G1 and `[[x.c0, x.c1], [y.c0, y.c1], [z.c0, z.c1]]` for G2, real part
+//! first. `z` is normally one, other values are normalised and `z = 0` is the
+//! point at infinity.
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{PrimeField, Zero};
+use ark_groth16::{Proof, VerifyingKey};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+type G1Json = Vec<String>;
+type G2Json = Vec<Vec<String>>;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProofJson {
+    pub pi_a: G1Json,
+    pub pi_b: G2Json,
+    pub pi_c: G1Json,
+    pub protocol: String,
+    pub curve: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VerificationKeyJson {
+    pub protocol: String,
+    pub curve: String,
+    #[serde(rename = "nPublic")]
+    pub n_public: usize,
+    pub vk_alpha_1: G1Json,
+    pub vk_beta_2: G2Json,
+    pub vk_gamma_2: G2Json,
+    pub vk_delta_2: G2Json,
+    /// Present in snarkjs output, not needed to verify
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vk_alphabeta_12: Option<serde_json::Value>,
+    #[serde(rename = "IC")]
+    pub ic: Vec<G1Json>,
+}
+
+impl ProofJson {
+    pub fn parse(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_proof(&self) -> Result<Proof<Bn254>, Error> {
+        check_scheme(&self.protocol, &self.curve)?;
+        Ok(Proof {
+            a: g1(&self.pi_a)?,
+            b: g2(&self.pi_b)?,
+            c: g1(&self.pi_c)?,
+        })
+    }
+
+    pub fn from_proof(proof: &Proof<Bn254>) -> Self {
+        Self {
+            pi_a: g1_json(&proof.a),
+            pi_b: g2_json(&proof.b),
+            pi_c: g1_json(&proof.c),
+            protocol: "groth16".into(),
+            curve: "bn128".into(),
+        }
+    }
+}
+
+impl VerificationKeyJson {
+    pub fn parse(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_verifying_key(&self) -> Result<VerifyingKey<Bn254>, Error> {
+        check_scheme(&self.protocol, &self.curve)?;
+        if self.ic.len() != self.n_public + 1 {
+            return Err(Error::InvalidEncoding(
+                "IC does not have nPublic + 1 points",
+            ));
+        }
+        Ok(VerifyingKey {
+            alpha_g1: g1(&self.vk_alpha_1)?,
+            beta_g2: g2(&self.vk_beta_2)?,
+            gamma_g2: g2(&self.vk_gamma_2)?,
+            delta_g2: g2(&self.vk_delta_2)?,
+            gamma_abc_g1: self
+                .ic
+                .iter()
+                .map(|point| g1(point))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn from_verifying_key(vk: &VerifyingKey<Bn254>) -> Self {
+        Self {
+            protocol: "groth16".into(),
+            curve: "bn128".into(),
+            n_public: vk.gamma_abc_g1.len() - 1,
+            vk_alpha_1: g1_json(&vk.alpha_g1),
+            vk_beta_2: g2_json(&vk.beta_g2),
+            vk_gamma_2: g2_json(&vk.gamma_g2),
+            vk_delta_2: g2_json(&vk.delta_g2),
+            vk_alphabeta_12: None,
+            ic: vk.gamma_abc_g1.iter().map(g1_json).collect(),
+        }
+    }
+}
+
+/// Public signals from `public.json`
+pub fn public_signals(json: &str) -> Result<Vec<Fr>, Error> {
+    let signals: Vec<String> = serde_json::from_str(json)?;
+    signals.iter().map(|signal| field(signal)).collect()
+}
+
+fn check_scheme(protocol: &str, curve: &str) -> Result<(), Error> {
+    if protocol != "groth16" {
+        return Err(Error::UnsupportedProtocol(protocol.into()));
+    }
+    // snarkjs calls BN254 bn128
+    if curve != "bn128" && curve != "bn254" {
+        return Err(Error::UnsupportedCurve(curve.into()));
+    }
+    Ok(())
+}
+
+/// Decimal string as an element of `F`, rejecting values outside the field
+fn field<F: PrimeField>(value: &str) -> Result<F, Error> {
+    let number: BigUint = value
+        .parse()
+        .map_err(|_| Error::InvalidNumber(value.into()))?;
+    if number >= F::MODULUS.into() {
+        return Err(Error::InvalidNumber(value.into()));
+    }
+    Ok(F::from(number))
+}
+
+fn fq2(value: &[String]) -> Result<Fq2, Error> {
+    let [c0, c1] = value else {
+        return Err(Error::InvalidEncoding("Fq2 element is not two numbers"));
+    };
+    Ok(Fq2::new(field(c0)?, field(c1)?))
+}
+
+fn g1(point: &[String]) -> Result<G1Affine, Error> {
+    let [x, y, z] = point else {
+        return Err(Error::InvalidEncoding("G1 point is not three numbers"));
+    };
+    let point = G1Projective::new_unchecked(field::<Fq>(x)?, field(y)?, field(z)?);
+    check(point.into_affine(), "G1 point is not on the curve")
+}
+
+fn g2(point: &[Vec<String>]) -> Result<G2Affine, Error> {
+    let [x, y, z] = point else {
+        return Err(Error::InvalidEncoding("G2 point is not three Fq2 elements"));
+    };
+    let point = G2Projective::new_unchecked(fq2(x)?, fq2(y)?, fq2(z)?);
+    check(point.into_affine(), "G2 point is not on the curve")
+}
+
+fn check<P: ark_ec::short_weierstrass::SWCurveConfig>(
+    point: ark_ec::short_weierstrass::Affine<P>,
+    msg: &'static str,
+) -> Result<ark_ec::short_weierstrass::Affine<P>, Error> {
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(Error::InvalidEncoding(msg));
+    }
+    Ok(point)
+}
+
+fn decimal<F: PrimeField>(value: &F) -> String {
+    value.into_bigint().to_string()
+}
+
+fn g1_json(point: &G1Affine) -> G1Json {
+    if point.is_zero() {
+        return vec!["0".into(), "1".into(), "0".into()];
+    }
+    vec![decimal(&point.x), decimal(&point.y), "1".into()]
+}
+
+fn g2_json(point: &G2Affine) -> G2Json {
+    let pair = |value: &Fq2| vec![decimal(&value.c0), decimal(&value.c1)];
+    if point.is_zero() {
+        return vec![
+            pair(&Fq2::zero()),
+            pair(&Fq2::from(1u64)),
+            pair(&Fq2::zero()),
+        ];
+    }
+    vec![pair(&point.x), pair(&point.y), pair(&Fq2::from(1u64))]
+}
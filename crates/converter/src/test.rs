@@ -0,0 +1,191 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use ark_bn254::{Bn254, Fq, G1Affine, G2Affine};
+use ark_ec::AffineRepr;
+use ark_groth16::{prepare_verifying_key, Groth16};
+
+use super::*;
+use encoding::*;
+use snarkjs::{public_signals, ProofJson, VerificationKeyJson};
+
+/// snarkjs files for a proof of `3 * 11 = 33` with ark-circom's multiplier
+/// circuit
+fn read(name: &str) -> String {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-vectors");
+    std::fs::read_to_string(dir.join(name)).unwrap()
+}
+
+fn fixture() -> (ProofJson, VerificationKeyJson) {
+    (
+        ProofJson::parse(&read("proof.json")).unwrap(),
+        VerificationKeyJson::parse(&read("verification_key.json")).unwrap(),
+    )
+}
+
+/// The G2 generator as snarkjs writes `vk_gamma_2`, real part first
+const GAMMA_2: &str = r#"[
+    ["10857046999023057135944570762232829481370756359578518086990519993285655852781",
+     "11559732032986387107991004021392285783925812861821192530917403151452391805634"],
+    ["8495653923123431417604973247489272438418190587263600148770280649306958101930",
+     "4082367875863433681332203403145435568316851327593401208105741076214120093531"],
+    ["1", "0"]
+]"#;
+
+#[test]
+fn test_snarkjs_proof_verifies() {
+    let (proof, vk) = fixture();
+    let proof = proof.to_proof().unwrap();
+    let vk = vk.to_verifying_key().unwrap();
+    let public = public_signals(&read("public.json")).unwrap();
+
+    let pvk = prepare_verifying_key(&vk);
+    assert!(Groth16::<Bn254>::verify_proof(&pvk, &proof, &public).unwrap());
+}
+
+#[test]
+fn test_g2_ordering() {
+    let (_, vk) = fixture();
+    assert_eq!(
+        vk.vk_gamma_2,
+        serde_json::from_str::<Vec<Vec<String>>>(GAMMA_2).unwrap()
+    );
+
+    let vk = vk.to_verifying_key().unwrap();
+    assert_eq!(vk.gamma_g2, G2Affine::generator());
+
+    // the imaginary part comes first in the packed layout
+    let words = encode_verifying_key(&vk);
+    let gamma_x_c1: num_bigint::BigUint =
+        "11559732032986387107991004021392285783925812861821192530917403151452391805634"
+            .parse()
+            .unwrap();
+    assert_eq!(words[6].to_vec(), gamma_x_c1.to_bytes_be());
+
+    // swapped coordinates are rejected, in JSON and in bytes
+    let mut swapped: Vec<Vec<String>> = serde_json::from_str(GAMMA_2).unwrap();
+    swapped[0].reverse();
+    let mut json = VerificationKeyJson::parse(&read("verification_key.json")).unwrap();
+    json.vk_gamma_2 = swapped;
+    assert!(matches!(
+        json.to_verifying_key(),
+        Err(Error::InvalidEncoding(_))
+    ));
+
+    let mut words = encode_g2(&G2Affine::generator());
+    words.swap(0, 1);
+    assert!(matches!(decode_g2(&words), Err(Error::InvalidEncoding(_))));
+}
+
+#[test]
+fn test_round_trip() {
+    let (proof_json, vk_json) = fixture();
+    let proof = proof_json.to_proof().unwrap();
+    let vk = vk_json.to_verifying_key().unwrap();
+
+    let words = encode_proof(&proof);
+    assert_eq!(decode_proof(&words).unwrap(), proof);
+    assert_eq!(decompress_proof(&compress_proof(&proof)).unwrap(), proof);
+    assert_eq!(ProofJson::from_proof(&proof), proof_json);
+
+    let words = encode_verifying_key(&vk);
+    assert_eq!(words.len(), 14 + 2 * vk.gamma_abc_g1.len());
+    assert_eq!(decode_verifying_key(&words).unwrap(), vk);
+    let compressed = compress_verifying_key(&vk);
+    assert_eq!(compressed.len(), 7 + vk.gamma_abc_g1.len());
+    assert_eq!(decompress_verifying_key(&compressed).unwrap(), vk);
+    assert_eq!(VerificationKeyJson::from_verifying_key(&vk), vk_json);
+}
+
+#[test]
+fn test_compression_flags() {
+    // the G1 generator is (1, 2), and 2 is the smaller of 2 and -2
+    let generator = G1Affine::generator();
+    let word = compress_g1(&generator);
+    assert_eq!(word[0], 0b10 << 6);
+    assert_eq!(word[31], 1);
+    let negated = compress_g1(&-generator);
+    assert_eq!(negated[0], 0b11 << 6);
+    assert_eq!(decompress_g1(&negated).unwrap(), -generator);
+
+    let g2 = G2Affine::generator();
+    assert_eq!(decompress_g2(&compress_g2(&-g2)).unwrap(), -g2);
+
+    // infinity
+    assert_eq!(compress_g1(&G1Affine::zero())[0], 0b01 << 6);
+    assert_eq!(
+        decompress_g1(&compress_g1(&G1Affine::zero())).unwrap(),
+        G1Affine::zero()
+    );
+    assert_eq!(
+        decompress_g2(&compress_g2(&G2Affine::zero())).unwrap(),
+        G2Affine::zero()
+    );
+    assert_eq!(
+        decode_g1(&encode_g1(&G1Affine::zero())).unwrap(),
+        G1Affine::zero()
+    );
+
+    // an uncompressed coordinate has no flag
+    assert!(matches!(
+        decompress_g1(&encode_fq(&Fq::from(1u64))),
+        Err(Error::InvalidEncoding(_))
+    ));
+}
+
+#[test]
+fn test_decode_invalid() {
+    let generator = G1Affine::generator();
+    assert_eq!(decode_g1(&encode_g1(&generator)).unwrap(), generator);
+
+    // not on the curve
+    let mut words = encode_g1(&generator);
+    words[1] = encode_fq(&(generator.y + Fq::from(1u64)));
+    assert!(matches!(decode_g1(&words), Err(Error::InvalidEncoding(_))));
+
+    // not reduced
+    let words: [Word; 2] = [[0xff; 32], encode_fq(&generator.y)];
+    assert!(matches!(decode_g1(&words), Err(Error::InvalidEncoding(_))));
+
+    // truncated key
+    let vk = fixture().1.to_verifying_key().unwrap();
+    let words = encode_verifying_key(&vk);
+    assert!(decode_verifying_key(&words[..words.len() - 1]).is_err());
+}
+
+#[test]
+fn test_snarkjs_invalid() {
+    let (proof, vk) = fixture();
+
+    // Jacobian coordinates are normalised: (4, 16, 2) is (1, 2)
+    let mut json = proof.clone();
+    json.pi_a = ["4", "16", "2"].map(String::from).to_vec();
+    assert_eq!(json.to_proof().unwrap().a, G1Affine::generator());
+
+    let mut json = proof.clone();
+    json.curve = "bls12381".into();
+    assert!(matches!(json.to_proof(), Err(Error::UnsupportedCurve(_))));
+
+    let mut json = proof.clone();
+    json.protocol = "plonk".into();
+    assert!(matches!(
+        json.to_proof(),
+        Err(Error::UnsupportedProtocol(_))
+    ));
+
+    // the base field modulus is not a field element
+    let mut json = proof;
+    json.pi_a[0] =
+        "21888242871839275222246405745257275088696311157297823662689037894645226208583".into();
+    assert!(matches!(json.to_proof(), Err(Error::InvalidNumber(_))));
+
+    let mut json = vk;
+    json.n_public = 2;
+    assert!(matches!(
+        json.to_verifying_key(),
+        Err(Error::InvalidEncoding(_))
+    ));
+
+    assert!(matches!(ProofJson::parse("{}"), Err(Error::Json(_))));
+}
@@ -0,0 +1,57 @@
+//! Baby Jubjub as defined in EIP-2494, the curve the Semaphore circuit uses.
+//!
+//! `ark-ed-on-bn254` ships the same curve in a different twisted Edwards
+//! form (a = 1), so the EIP-2494 coefficients are declared here and the
+//! subgroup base point is used as generator.
+
+use ark_ec::{
+    models::CurveConfig,
+    twisted_edwards::{Affine, MontCurveConfig, TECurveConfig},
+};
+use ark_ed_on_bn254::{Fq, Fr};
+use ark_ff::{Field, MontFp};
+
+pub type EdwardsAffine = Affine<BabyJubjubConfig>;
+
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct BabyJubjubConfig;
+
+impl CurveConfig for BabyJubjubConfig {
+    type BaseField = Fq;
+    type ScalarField = Fr;
+
+    const COFACTOR: &'static [u64] = &[8];
+
+    // 8^-1 mod l
+    const COFACTOR_INV: Fr =
+        MontFp!("2394026564107420727433200628387514462817212225638746351800188703329891451411");
+}
+
+/// a·x² + y² = 1 + d·x²·y²
+impl TECurveConfig for BabyJubjubConfig {
+    const COEFF_A: Fq = MontFp!("168700");
+    const COEFF_D: Fq = MontFp!("168696");
+    const GENERATOR: EdwardsAffine = EdwardsAffine::new_unchecked(BASE_X, BASE_Y);
+
+    type MontCurveConfig = BabyJubjubConfig;
+
+    #[inline(always)]
+    fn mul_by_a(elem: Fq) -> Fq {
+        elem * <BabyJubjubConfig as TECurveConfig>::COEFF_A
+    }
+}
+
+/// B·y² = x³ + A·x² + x
+impl MontCurveConfig for BabyJubjubConfig {
+    const COEFF_A: Fq = MontFp!("168698");
+    const COEFF_B: Fq = Fq::ONE;
+
+    type TECurveConfig = BabyJubjubConfig;
+}
+
+/// Base point of the prime order subgroup, x coordinate
+pub const BASE_X: Fq =
+    MontFp!("5299619240641551281634865583518297030282874472190772894086521144482721001553");
+/// Base point of the prime order subgroup, y coordinate
+pub const BASE_Y: Fq =
+    MontFp!("16950150798460657717958625567821834550301663161624707787222815936182638968203");
@@ -0,0 +1,176 @@
+//! Semaphore v4 identities.
+//!
+//! An identity is an EdDSA key pair on Baby Jubjub. The secret scalar is
+//! derived from the private key as in RFC 8032 (blake-512, pruned, divided by
+//! the cofactor), the public key is that scalar times the subgroup base point
+//! and the identity commitment is `poseidon(public_key.x, public_key.y)`.
+//! Identities are exported, like Semaphore's `Identity.export()`, as the
+//! base64 encoded private key.
+
+use std::fmt;
+use std::ops::Mul;
+
+use ark_ec::{twisted_edwards::TECurveConfig, CurveGroup};
+use ark_ff::{BigInteger, PrimeField};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use blake::Blake;
+use group::poseidon;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use soroban_sdk::{Bytes, Env};
+use zeroize::Zeroize;
+
+pub use ark_ed_on_bn254::{Fq, Fr};
+pub use baby_jubjub::{BabyJubjubConfig, EdwardsAffine};
+
+pub mod baby_jubjub;
+
+#[derive(Debug)]
+pub enum Error {
+    /// An exported identity is not valid base64
+    InvalidExport(base64::DecodeError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidExport(err) => write!(f, "invalid exported identity: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Semaphore identity. The private key and secret scalar are left out of
+/// `Debug` and zeroed on drop
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Identity {
+    private_key: Vec<u8>,
+    secret_scalar: Fr,
+    public_key: EdwardsAffine,
+    commitment: Fq,
+}
+
+impl Identity {
+    /// Identity with a random 32 byte private key
+    pub fn random() -> Self {
+        let mut private_key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut private_key);
+        let identity = Self::new(&private_key);
+        private_key.zeroize();
+        identity
+    }
+
+    /// Identity derived from `private_key`
+    pub fn new(private_key: &[u8]) -> Self {
+        let secret_scalar = secret_scalar(private_key);
+        let public_key = BabyJubjubConfig::GENERATOR.mul(secret_scalar).into_affine();
+        let commitment = poseidon::hash2(public_key.x, public_key.y);
+
+        Self {
+            private_key: private_key.to_vec(),
+            secret_scalar,
+            public_key,
+            commitment,
+        }
+    }
+
+    /// Identity from the output of [`Identity::export`]
+    pub fn import(exported: &str) -> Result<Self, Error> {
+        let mut private_key = STANDARD.decode(exported).map_err(Error::InvalidExport)?;
+        let identity = Self::new(&private_key);
+        private_key.zeroize();
+        Ok(identity)
+    }
+
+    /// Base64 encoded private key
+    pub fn export(&self) -> String {
+        STANDARD.encode(&self.private_key)
+    }
+
+    pub fn private_key(&self) -> &[u8] {
+        &self.private_key
+    }
+
+    /// Secret the circuit takes as input
+    pub fn secret_scalar(&self) -> Fr {
+        self.secret_scalar
+    }
+
+    pub fn public_key(&self) -> EdwardsAffine {
+        self.public_key
+    }
+
+    pub fn commitment(&self) -> Fq {
+        self.commitment
+    }
+
+    /// Commitment as the group contract's `add_member` takes it: the field
+    /// element in 32 bytes, big-endian
+    pub fn commitment_bytes(&self, env: &Env) -> Bytes {
+        Bytes::from_array(env, &field_to_bytes(&self.commitment))
+    }
+}
+
+impl fmt::Debug for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Identity")
+            .field("public_key", &self.public_key)
+            .field("commitment", &self.commitment)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Identity {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+        self.secret_scalar.zeroize();
+    }
+}
+
+impl From<Identity> for String {
+    fn from(identity: Identity) -> Self {
+        identity.export()
+    }
+}
+
+impl TryFrom<String> for Identity {
+    type Error = Error;
+
+    fn try_from(exported: String) -> Result<Self, Error> {
+        Self::import(&exported)
+    }
+}
+
+/// Field element as 32 big-endian bytes, the encoding used on chain
+pub fn field_to_bytes(value: &Fq) -> [u8; 32] {
+    value.into_bigint().to_bytes_be().try_into().unwrap()
+}
+
+/// Field element from big-endian bytes, reduced modulo the field order
+pub fn field_from_bytes(bytes: &[u8]) -> Fq {
+    Fq::from_be_bytes_mod_order(bytes)
+}
+
+/// blake-512 of the private key, pruned, first half divided by the cofactor
+fn secret_scalar(private_key: &[u8]) -> Fr {
+    let mut hash = [0u8; 64];
+    let mut hasher = Blake::new(512).unwrap();
+    hasher.update(private_key);
+    hasher.finalise(&mut hash);
+
+    hash[0] &= 0xF8;
+    hash[31] &= 0x7F;
+    hash[31] |= 0x40;
+
+    // little-endian shift right by 3
+    let mut scalar = [0u8; 32];
+    for i in 0..32 {
+        let next = if i < 31 { hash[i + 1] } else { 0 };
+        scalar[i] = (hash[i] >> 3) | (next << 5);
+    }
+    Fr::from_le_bytes_mod_order(&scalar)
+}
+
+mod test;
@@ -0,0 +1,75 @@
+#![cfg(test)]
+
+use super::*;
+use ark_ff::MontFp;
+
+// Semaphore v4 vectors for the private key "privateKey", produced by the
+// TypeScript implementation (https://github.com/brech1/sem-test-values)
+const PRIVATE_KEY: &[u8] = b"privateKey";
+const SECRET_SCALAR: Fr =
+    MontFp!("1319709833472015827730826418408303647941748850729897255051940662182776719635");
+const PUBLIC_KEY_X: Fq =
+    MontFp!("20191161190634177714856258432742391014210684311546132016070244128804840948064");
+const PUBLIC_KEY_Y: Fq =
+    MontFp!("15209227963454794938053687888234270810990820964270375245744800564428536818120");
+const COMMITMENT: Fq =
+    MontFp!("11372478937056182347300323057848769551333725898578571354328589544822167334484");
+
+#[test]
+fn test_known_answer() {
+    let identity = Identity::new(PRIVATE_KEY);
+    assert_eq!(identity.secret_scalar(), SECRET_SCALAR);
+    assert_eq!(identity.public_key().x, PUBLIC_KEY_X);
+    assert_eq!(identity.public_key().y, PUBLIC_KEY_Y);
+    assert_eq!(identity.commitment(), COMMITMENT);
+    assert!(identity.public_key().is_on_curve());
+    assert!(identity
+        .public_key()
+        .is_in_correct_subgroup_assuming_on_curve());
+}
+
+#[test]
+fn test_commitment_bytes() {
+    let env = Env::default();
+    let identity = Identity::new(PRIVATE_KEY);
+    let mut bytes = [0u8; 32];
+    identity.commitment_bytes(&env).copy_into_slice(&mut bytes);
+
+    assert_eq!(
+        hex::encode(bytes),
+        "19249850380b1a0211f37352af6aa754777de7049304c7d098a4d99124d46e54"
+    );
+    assert_eq!(field_from_bytes(&bytes), COMMITMENT);
+}
+
+#[test]
+fn test_export_import() {
+    let identity = Identity::new(PRIVATE_KEY);
+    assert_eq!(identity.export(), "cHJpdmF0ZUtleQ==");
+    assert_eq!(Identity::import(&identity.export()).unwrap(), identity);
+    assert!(matches!(
+        Identity::import("not base64!"),
+        Err(Error::InvalidExport(_))
+    ));
+
+    let json = serde_json::to_string(&identity).unwrap();
+    assert_eq!(json, "\"cHJpdmF0ZUtleQ==\"");
+    assert_eq!(serde_json::from_str::<Identity>(&json).unwrap(), identity);
+}
+
+#[test]
+fn test_debug_redacts_private_key() {
+    let identity = Identity::new(PRIVATE_KEY);
+    let debug = format!("{identity:?}");
+    assert!(debug.contains(&format!("{COMMITMENT:?}")));
+    assert!(!debug.contains(&format!("{:?}", identity.private_key())));
+    assert!(!debug.contains(&format!("{SECRET_SCALAR:?}")));
+}
+
+#[test]
+fn test_random() {
+    let identity = Identity::random();
+    assert_eq!(identity.private_key().len(), 32);
+    assert_ne!(identity, Identity::random());
+    assert_eq!(Identity::new(identity.private_key()), identity);
+}
@@ -0,0 +1,503 @@
+//! Off-chain indexer for the `group` and `semaphore` contracts.
+//!
+//! The indexer replays the events described in the `events` crate into local
+//! copies of the group trees, using the same `tree::Tree` as the contract,
+//! and checks every computed root against the emitted one. It also
+//! keeps the set of spent nullifiers per group.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use events::{
+    GroupAdminRenounced, GroupAdminUpdated, GroupArchived, GroupCreated, GroupDeleted, GroupEvent,
+    GroupTreeSelected, MemberAdded, MemberRemoved, MemberUpdated, MembersAdded, MembersRemoved,
+    MembersUpdated, ProofValidated, SCHEMA_VERSION,
+};
+use group::{
+    datatypes::{HashFunction, TreeKind},
+    indexed,
+    proof::{NonMembershipProof, Proof, SparseProof},
+    tree::{GroupTree, Tree},
+};
+use soroban_sdk::{
+    xdr::{self, Limits, ReadXdr, ScVal},
+    Bytes, Env, Symbol, TryFromVal, Val,
+};
+
+pub use source::{EnvSource, EventSource, JsonFileSource, RawEvent};
+
+pub mod source;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Xdr(xdr::Error),
+    /// An event could not be decoded into the expected type
+    MalformedEvent(String),
+    /// The event was published with a schema version this indexer does not know
+    UnsupportedSchema(u32),
+    /// A tree event references a group that was never created, or was deleted
+    UnknownGroup(u32),
+    /// A tree event changes a group after it was archived
+    ArchivedGroup(u32),
+    /// The replayed tree disagrees with the root emitted by the contract
+    RootMismatch {
+        group_id: u32,
+        expected: String,
+        computed: String,
+    },
+    /// A nullifier was spent twice for the same group
+    NullifierReused { group_id: u32, nullifier: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "io error: {err}"),
+            Error::Json(err) => write!(f, "invalid event file: {err}"),
+            Error::Xdr(err) => write!(f, "invalid xdr: {err}"),
+            Error::MalformedEvent(msg) => write!(f, "malformed event: {msg}"),
+            Error::UnsupportedSchema(version) => {
+                write!(f, "unsupported event schema version {version}")
+            }
+            Error::UnknownGroup(group_id) => {
+                write!(f, "group {group_id} was never created or was deleted")
+            }
+            Error::ArchivedGroup(group_id) => write!(f, "group {group_id} is archived"),
+            Error::RootMismatch {
+                group_id,
+                expected,
+                computed,
+            } => write!(
+                f,
+                "root mismatch in group {group_id}: contract emitted {expected}, replay computed {computed}"
+            ),
+            Error::NullifierReused {
+                group_id,
+                nullifier,
+            } => write!(f, "nullifier {nullifier} spent twice in group {group_id}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<xdr::Error> for Error {
+    fn from(err: xdr::Error) -> Self {
+        Error::Xdr(err)
+    }
+}
+
+impl From<soroban_sdk::Error> for Error {
+    fn from(err: soroban_sdk::Error) -> Self {
+        Error::MalformedEvent(format!("{err:?}"))
+    }
+}
+
+/// Local copy of a group rebuilt from its events
+pub struct GroupState {
+    /// `None` once the admin renounced
+    admin: Option<String>,
+    depth: u32,
+    /// Set once the group is archived, after which its tree cannot change
+    archived: bool,
+    tree: Tree,
+    /// Identity commitment stored at each leaf, `None` once removed
+    leaves: Vec<Option<Vec<u8>>>,
+}
+
+impl GroupState {
+    pub fn admin(&self) -> Option<&str> {
+        self.admin.as_deref()
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archived
+    }
+
+    pub fn tree_kind(&self) -> TreeKind {
+        self.tree.kind()
+    }
+
+    pub fn hash_function(&self) -> HashFunction {
+        self.tree.hash_function()
+    }
+
+    pub fn root(&self) -> Vec<u8> {
+        to_vec(&self.tree.root())
+    }
+
+    /// Identity commitment at every leaf in leaf order, `None` for removed leaves
+    pub fn leaves(&self) -> &[Option<Vec<u8>>] {
+        &self.leaves
+    }
+
+    /// Identity commitments of the current members in leaf order
+    pub fn members(&self) -> Vec<Vec<u8>> {
+        self.leaves.iter().flatten().cloned().collect()
+    }
+
+    /// Leaf index of an identity commitment
+    pub fn index_of(&self, identity_commitment: &[u8]) -> Option<u32> {
+        self.leaves
+            .iter()
+            .position(|leaf| leaf.as_deref() == Some(identity_commitment))
+            .map(|index| index as u32)
+    }
+
+    /// Merkle proof for the leaf at `index`, as `group::get_proof` returns it.
+    /// `None` for sparse trees
+    pub fn proof(&self, index: u32) -> Option<Proof> {
+        self.tree.merkle_tree()?.proof(index as usize)
+    }
+
+    /// Proof that `identity_commitment` is not a member, as
+    /// `group::prove_non_membership` returns it. `None` for members and for
+    /// groups without an indexed tree
+    pub fn non_membership_proof(
+        &self,
+        env: &Env,
+        identity_commitment: &[u8],
+    ) -> Option<NonMembershipProof> {
+        let Tree::Indexed(tree) = &self.tree else {
+            return None;
+        };
+        let key = indexed::key(env, &Bytes::from_slice(env, identity_commitment));
+        tree.non_membership_proof(env, &key)
+    }
+
+    /// Proof that `identity_commitment` is a member, as
+    /// `group::get_sparse_proof` returns it. `None` for non-members and for
+    /// groups without a sparse tree
+    pub fn sparse_proof(&self, env: &Env, identity_commitment: &[u8]) -> Option<SparseProof> {
+        let Tree::Sparse(tree) = &self.tree else {
+            return None;
+        };
+        let key = indexed::key(env, &Bytes::from_slice(env, identity_commitment));
+        tree.proof(env, &key)
+    }
+
+    fn set_leaf(&mut self, index: u32, identity_commitment: Option<Vec<u8>>) {
+        let index = index as usize;
+        if self.leaves.len() <= index {
+            self.leaves.resize(index + 1, None);
+        }
+        self.leaves[index] = identity_commitment;
+    }
+}
+
+/// Replays contract events into local group trees and nullifier sets
+pub struct Indexer {
+    env: Env,
+    contract_id: Option<String>,
+    groups: BTreeMap<u32, GroupState>,
+    /// Tree kinds and hash functions announced for groups about to be
+    /// created
+    tree_kinds: BTreeMap<u32, (TreeKind, HashFunction)>,
+    nullifiers: BTreeMap<u32, BTreeSet<Vec<u8>>>,
+}
+
+impl Default for Indexer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Indexer {
+    /// Index events from every contract
+    pub fn new() -> Self {
+        let env = Env::default();
+        env.budget().reset_unlimited();
+        Self {
+            env,
+            contract_id: None,
+            groups: BTreeMap::new(),
+            tree_kinds: BTreeMap::new(),
+            nullifiers: BTreeMap::new(),
+        }
+    }
+
+    /// Only index events published by `contract_id`
+    pub fn for_contract(contract_id: impl Into<String>) -> Self {
+        Self {
+            contract_id: Some(contract_id.into()),
+            ..Self::new()
+        }
+    }
+
+    /// Environment the local trees and proofs live in
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+
+    /// Replay every event from `source`
+    pub fn sync(&mut self, source: &mut impl EventSource) -> Result<(), Error> {
+        for event in source.events()? {
+            self.apply(&event)?;
+        }
+        Ok(())
+    }
+
+    /// Replay a single event; events that are not part of the schema are skipped
+    pub fn apply(&mut self, event: &RawEvent) -> Result<(), Error> {
+        if let Some(contract_id) = &self.contract_id {
+            if *contract_id != event.contract_id {
+                return Ok(());
+            }
+        }
+
+        let topics = event
+            .topic
+            .iter()
+            .map(|topic| self.decode::<Val>(topic))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (Some(name), Some(version)) = (topics.first(), topics.get(1)) else {
+            return Ok(());
+        };
+        let (Ok(name), Ok(version)) = (
+            Symbol::try_from_val(&self.env, name),
+            u32::try_from_val(&self.env, version),
+        ) else {
+            return Ok(());
+        };
+        let name = name.to_string();
+
+        if ![
+            GroupCreated::NAME,
+            GroupTreeSelected::NAME,
+            GroupAdminUpdated::NAME,
+            GroupAdminRenounced::NAME,
+            GroupArchived::NAME,
+            GroupDeleted::NAME,
+            MemberAdded::NAME,
+            MembersAdded::NAME,
+            MemberUpdated::NAME,
+            MembersUpdated::NAME,
+            MemberRemoved::NAME,
+            MembersRemoved::NAME,
+            ProofValidated::NAME,
+        ]
+        .contains(&name.as_str())
+        {
+            return Ok(());
+        }
+        if version != SCHEMA_VERSION {
+            return Err(Error::UnsupportedSchema(version));
+        }
+
+        match name.as_str() {
+            GroupCreated::NAME => {
+                let event: GroupCreated = self.decode(&event.value)?;
+                let (kind, hash) = self
+                    .tree_kinds
+                    .remove(&event.group_id)
+                    .unwrap_or((TreeKind::Incremental, HashFunction::Keccak256));
+                self.groups.insert(
+                    event.group_id,
+                    GroupState {
+                        admin: Some(event.admin.to_string().to_string()),
+                        depth: event.depth,
+                        archived: false,
+                        tree: Tree::new(&self.env, kind, hash, event.depth),
+                        leaves: Vec::new(),
+                    },
+                );
+                self.check_root(event.group_id, &event.root)
+            }
+            GroupTreeSelected::NAME => {
+                let event: GroupTreeSelected = self.decode(&event.value)?;
+                let kind = TreeKind::from_name(&self.env, &event.tree).ok_or_else(|| {
+                    Error::MalformedEvent(format!("unknown tree {}", event.tree.to_string()))
+                })?;
+                let hash = HashFunction::from_name(&self.env, &event.hash).ok_or_else(|| {
+                    Error::MalformedEvent(format!("unknown hash {}", event.hash.to_string()))
+                })?;
+                self.tree_kinds.insert(event.group_id, (kind, hash));
+                Ok(())
+            }
+            GroupAdminUpdated::NAME => {
+                let event: GroupAdminUpdated = self.decode(&event.value)?;
+                self.group_mut(event.group_id)?.admin = Some(event.new_admin.to_string().to_string());
+                Ok(())
+            }
+            GroupAdminRenounced::NAME => {
+                let event: GroupAdminRenounced = self.decode(&event.value)?;
+                self.group_mut(event.group_id)?.admin = None;
+                Ok(())
+            }
+            GroupArchived::NAME => {
+                let event: GroupArchived = self.decode(&event.value)?;
+                self.group_mut(event.group_id)?.archived = true;
+                Ok(())
+            }
+            // nullifiers stay spent after their group is deleted
+            GroupDeleted::NAME => {
+                let event: GroupDeleted = self.decode(&event.value)?;
+                self.groups
+                    .remove(&event.group_id)
+                    .ok_or(Error::UnknownGroup(event.group_id))?;
+                Ok(())
+            }
+            MemberAdded::NAME => {
+                let event: MemberAdded = self.decode(&event.value)?;
+                self.set_leaf(event.group_id, event.index, Some(event.identity_commitment))?;
+                self.check_root(event.group_id, &event.root)
+            }
+            MembersAdded::NAME => {
+                let event: MembersAdded = self.decode(&event.value)?;
+                for (offset, identity_commitment) in event.identity_commitments.iter().enumerate() {
+                    self.set_leaf(
+                        event.group_id,
+                        event.start_index + offset as u32,
+                        Some(identity_commitment),
+                    )?;
+                }
+                self.check_root(event.group_id, &event.root)
+            }
+            MemberUpdated::NAME => {
+                let event: MemberUpdated = self.decode(&event.value)?;
+                self.set_leaf(
+                    event.group_id,
+                    event.index,
+                    Some(event.new_identity_commitment),
+                )?;
+                self.check_root(event.group_id, &event.root)
+            }
+            MembersUpdated::NAME => {
+                let event: MembersUpdated = self.decode(&event.value)?;
+                for (index, identity_commitment) in
+                    event.indexes.iter().zip(event.new_identity_commitments.iter())
+                {
+                    self.set_leaf(event.group_id, index, Some(identity_commitment))?;
+                }
+                self.check_root(event.group_id, &event.root)
+            }
+            MembersRemoved::NAME => {
+                let event: MembersRemoved = self.decode(&event.value)?;
+                for index in event.indexes.iter() {
+                    self.set_leaf(event.group_id, index, None)?;
+                }
+                self.check_root(event.group_id, &event.root)
+            }
+            MemberRemoved::NAME => {
+                let event: MemberRemoved = self.decode(&event.value)?;
+                self.set_leaf(event.group_id, event.index, None)?;
+                self.check_root(event.group_id, &event.root)
+            }
+            ProofValidated::NAME => {
+                let event: ProofValidated = self.decode(&event.value)?;
+                let nullifier = to_vec(&event.nullifier);
+                if !self
+                    .nullifiers
+                    .entry(event.group_id)
+                    .or_default()
+                    .insert(nullifier.clone())
+                {
+                    return Err(Error::NullifierReused {
+                        group_id: event.group_id,
+                        nullifier: hex::encode(nullifier),
+                    });
+                }
+                Ok(())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn group(&self, group_id: u32) -> Option<&GroupState> {
+        self.groups.get(&group_id)
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = (u32, &GroupState)> {
+        self.groups.iter().map(|(group_id, group)| (*group_id, group))
+    }
+
+    /// Whether `nullifier` was already spent in `group_id`
+    pub fn is_nullifier_used(&self, group_id: u32, nullifier: &[u8]) -> bool {
+        self.nullifiers
+            .get(&group_id)
+            .is_some_and(|nullifiers| nullifiers.contains(nullifier))
+    }
+
+    /// Spent nullifiers of `group_id`
+    pub fn nullifiers(&self, group_id: u32) -> impl Iterator<Item = &Vec<u8>> {
+        self.nullifiers.get(&group_id).into_iter().flatten()
+    }
+
+    fn decode<T: TryFromVal<Env, Val>>(&self, value: &str) -> Result<T, Error> {
+        let value = ScVal::from_xdr_base64(value, Limits::none())?;
+        let value = Val::try_from_val(&self.env, &value)
+            .map_err(|_| Error::MalformedEvent(format!("{value:?}")))?;
+        T::try_from_val(&self.env, &value)
+            .map_err(|_| Error::MalformedEvent(format!("{value:?}")))
+    }
+
+    fn group_mut(&mut self, group_id: u32) -> Result<&mut GroupState, Error> {
+        self.groups
+            .get_mut(&group_id)
+            .ok_or(Error::UnknownGroup(group_id))
+    }
+
+    fn set_leaf(
+        &mut self,
+        group_id: u32,
+        index: u32,
+        identity_commitment: Option<Bytes>,
+    ) -> Result<(), Error> {
+        let env = self.env.clone();
+        let group = self.group_mut(group_id)?;
+        if group.archived {
+            return Err(Error::ArchivedGroup(group_id));
+        }
+        let old = group
+            .leaves
+            .get(index as usize)
+            .cloned()
+            .flatten()
+            .map(|old| Bytes::from_slice(&env, &old));
+        match (old, &identity_commitment) {
+            (Some(old), Some(new)) => group.tree.update(&env, &old, new, index),
+            (Some(old), None) => group.tree.remove(&env, &old, index),
+            (None, Some(new)) => group.tree.insert(&env, new, index),
+            // nothing to remove; the root check reports the missing member
+            (None, None) => {}
+        }
+        group.set_leaf(index, identity_commitment.map(|c| to_vec(&c)));
+        Ok(())
+    }
+
+    fn check_root(&self, group_id: u32, expected: &Bytes) -> Result<(), Error> {
+        let computed = self.groups[&group_id].tree.root();
+        if computed != *expected {
+            return Err(Error::RootMismatch {
+                group_id,
+                expected: hex::encode(to_vec(expected)),
+                computed: hex::encode(to_vec(&computed)),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn to_vec(bytes: &Bytes) -> Vec<u8> {
+    bytes.iter().collect()
+}
+
+mod test;
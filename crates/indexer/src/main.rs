@@ -0,0 +1,46 @@
+//! Rebuild group trees from a JSON event file and print them.
+//!
+//! Usage: `semaphore-indexer <events.json> [--contract <contract id>]`
+
+use std::process::ExitCode;
+
+use indexer::{Indexer, JsonFileSource};
+use serde_json::json;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (path, contract_id) = match args.as_slice() {
+        [path] => (path, None),
+        [path, flag, contract_id] if flag == "--contract" => (path, Some(contract_id)),
+        _ => {
+            eprintln!("usage: semaphore-indexer <events.json> [--contract <contract id>]");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut indexer = match contract_id {
+        Some(contract_id) => Indexer::for_contract(contract_id),
+        None => Indexer::new(),
+    };
+    if let Err(err) = indexer.sync(&mut JsonFileSource::new(path)) {
+        eprintln!("{err}");
+        return ExitCode::FAILURE;
+    }
+
+    let groups: Vec<_> = indexer
+        .groups()
+        .map(|(group_id, group)| {
+            json!({
+                "group_id": group_id,
+                "admin": group.admin(),
+                "depth": group.depth(),
+                "archived": group.is_archived(),
+                "root": hex::encode(group.root()),
+                "members": group.members().iter().map(hex::encode).collect::<Vec<_>>(),
+                "nullifiers": indexer.nullifiers(group_id).map(hex::encode).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    println!("{}", serde_json::to_string_pretty(&json!({ "groups": groups })).unwrap());
+    ExitCode::SUCCESS
+}
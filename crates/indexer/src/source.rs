@@ -0,0 +1,98 @@
+//! Where contract events come from.
+//!
+//! Events use the same shape as the `getEvents` RPC response: the contract
+//! id as a strkey and the topics and value as base64 encoded `ScVal` XDR.
+
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use soroban_sdk::{
+    testutils::Events,
+    xdr::{Limits, ScVal, WriteXdr},
+    Env, TryFromVal,
+};
+
+use crate::Error;
+
+/// A contract event as returned by `getEvents`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawEvent {
+    pub contract_id: String,
+    #[serde(default)]
+    pub ledger: u32,
+    pub topic: Vec<String>,
+    pub value: String,
+}
+
+/// A source of contract events, oldest first
+pub trait EventSource {
+    fn events(&mut self) -> Result<Vec<RawEvent>, Error>;
+}
+
+/// Events read from a JSON file, either a bare array or a `getEvents`
+/// result object with an `events` field
+pub struct JsonFileSource {
+    path: PathBuf,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonEvents {
+    List(Vec<RawEvent>),
+    Result { events: Vec<RawEvent> },
+}
+
+impl JsonFileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl EventSource for JsonFileSource {
+    fn events(&mut self) -> Result<Vec<RawEvent>, Error> {
+        let json = fs::read_to_string(&self.path)?;
+        Ok(match serde_json::from_str(&json)? {
+            JsonEvents::List(events) => events,
+            JsonEvents::Result { events } => events,
+        })
+    }
+}
+
+/// Stand-in for an RPC node: the events recorded by a local `Env`
+pub struct EnvSource<'a> {
+    env: &'a Env,
+}
+
+impl<'a> EnvSource<'a> {
+    pub fn new(env: &'a Env) -> Self {
+        Self { env }
+    }
+}
+
+impl EventSource for EnvSource<'_> {
+    fn events(&mut self) -> Result<Vec<RawEvent>, Error> {
+        let ledger = self.env.ledger().sequence();
+        self.env
+            .events()
+            .all()
+            .iter()
+            .map(|(contract_id, topics, value)| {
+                let topic = topics
+                    .iter()
+                    .map(|topic| encode(&ScVal::try_from_val(self.env, &topic)?))
+                    .collect::<Result<_, _>>()?;
+                Ok(RawEvent {
+                    contract_id: contract_id.to_string().to_string(),
+                    ledger,
+                    topic,
+                    value: encode(&ScVal::try_from_val(self.env, &value)?)?,
+                })
+            })
+            .collect()
+    }
+}
+
+fn encode(value: &ScVal) -> Result<String, Error> {
+    Ok(value.to_xdr_base64(Limits::none())?)
+}
@@ -0,0 +1,312 @@
+#![cfg(test)]
+
+use super::*;
+use group::{SemaphoreGroupContract, SemaphoreGroupContractClient};
+use soroban_sdk::{contract, testutils::Address as _, Address};
+
+const GROUP_ID: u32 = 0;
+
+#[contract]
+struct ProofEventsContract;
+
+fn commitment(env: &Env, seed: u8) -> Bytes {
+    Bytes::from_slice(env, &[seed; 48])
+}
+
+/// Run a few membership changes against a real group contract
+fn populated_group(env: &Env) -> (Address, SemaphoreGroupContractClient<'_>) {
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(env),));
+    let client = SemaphoreGroupContractClient::new(env, &contract_id);
+    assert_eq!(client.create_group(&Address::generate(env), &None), GROUP_ID);
+    for seed in 1..=2 {
+        client.add_member(&GROUP_ID, &commitment(env, seed));
+    }
+    client.add_members(
+        &GROUP_ID,
+        &soroban_sdk::vec![env, commitment(env, 3), commitment(env, 4)],
+    );
+    client.update_member(&GROUP_ID, &commitment(env, 2), &commitment(env, 5));
+    client.remove_member(&GROUP_ID, &commitment(env, 3));
+    (contract_id, client)
+}
+
+#[test]
+fn test_replay_matches_contract() {
+    let env = Env::default();
+    let (contract_id, client) = populated_group(&env);
+
+    let mut indexer = Indexer::for_contract(contract_id.to_string().to_string());
+    indexer.sync(&mut EnvSource::new(&env)).unwrap();
+    let group = indexer.group(GROUP_ID).unwrap();
+
+    assert_eq!(group.root(), to_vec(&client.get_merkle_root(&GROUP_ID)));
+    assert_eq!(
+        group.members(),
+        [1, 5, 4].map(|seed| to_vec(&commitment(&env, seed)))
+    );
+    assert_eq!(group.leaves()[2], None);
+    assert_eq!(group.index_of(&to_vec(&commitment(&env, 4))), Some(3));
+    assert_eq!(group.admin(), Some(client.get_group_admin(&GROUP_ID).to_string().to_string().as_str()));
+
+    // proofs from the local tree match the contract's proofs
+    let proof = group.proof(3).unwrap();
+    let contract_proof = client.get_proof(&GROUP_ID, &3);
+    assert_eq!(
+        proof.0.iter().map(|branch| to_vec(&branch.into_inner())).collect::<Vec<_>>(),
+        contract_proof.0.iter().map(|branch| to_vec(&branch.into_inner())).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_replay_batches() {
+    let env = Env::default();
+    let (contract_id, client) = populated_group(&env);
+    client.update_members(
+        &GROUP_ID,
+        &soroban_sdk::vec![
+            &env,
+            (commitment(&env, 4), commitment(&env, 6)),
+            (commitment(&env, 1), commitment(&env, 7)),
+        ],
+    );
+    client.remove_members(&GROUP_ID, &soroban_sdk::vec![&env, commitment(&env, 5)]);
+
+    let mut indexer = Indexer::for_contract(contract_id.to_string().to_string());
+    indexer.sync(&mut EnvSource::new(&env)).unwrap();
+    let group = indexer.group(GROUP_ID).unwrap();
+    assert_eq!(group.root(), to_vec(&client.get_merkle_root(&GROUP_ID)));
+    assert_eq!(group.members(), [7, 6].map(|seed| to_vec(&commitment(&env, seed))));
+}
+
+#[test]
+fn test_replay_indexed_group() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let metadata = group::datatypes::GroupMetadata {
+        name: None,
+        description_uri: None,
+        depth: None,
+        tree: TreeKind::Indexed,
+        hash: HashFunction::Keccak256,
+    };
+    let group_id = client.create_group(&Address::generate(&env), &Some(metadata));
+    client.add_members(
+        &group_id,
+        &soroban_sdk::vec![&env, commitment(&env, 1), commitment(&env, 2), commitment(&env, 3)],
+    );
+    client.update_member(&group_id, &commitment(&env, 1), &commitment(&env, 4));
+    client.remove_members(&group_id, &soroban_sdk::vec![&env, commitment(&env, 2)]);
+
+    let mut indexer = Indexer::for_contract(contract_id.to_string().to_string());
+    indexer.sync(&mut EnvSource::new(&env)).unwrap();
+    let group = indexer.group(group_id).unwrap();
+    assert_eq!(group.root(), to_vec(&client.get_merkle_root(&group_id)));
+    assert_eq!(group.members(), [4, 3].map(|seed| to_vec(&commitment(&env, seed))));
+    assert_eq!(group.index_of(&to_vec(&commitment(&env, 4))), Some(1));
+
+    // non-membership proofs from the local tree match the contract's
+    let outsider = commitment(&env, 2);
+    assert_eq!(
+        group.non_membership_proof(indexer.env(), &to_vec(&outsider)).map(|proof| proof.low_key.to_array()),
+        Some(client.prove_non_membership(&group_id, &outsider).low_key.to_array())
+    );
+    assert!(group.non_membership_proof(indexer.env(), &to_vec(&commitment(&env, 3))).is_none());
+}
+
+#[test]
+fn test_replay_sparse_group() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register(SemaphoreGroupContract, (Address::generate(&env),));
+    let client = SemaphoreGroupContractClient::new(&env, &contract_id);
+    let metadata = group::datatypes::GroupMetadata {
+        name: None,
+        description_uri: None,
+        depth: None,
+        tree: TreeKind::Sparse,
+        hash: HashFunction::Sha256,
+    };
+    let group_id = client.create_group(&Address::generate(&env), &Some(metadata));
+    client.add_members(
+        &group_id,
+        &soroban_sdk::vec![&env, commitment(&env, 1), commitment(&env, 2), commitment(&env, 3)],
+    );
+    client.update_members(
+        &group_id,
+        &soroban_sdk::vec![&env, (commitment(&env, 1), commitment(&env, 4))],
+    );
+    client.remove_member(&group_id, &commitment(&env, 2));
+
+    let mut indexer = Indexer::for_contract(contract_id.to_string().to_string());
+    indexer.sync(&mut EnvSource::new(&env)).unwrap();
+    let group = indexer.group(group_id).unwrap();
+    assert_eq!(group.tree_kind(), TreeKind::Sparse);
+    assert_eq!(group.hash_function(), HashFunction::Sha256);
+    assert_eq!(group.root(), to_vec(&client.get_merkle_root(&group_id)));
+    assert_eq!(group.members(), [4, 3].map(|seed| to_vec(&commitment(&env, seed))));
+
+    // proofs from the local tree match the contract's
+    let member = commitment(&env, 4);
+    assert_eq!(
+        group.sparse_proof(indexer.env(), &to_vec(&member)).map(|proof| proof.level),
+        Some(client.get_sparse_proof(&group_id, &member).level)
+    );
+    assert!(group.sparse_proof(indexer.env(), &to_vec(&commitment(&env, 2))).is_none());
+    assert!(group.proof(0).is_none());
+}
+
+#[test]
+fn test_replay_renounced_admin() {
+    let env = Env::default();
+    let (contract_id, client) = populated_group(&env);
+    client.renounce_admin(&GROUP_ID);
+
+    let mut indexer = Indexer::for_contract(contract_id.to_string().to_string());
+    indexer.sync(&mut EnvSource::new(&env)).unwrap();
+    assert_eq!(indexer.group(GROUP_ID).unwrap().admin(), None);
+}
+
+#[test]
+fn test_replay_archived_group() {
+    let env = Env::default();
+    let (contract_id, client) = populated_group(&env);
+    client.archive_group(&GROUP_ID);
+
+    let mut indexer = Indexer::for_contract(contract_id.to_string().to_string());
+    indexer.sync(&mut EnvSource::new(&env)).unwrap();
+    let group = indexer.group(GROUP_ID).unwrap();
+    assert!(group.is_archived());
+    assert_eq!(group.root(), to_vec(&client.get_merkle_root(&GROUP_ID)));
+
+    // the tree of an archived group cannot change anymore
+    let event = MemberRemoved {
+        group_id: GROUP_ID,
+        identity_commitment: commitment(&env, 1),
+        index: 0,
+        root: Bytes::from_slice(&env, &[0u8; 32]),
+    };
+    env.as_contract(&contract_id, || event.publish(&env));
+    let events = EnvSource::new(&env).events().unwrap();
+    assert!(matches!(
+        indexer.apply(events.last().unwrap()),
+        Err(Error::ArchivedGroup(GROUP_ID))
+    ));
+}
+
+#[test]
+fn test_replay_deleted_group() {
+    let env = Env::default();
+    let (contract_id, client) = populated_group(&env);
+    client.archive_group(&GROUP_ID);
+    // the first call leaves a member behind, so the group is not deleted yet
+    let members = [1, 5, 4].map(|seed| commitment(&env, seed));
+    assert_eq!(
+        client.delete_group(&GROUP_ID, &soroban_sdk::vec![&env, members[0].clone()]),
+        2
+    );
+
+    let mut indexer = Indexer::for_contract(contract_id.to_string().to_string());
+    indexer.sync(&mut EnvSource::new(&env)).unwrap();
+    assert!(indexer.group(GROUP_ID).is_some());
+
+    client.delete_group(
+        &GROUP_ID,
+        &soroban_sdk::vec![&env, members[1].clone(), members[2].clone()],
+    );
+    let mut indexer = Indexer::for_contract(contract_id.to_string().to_string());
+    indexer.sync(&mut EnvSource::new(&env)).unwrap();
+    assert!(indexer.group(GROUP_ID).is_none());
+    assert_eq!(indexer.groups().count(), 0);
+}
+
+#[test]
+fn test_replay_from_json_file() {
+    let env = Env::default();
+    let (_, client) = populated_group(&env);
+
+    let events = EnvSource::new(&env).events().unwrap();
+    let path = std::env::temp_dir().join("semaphore-indexer-events.json");
+    std::fs::write(&path, serde_json::to_string(&json_result(&events)).unwrap()).unwrap();
+
+    let mut indexer = Indexer::new();
+    indexer.sync(&mut JsonFileSource::new(&path)).unwrap();
+    assert_eq!(
+        indexer.group(GROUP_ID).unwrap().root(),
+        to_vec(&client.get_merkle_root(&GROUP_ID))
+    );
+}
+
+#[test]
+fn test_missing_event_is_a_root_mismatch() {
+    let env = Env::default();
+    populated_group(&env);
+
+    let mut events = EnvSource::new(&env).events().unwrap();
+    // drop the first member_added event
+    events.remove(2);
+
+    let mut indexer = Indexer::new();
+    let result = events.iter().try_for_each(|event| indexer.apply(event));
+    assert!(matches!(
+        result,
+        Err(Error::RootMismatch { group_id: GROUP_ID, .. })
+    ));
+}
+
+#[test]
+fn test_nullifiers() {
+    let env = Env::default();
+    let contract_id = env.register(ProofEventsContract, ());
+    let proof_validated = |nullifier: u8| ProofValidated {
+        group_id: GROUP_ID,
+        merkle_tree_depth: 16,
+        merkle_tree_root: Bytes::from_slice(&env, &[0u8; 32]),
+        nullifier: Bytes::from_slice(&env, &[nullifier; 32]),
+        message: Bytes::from_slice(&env, &[1u8; 32]),
+        scope: Bytes::from_slice(&env, &[2u8; 32]),
+    };
+    env.as_contract(&contract_id, || proof_validated(7).publish(&env));
+
+    let mut indexer = Indexer::new();
+    indexer.sync(&mut EnvSource::new(&env)).unwrap();
+    assert!(indexer.is_nullifier_used(GROUP_ID, &[7u8; 32]));
+    assert!(!indexer.is_nullifier_used(GROUP_ID, &[8u8; 32]));
+
+    // replaying the same spend again is reported
+    assert!(matches!(
+        indexer.sync(&mut EnvSource::new(&env)),
+        Err(Error::NullifierReused { group_id: GROUP_ID, .. })
+    ));
+}
+
+#[test]
+fn test_older_schema_is_rejected() {
+    let env = Env::default();
+    let contract_id = env.register(ProofEventsContract, ());
+    let event = MemberAdded {
+        group_id: GROUP_ID,
+        identity_commitment: commitment(&env, 1),
+        index: 0,
+        root: Bytes::from_slice(&env, &[0u8; 32]),
+    };
+    env.as_contract(&contract_id, || {
+        env.events()
+            .publish((Symbol::new(&env, MemberAdded::NAME), 0u32, GROUP_ID), event)
+    });
+
+    let mut indexer = Indexer::new();
+    assert!(matches!(
+        indexer.sync(&mut EnvSource::new(&env)),
+        Err(Error::UnsupportedSchema(0))
+    ));
+}
+
+fn json_result(events: &[RawEvent]) -> serde_json::Value {
+    serde_json::json!({ "latestLedger": 0, "events": events })
+}
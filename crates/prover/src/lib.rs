@@ -0,0 +1,255 @@
+//! Groth16 prover for Semaphore circuits.
+//!
+//! [`Prover`] loads a circom witness generator (`.wasm`) and a snarkjs proving
+//! key (`.zkey`) from local files and proves over BN254. Proofs are encoded
+//! with [`encoding`] from the `converter` crate, the layout the contracts take, and
+//! [`SemaphoreProver::prove_and_encode`] returns a `SemaphoreProof` ready to
+//! submit to `validate_proof`.
+
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use ark_bn254::{Bn254, Fr};
+use ark_circom::{index::NPIndex, read_zkey, CircomReduction, WitnessCalculator};
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_relations::gr1cs::SynthesisError;
+use ark_serialize::SerializationError;
+use ark_std::{rand::thread_rng, UniformRand};
+use identity::Identity;
+use num_bigint::BigInt;
+use semaphore::SemaphoreProof;
+use soroban_sdk::{BytesN, Env, Vec};
+use wasmer::Store;
+use witness::{CircuitInput, GroupRoot};
+
+use converter::encoding::encode_fr;
+pub use converter::{decode_proof, encode_proof, encoding};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    /// The `.zkey` file could not be parsed
+    Zkey(SerializationError),
+    /// The witness generator could not be loaded or run
+    Witness(String),
+    Synthesis(SynthesisError),
+    /// The circuit input could not be built
+    Input(witness::Error),
+    /// A circuit input signal is not a decimal integer
+    InvalidSignal {
+        name: &'static str,
+        value: String,
+    },
+    /// The circuit does not have the four public signals of a Semaphore circuit
+    MissingPublicSignals(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "io error: {err}"),
+            Error::Zkey(err) => write!(f, "invalid zkey: {err}"),
+            Error::Witness(err) => write!(f, "witness generation failed: {err}"),
+            Error::Synthesis(err) => write!(f, "proving failed: {err}"),
+            Error::Input(err) => write!(f, "{err}"),
+            Error::InvalidSignal { name, value } => {
+                write!(
+                    f,
+                    "circuit input {name} is not a decimal integer: {value:?}"
+                )
+            }
+            Error::MissingPublicSignals(count) => {
+                write!(
+                    f,
+                    "circuit has {count} public signals, a Semaphore circuit has 4"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<SerializationError> for Error {
+    fn from(err: SerializationError) -> Self {
+        Error::Zkey(err)
+    }
+}
+
+impl From<SynthesisError> for Error {
+    fn from(err: SynthesisError) -> Self {
+        Error::Synthesis(err)
+    }
+}
+
+impl From<witness::Error> for Error {
+    fn from(err: witness::Error) -> Self {
+        Error::Input(err)
+    }
+}
+
+/// Groth16 prover for one circom circuit
+pub struct Prover {
+    store: Store,
+    witness: WitnessCalculator,
+    proving_key: ProvingKey<Bn254>,
+    matrices: NPIndex<Fr>,
+}
+
+impl Prover {
+    /// Load the circuit's witness generator and proving key
+    pub fn load(wasm: impl AsRef<Path>, zkey: impl AsRef<Path>) -> Result<Self, Error> {
+        let (proving_key, matrices) = read_zkey(&mut File::open(zkey)?)?;
+        let mut store = Store::default();
+        let witness = WitnessCalculator::new(&mut store, wasm)
+            .map_err(|err| Error::Witness(err.to_string()))?;
+        Ok(Self {
+            store,
+            witness,
+            proving_key,
+            matrices,
+        })
+    }
+
+    pub fn verifying_key(&self) -> &VerifyingKey<Bn254> {
+        &self.proving_key.vk
+    }
+
+    /// Prove for the named circuit inputs, returning the proof and the public
+    /// signals (outputs first, then public inputs, as snarkjs orders them)
+    pub fn prove(
+        &mut self,
+        inputs: impl IntoIterator<Item = (String, std::vec::Vec<BigInt>)>,
+    ) -> Result<(Proof<Bn254>, std::vec::Vec<Fr>), Error> {
+        let assignment = self
+            .witness
+            .calculate_witness_element::<Fr, _>(&mut self.store, inputs, true)
+            .map_err(|err| Error::Witness(err.to_string()))?;
+
+        let mut rng = thread_rng();
+        let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
+            &self.proving_key,
+            Fr::rand(&mut rng),
+            Fr::rand(&mut rng),
+            &[
+                self.matrices.a.clone(),
+                self.matrices.b.clone(),
+                self.matrices.c.clone(),
+            ],
+            self.matrices.num_instance_variables,
+            self.matrices.num_constraints,
+            &assignment,
+        )?;
+        let public = assignment[1..self.matrices.num_instance_variables].to_vec();
+        Ok((proof, public))
+    }
+
+    /// Prove for an `input.json` built by the `witness` crate
+    pub fn prove_input(
+        &mut self,
+        input: &CircuitInput,
+    ) -> Result<(Proof<Bn254>, std::vec::Vec<Fr>), Error> {
+        let value = |name, value: &str| {
+            value.parse::<BigInt>().map_err(|_| Error::InvalidSignal {
+                name,
+                value: value.to_string(),
+            })
+        };
+        let siblings = input
+            .merkle_proof_siblings
+            .iter()
+            .map(|s| value("merkleProofSiblings", s))
+            .collect::<Result<_, _>>()?;
+        self.prove([
+            ("secret".into(), vec![value("secret", &input.secret)?]),
+            (
+                "merkleProofLength".into(),
+                vec![value("merkleProofLength", &input.merkle_proof_length)?],
+            ),
+            (
+                "merkleProofIndex".into(),
+                vec![value("merkleProofIndex", &input.merkle_proof_index)?],
+            ),
+            ("merkleProofSiblings".into(), siblings),
+            ("message".into(), vec![value("message", &input.message)?]),
+            ("scope".into(), vec![value("scope", &input.scope)?]),
+        ])
+    }
+
+    /// Check a proof against the circuit's verifying key
+    pub fn verify(&self, proof: &Proof<Bn254>, public: &[Fr]) -> Result<bool, Error> {
+        let key = prepare_verifying_key(self.verifying_key());
+        Ok(Groth16::<Bn254>::verify_proof(&key, proof, public)?)
+    }
+}
+
+/// Prover for a Semaphore circuit compiled for trees of depth `depth`
+pub struct SemaphoreProver {
+    pub prover: Prover,
+    pub depth: u32,
+}
+
+impl SemaphoreProver {
+    /// Load `semaphore-<depth>.wasm` / `.zkey` style artifacts
+    pub fn load(wasm: impl AsRef<Path>, zkey: impl AsRef<Path>, depth: u32) -> Result<Self, Error> {
+        Ok(Self {
+            prover: Prover::load(wasm, zkey)?,
+            depth,
+        })
+    }
+
+    /// Prove that `identity` is the member behind `proof` in the group with
+    /// root `group`, and encode the result for `validate_proof`. The group
+    /// tree must be hashed with Poseidon, as the circuit hashes it
+    pub fn prove_and_encode(
+        &mut self,
+        env: &Env,
+        identity: &Identity,
+        proof: &group::proof::Proof,
+        group: &GroupRoot,
+        message: &[u8; 32],
+        scope: &[u8; 32],
+    ) -> Result<SemaphoreProof, Error> {
+        let input = CircuitInput::new(env, identity, proof, group, message, scope, self.depth)?;
+        let (proof, public) = self.prover.prove_input(&input)?;
+        semaphore_proof(env, self.depth, &proof, &public, message, scope)
+    }
+}
+
+/// `SemaphoreProof` from a proof and its public signals
+/// `[merkle_root, nullifier, hash(message), hash(scope)]`; message and scope
+/// are the unhashed values, which the contract hashes itself
+pub fn semaphore_proof(
+    env: &Env,
+    depth: u32,
+    proof: &Proof<Bn254>,
+    public: &[Fr],
+    message: &[u8; 32],
+    scope: &[u8; 32],
+) -> Result<SemaphoreProof, Error> {
+    let [merkle_tree_root, nullifier, _, _] = public else {
+        return Err(Error::MissingPublicSignals(public.len()));
+    };
+
+    let mut points = Vec::new(env);
+    for word in encode_proof(proof) {
+        points.push_back(BytesN::from_array(env, &word));
+    }
+    Ok(SemaphoreProof {
+        merkle_tree_depth: depth,
+        merkle_tree_root: BytesN::from_array(env, &encode_fr(merkle_tree_root)),
+        nullifier: BytesN::from_array(env, &encode_fr(nullifier)),
+        message: BytesN::from_array(env, message),
+        scope: BytesN::from_array(env, scope),
+        points,
+    })
+}
+
+mod test;
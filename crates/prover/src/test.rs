@@ -0,0 +1,100 @@
+#![cfg(test)]
+
+use super::*;
+
+/// Prover for `test-vectors/mycircuit.circom`, `c <== a * b` with `c` public
+fn multiplier() -> Prover {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-vectors");
+    Prover::load(dir.join("mycircuit.wasm"), dir.join("test.zkey")).unwrap()
+}
+
+fn inputs(a: u32, b: u32) -> [(String, std::vec::Vec<BigInt>); 2] {
+    [("a".into(), vec![a.into()]), ("b".into(), vec![b.into()])]
+}
+
+#[test]
+fn test_prove() {
+    let mut prover = multiplier();
+    let (proof, public) = prover.prove(inputs(3, 11)).unwrap();
+    assert_eq!(public, [Fr::from(33u64)]);
+    assert!(prover.verify(&proof, &public).unwrap());
+    assert!(!prover.verify(&proof, &[Fr::from(34u64)]).unwrap());
+
+    // proofs are randomized
+    let (other, _) = prover.prove(inputs(3, 11)).unwrap();
+    assert_ne!(encode_proof(&proof), encode_proof(&other));
+}
+
+#[test]
+fn test_invalid_wasm() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-vectors");
+    let result = Prover::load(dir.join("test.zkey"), dir.join("test.zkey"));
+    assert!(matches!(result, Err(Error::Witness(_))));
+}
+
+#[test]
+fn test_encoding_round_trip() {
+    let mut prover = multiplier();
+    let (proof, public) = prover.prove(inputs(5, 7)).unwrap();
+
+    let words = encode_proof(&proof);
+    let decoded = decode_proof(&words).unwrap();
+    assert_eq!(decoded, proof);
+    assert!(prover.verify(&decoded, &public).unwrap());
+
+    // G2 coordinates are stored imaginary part first
+    assert_eq!(words[2], encoding::encode_fq(&proof.b.x.c1));
+    assert_eq!(words[3], encoding::encode_fq(&proof.b.x.c0));
+    // swapping them gives a point off the curve
+    let mut swapped = words;
+    swapped.swap(2, 3);
+    assert!(decode_proof(&swapped).is_err());
+}
+
+#[test]
+fn test_semaphore_proof() {
+    let env = Env::default();
+    let mut prover = multiplier();
+    let (proof, _) = prover.prove(inputs(3, 11)).unwrap();
+    let message = [1u8; 32];
+    let scope = [2u8; 32];
+    let public = [1u64, 2, 3, 4].map(Fr::from);
+
+    let encoded = semaphore_proof(&env, 16, &proof, &public, &message, &scope).unwrap();
+    assert_eq!(encoded.merkle_tree_depth, 16);
+    assert_eq!(encoded.merkle_tree_root.to_array()[31], 1);
+    assert_eq!(encoded.nullifier.to_array()[31], 2);
+    assert_eq!(encoded.message.to_array(), message);
+    assert_eq!(encoded.scope.to_array(), scope);
+    assert_eq!(
+        encoded
+            .points
+            .iter()
+            .map(|point| point.to_array())
+            .collect::<std::vec::Vec<_>>(),
+        encode_proof(&proof)
+    );
+
+    assert!(matches!(
+        semaphore_proof(&env, 16, &proof, &[Fr::from(33u64)], &message, &scope),
+        Err(Error::MissingPublicSignals(1))
+    ));
+}
+
+#[test]
+fn test_invalid_signal() {
+    let mut prover = multiplier();
+    let input = CircuitInput {
+        secret: "1".into(),
+        merkle_proof_length: "1".into(),
+        merkle_proof_index: "0".into(),
+        merkle_proof_siblings: vec!["0".into(), "0x01".into()],
+        message: "2".into(),
+        scope: "3".into(),
+    };
+    let err = prover.prove_input(&input).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "circuit input merkleProofSiblings is not a decimal integer: \"0x01\""
+    );
+}
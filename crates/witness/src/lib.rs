@@ -0,0 +1,171 @@
+//! Inputs for the Semaphore v4 circom circuit.
+//!
+//! [`CircuitInput::new`] turns an identity, a Merkle proof from
+//! `group::get_proof`, a message and a scope into the `input.json` layout the
+//! circuit's witness generator reads. The proof is first checked against the
+//! group root, so an out of date or foreign proof is rejected before any
+//! witness is computed.
+//!
+//! The circuit recomputes the root from the identity commitment and the
+//! siblings with Poseidon, so only incremental groups created with
+//! `HashFunction::Poseidon` can be proven: their leaves are the commitments
+//! themselves and their nodes are combined with the circuit's hash, so the
+//! root a proof commits to is the group's root.
+
+use std::fmt;
+
+use ark_ff::PrimeField;
+use group::{
+    datatypes::HashFunction,
+    proof::{Branch, Proof},
+};
+use identity::{field_from_bytes, Fq, Identity};
+use serde::{Deserialize, Serialize};
+use soroban_sdk::{Bytes, Env};
+
+/// Deepest tree the Semaphore circuits are compiled for
+pub const MAX_DEPTH: u32 = 32;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The group tree is not hashed with Poseidon, so the circuit cannot
+    /// recompute its root
+    UnsupportedHash(HashFunction),
+    /// The root computed from the identity and proof is not the group root
+    RootMismatch { expected: String, computed: String },
+    /// The proof has more levels than the circuit
+    ProofTooLong { length: u32, depth: u32 },
+    /// Circuit depth outside `1..=MAX_DEPTH`
+    InvalidDepth(u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnsupportedHash(hash) => {
+                write!(f, "group tree is hashed with {hash:?}, the circuit needs Poseidon")
+            }
+            Error::RootMismatch { expected, computed } => write!(
+                f,
+                "root mismatch: group root is {expected}, proof computes {computed}"
+            ),
+            Error::ProofTooLong { length, depth } => {
+                write!(f, "proof has {length} levels, circuit depth is {depth}")
+            }
+            Error::InvalidDepth(depth) => {
+                write!(f, "circuit depth {depth} is not in 1..={MAX_DEPTH}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Root of a group and the hash function of its tree, as `get_group` returns
+/// them
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupRoot {
+    pub root: Bytes,
+    pub hash: HashFunction,
+}
+
+/// `input.json` of the Semaphore circuit, every value a decimal string
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CircuitInput {
+    pub secret: String,
+    pub merkle_proof_length: String,
+    pub merkle_proof_index: String,
+    /// Padded with zeros to the circuit depth
+    pub merkle_proof_siblings: Vec<String>,
+    pub message: String,
+    pub scope: String,
+}
+
+impl CircuitInput {
+    /// Build the input for a proof of membership of `identity` in the group
+    /// with root `group`, for a circuit compiled with depth `depth`
+    pub fn new(
+        env: &Env,
+        identity: &Identity,
+        proof: &Proof,
+        group: &GroupRoot,
+        message: &[u8; 32],
+        scope: &[u8; 32],
+        depth: u32,
+    ) -> Result<Self, Error> {
+        if depth == 0 || depth > MAX_DEPTH {
+            return Err(Error::InvalidDepth(depth));
+        }
+        if proof.0.len() > depth {
+            return Err(Error::ProofTooLong {
+                length: proof.0.len(),
+                depth,
+            });
+        }
+
+        if group.hash != HashFunction::Poseidon {
+            return Err(Error::UnsupportedHash(group.hash));
+        }
+
+        // the leaf of a Poseidon tree is the commitment itself
+        let commitment = identity.commitment_bytes(env);
+        let computed = proof.root(env, &group.hash, &commitment);
+        if computed != group.root {
+            return Err(Error::RootMismatch {
+                expected: hex::encode(to_vec(&group.root)),
+                computed: hex::encode(to_vec(&computed)),
+            });
+        }
+
+        let mut siblings = Vec::with_capacity(depth as usize);
+        let mut index = 0u64;
+        for (level, branch) in proof.0.iter().enumerate() {
+            let sibling = match branch {
+                // the sibling is hashed on the left, so this node is a right child
+                Branch::Left(sibling) => {
+                    index |= 1 << level;
+                    sibling
+                }
+                Branch::Right(sibling) => sibling,
+            };
+            siblings.push(decimal(&field_from_bytes(&to_vec(&sibling))));
+        }
+        siblings.resize(depth as usize, "0".into());
+
+        Ok(Self {
+            secret: identity.secret_scalar().into_bigint().to_string(),
+            merkle_proof_length: proof.0.len().to_string(),
+            merkle_proof_index: index.to_string(),
+            merkle_proof_siblings: siblings,
+            message: decimal(&hash(env, message)),
+            scope: decimal(&hash(env, scope)),
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+/// Semaphore's `hash` for messages and scopes: keccak256 shifted right by 8
+/// bits so the result fits in the field
+pub fn hash(env: &Env, value: &[u8; 32]) -> Fq {
+    let digest = env
+        .crypto()
+        .keccak256(&Bytes::from_array(env, value))
+        .to_array();
+    let mut shifted = [0u8; 32];
+    shifted[1..].copy_from_slice(&digest[..31]);
+    field_from_bytes(&shifted)
+}
+
+fn decimal(value: &Fq) -> String {
+    value.into_bigint().to_string()
+}
+
+fn to_vec(bytes: &Bytes) -> Vec<u8> {
+    bytes.iter().collect()
+}
+
+mod test;
@@ -0,0 +1,216 @@
+#![cfg(test)]
+
+use super::*;
+use group::{
+    imt::{leaf, MerkleTree},
+    poseidon, DEFAULT_DEPTH, EMPTY_LEAF,
+};
+
+const MESSAGE: [u8; 32] = [1u8; 32];
+const SCOPE: [u8; 32] = [2u8; 32];
+
+/// Poseidon group tree holding the commitments of `identities` in order
+fn tree(env: &Env, identities: &[Identity]) -> MerkleTree {
+    tree_with_hash(env, identities, HashFunction::Poseidon)
+}
+
+fn tree_with_hash(env: &Env, identities: &[Identity], hash: HashFunction) -> MerkleTree {
+    env.budget().reset_unlimited();
+    let empty_leaf = Bytes::from_slice(env, &EMPTY_LEAF);
+    let mut tree = MerkleTree::with_hash(env, DEFAULT_DEPTH, empty_leaf, hash);
+    for (index, identity) in identities.iter().enumerate() {
+        let commitment = identity.commitment_bytes(env);
+        tree.add_leaf(env, index, leaf(env, hash, &commitment).unwrap());
+    }
+    tree
+}
+
+fn group_root(tree: &MerkleTree) -> GroupRoot {
+    GroupRoot {
+        root: tree.get_root(),
+        hash: tree.hash_function(),
+    }
+}
+
+fn identities() -> std::vec::Vec<Identity> {
+    (0..4u8).map(|seed| Identity::new(&[seed])).collect()
+}
+
+#[test]
+fn test_circuit_input() {
+    let env = Env::default();
+    let identities = identities();
+    let tree = tree(&env, &identities);
+    let proof = tree.proof(2).unwrap();
+
+    let input = CircuitInput::new(
+        &env,
+        &identities[2],
+        &proof,
+        &group_root(&tree),
+        &MESSAGE,
+        &SCOPE,
+        16,
+    )
+    .unwrap();
+
+    assert_eq!(input.secret, identities[2].secret_scalar().to_string());
+    assert_eq!(input.merkle_proof_length, DEFAULT_DEPTH.to_string());
+    assert_eq!(input.merkle_proof_index, "2");
+    assert_eq!(input.merkle_proof_siblings.len(), 16);
+    assert!(input.merkle_proof_siblings[DEFAULT_DEPTH as usize..]
+        .iter()
+        .all(|sibling| sibling == "0"));
+    // the first sibling is the leaf at index 3
+    let Branch::Right(sibling) = proof.0.get(0).unwrap() else {
+        panic!("leaf 2 is a left child");
+    };
+    assert_eq!(
+        input.merkle_proof_siblings[0],
+        field_from_bytes(&to_vec(&sibling)).to_string()
+    );
+    assert_eq!(input.message, hash(&env, &MESSAGE).to_string());
+    assert_eq!(input.scope, hash(&env, &SCOPE).to_string());
+
+    let json: serde_json::Value = serde_json::from_str(&input.to_json()).unwrap();
+    let mut keys: std::vec::Vec<_> = json.as_object().unwrap().keys().cloned().collect();
+    keys.sort();
+    assert_eq!(
+        keys,
+        [
+            "merkleProofIndex",
+            "merkleProofLength",
+            "merkleProofSiblings",
+            "message",
+            "scope",
+            "secret"
+        ]
+    );
+}
+
+#[test]
+fn test_circuit_computes_group_root() {
+    let env = Env::default();
+    let identities = identities();
+    let tree = tree(&env, &identities);
+    let input = CircuitInput::new(
+        &env,
+        &identities[1],
+        &tree.proof(1).unwrap(),
+        &group_root(&tree),
+        &MESSAGE,
+        &SCOPE,
+        16,
+    )
+    .unwrap();
+
+    // the root as the circuit's BinaryMerkleRoot recomputes it from the
+    // commitment, the index bits and the siblings
+    let length: usize = input.merkle_proof_length.parse().unwrap();
+    let index: u64 = input.merkle_proof_index.parse().unwrap();
+    let mut node = identities[1].commitment();
+    for (level, sibling) in input.merkle_proof_siblings[..length].iter().enumerate() {
+        let sibling: Fq = sibling.parse().unwrap();
+        node = match index >> level & 1 {
+            0 => poseidon::hash2(node, sibling),
+            _ => poseidon::hash2(sibling, node),
+        };
+    }
+    assert_eq!(
+        Bytes::from_array(&env, &identity::field_to_bytes(&node)),
+        tree.get_root()
+    );
+}
+
+#[test]
+fn test_group_hash_function() {
+    let env = Env::default();
+    let identities = identities();
+    for hash in [HashFunction::Keccak256, HashFunction::Sha256] {
+        let tree = tree_with_hash(&env, &identities, hash);
+        let result = CircuitInput::new(
+            &env,
+            &identities[1],
+            &tree.proof(1).unwrap(),
+            &group_root(&tree),
+            &MESSAGE,
+            &SCOPE,
+            16,
+        );
+        assert_eq!(result, Err(Error::UnsupportedHash(hash)));
+    }
+}
+
+#[test]
+fn test_hash() {
+    let env = Env::default();
+    // keccak256 of 32 zero bytes, shifted right by 8 bits
+    assert_eq!(
+        identity::field_to_bytes(&hash(&env, &[0u8; 32])),
+        [
+            0x00, 0x29, 0x0d, 0xec, 0xd9, 0x54, 0x8b, 0x62, 0xa8, 0xd6, 0x03, 0x45, 0xa9, 0x88,
+            0x38, 0x6f, 0xc8, 0x4b, 0xa6, 0xbc, 0x95, 0x48, 0x40, 0x08, 0xf6, 0x36, 0x2f, 0x93,
+            0x16, 0x0e, 0xf3, 0xe5
+        ]
+    );
+}
+
+#[test]
+fn test_root_mismatch() {
+    let env = Env::default();
+    let identities = identities();
+    let tree = tree(&env, &identities);
+    let root = group_root(&tree);
+
+    // proof for another member
+    let result = CircuitInput::new(
+        &env,
+        &identities[2],
+        &tree.proof(1).unwrap(),
+        &root,
+        &MESSAGE,
+        &SCOPE,
+        16,
+    );
+    assert!(matches!(result, Err(Error::RootMismatch { .. })));
+
+    // identity that is not in the group
+    let result = CircuitInput::new(
+        &env,
+        &Identity::new(b"outsider"),
+        &tree.proof(2).unwrap(),
+        &root,
+        &MESSAGE,
+        &SCOPE,
+        16,
+    );
+    assert!(matches!(result, Err(Error::RootMismatch { .. })));
+}
+
+#[test]
+fn test_depth() {
+    let env = Env::default();
+    let identities = identities();
+    let tree = tree(&env, &identities);
+    let proof = tree.proof(0).unwrap();
+    let root = group_root(&tree);
+    let input =
+        |depth| CircuitInput::new(&env, &identities[0], &proof, &root, &MESSAGE, &SCOPE, depth);
+
+    assert_eq!(
+        input(DEFAULT_DEPTH - 1),
+        Err(Error::ProofTooLong {
+            length: DEFAULT_DEPTH,
+            depth: DEFAULT_DEPTH - 1
+        })
+    );
+    assert_eq!(input(0), Err(Error::InvalidDepth(0)));
+    assert_eq!(
+        input(MAX_DEPTH + 1),
+        Err(Error::InvalidDepth(MAX_DEPTH + 1))
+    );
+    assert_eq!(
+        input(DEFAULT_DEPTH).unwrap().merkle_proof_siblings.len(),
+        DEFAULT_DEPTH as usize
+    );
+}